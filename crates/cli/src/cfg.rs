@@ -1,18 +1,30 @@
-use anyhow::{Context, Result};
-use clap::{Args, Parser, Subcommand};
+use anyhow::{bail, ensure, Context, Result};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use config::{builder::DefaultState, ConfigBuilder, File};
 use dirs::home_dir;
 use dotenvy::dotenv;
 use log::{error, warn};
+use prism_common::{digest::Digest, keys::VerifyingKey};
 use prism_errors::{DataAvailabilityError, GeneralError};
-use prism_prover::webserver::WebServerConfig;
-use prism_storage::redis::RedisConfig;
+use prism_lightclient::{CommitmentAttestor, FullNodeClient, TrustedCheckpoint};
+use prism_prover::{
+    prover::{epoch_policy::EpochPolicy, mempool::MempoolConfig, PROVING_QUEUE_SIZE},
+    webserver::WebServerConfig,
+};
+use prism_proving::ProvingBackendConfig;
+use prism_storage::{
+    inmemory::InMemoryDatabase,
+    redis::RedisConfig,
+    rocksdb::{RocksDBConfig, RocksDBConnection},
+    Database, RedisConnection,
+};
 use serde::{Deserialize, Serialize};
-use std::{fs, path::Path, sync::Arc};
+use std::{fs, path::Path, sync::Arc, time::Duration};
 
 use prism_da::{
     celestia::{CelestiaConfig, CelestiaConnection},
     consts::{DA_RETRY_COUNT, DA_RETRY_INTERVAL},
+    devnet::{DevnetConfig, DevnetConnection},
     memory::InMemoryDataAvailabilityLayer,
     DataAvailabilityLayer,
 };
@@ -33,6 +45,15 @@ pub struct CommandArgs {
     #[arg(short = 'r', long)]
     redis_client: Option<String>,
 
+    /// Storage backend used by the node. Light clients default to RocksDB under
+    /// `~/.prism/lightclient`, all other nodes to Redis
+    #[arg(long, value_enum)]
+    db_type: Option<StorageBackend>,
+
+    /// Path to the RocksDB data directory
+    #[arg(long)]
+    rocksdb_path: Option<String>,
+
     #[arg(long)]
     verifying_key: Option<String>,
 
@@ -44,6 +65,9 @@ pub struct CommandArgs {
 
     #[command(flatten)]
     webserver: WebserverArgs,
+
+    #[command(flatten)]
+    prover: ProverArgs,
 }
 
 #[derive(Parser, Clone, Debug, Deserialize)]
@@ -68,6 +92,10 @@ struct CelestiaArgs {
     #[arg(long)]
     operation_namespace_id: Option<String>,
 
+    /// Celestia Checkpoint Namespace ID
+    #[arg(long)]
+    checkpoint_namespace_id: Option<String>,
+
     // Height to start searching the DA layer for SNARKs on
     #[arg(short = 's', long)]
     celestia_start_height: Option<u64>,
@@ -88,15 +116,144 @@ struct WebserverArgs {
     port: Option<u16>,
 }
 
+#[derive(Args, Deserialize, Clone, Debug)]
+#[group(required = false, multiple = true)]
+struct ProverArgs {
+    /// Maximum number of executed epochs waiting to be proven
+    #[arg(long)]
+    proving_queue_size: Option<usize>,
+
+    /// Number of attempts to connect or submit to the DA layer before giving up
+    #[arg(long)]
+    da_retry_count: Option<u64>,
+
+    /// Seconds before the first retry of a failed DA submission, doubled on every further retry
+    #[arg(long)]
+    da_retry_interval_secs: Option<u64>,
+
+    /// Maximum number of transactions waiting to be applied in an epoch
+    #[arg(long)]
+    mempool_max_transactions: Option<usize>,
+
+    /// Maximum number of pending transactions for a single account
+    #[arg(long)]
+    mempool_max_transactions_per_account: Option<usize>,
+
+    /// Maximum number of transactions included in a single epoch
+    #[arg(long)]
+    epoch_max_transactions: Option<usize>,
+
+    /// Minimum number of DA heights between two epochs
+    #[arg(long)]
+    epoch_min_da_heights: Option<u64>,
+
+    /// Number of DA heights after the last epoch at which buffered transactions are finalized
+    #[arg(long)]
+    epoch_max_da_heights: Option<u64>,
+
+    /// Estimated zkVM cycle budget of a single epoch
+    #[arg(long)]
+    epoch_max_cycles: Option<u64>,
+
+    /// Number of DA heights without an epoch after which an empty epoch is finalized
+    #[arg(long)]
+    epoch_heartbeat_interval: Option<u64>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub webserver: Option<WebServerConfig>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub celestia_config: Option<CelestiaConfig>,
+    /// Local DA devnet to connect to, see `prism-da-devnet`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub devnet_config: Option<DevnetConfig>,
     pub da_layer: DALayerOption,
+    /// Storage backend, see [`initialize_db`] and [`initialize_light_client_db`] for the
+    /// defaults.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub db_type: Option<StorageBackend>,
     pub redis_config: Option<RedisConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rocksdb_config: Option<RocksDBConfig>,
     pub verifying_key: Option<String>,
+    #[serde(default)]
+    pub proving_backend: ProvingBackendConfig,
+    /// Number of epochs aggregated into each checkpoint created by the prover.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checkpoint_interval: Option<u64>,
+    /// State a fresh light client starts verifying from instead of the start height.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trusted_checkpoint: Option<TrustedCheckpointConfig>,
+    /// Maximum number of executed epochs waiting to be proven.
+    #[serde(default = "default_proving_queue_size")]
+    pub proving_queue_size: usize,
+    /// Number of attempts to connect or submit to the DA layer before giving up.
+    #[serde(default = "default_da_retry_count")]
+    pub da_retry_count: u64,
+    /// Seconds before the first retry of a failed DA submission, doubled on every further retry.
+    #[serde(default = "default_da_retry_interval_secs")]
+    pub da_retry_interval_secs: u64,
+    /// Limits of the pool of transactions waiting to be applied in an epoch.
+    #[serde(default)]
+    pub mempool: MempoolConfig,
+    /// Decides when the prover finalizes new epochs and how many transactions they include.
+    #[serde(default)]
+    pub epoch_policy: EpochPolicy,
+}
+
+impl Config {
+    pub fn da_retry_interval(&self) -> Duration {
+        Duration::from_secs(self.da_retry_interval_secs)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TrustedCheckpointConfig {
+    /// Number of epochs before the checkpoint.
+    pub epoch: u64,
+    /// Hex encoded commitment after those epochs.
+    pub commitment: String,
+    /// DA height the last epoch before the checkpoint was posted at.
+    pub da_height: u64,
+    /// Full nodes the checkpoint is cross-checked against, at least one has to confirm it.
+    pub full_nodes: Vec<FullNodeConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FullNodeConfig {
+    pub url: String,
+    /// Key the full node signs its responses with.
+    pub verifying_key: String,
+}
+
+impl TrustedCheckpointConfig {
+    pub fn checkpoint(&self) -> Result<TrustedCheckpoint> {
+        Ok(TrustedCheckpoint {
+            epoch: self.epoch,
+            commitment: Digest::from_hex(&self.commitment)
+                .context("Invalid trusted checkpoint commitment")?,
+            da_height: self.da_height,
+        })
+    }
+
+    pub fn attestors(&self) -> Result<Vec<Arc<dyn CommitmentAttestor>>> {
+        self.full_nodes
+            .iter()
+            .map(|node| {
+                let VerifyingKey::Ed25519(key) =
+                    VerifyingKey::try_from(node.verifying_key.clone())?
+                else {
+                    bail!(
+                        "verifying key of full node {} is not an ed25519 key",
+                        node.url
+                    );
+                };
+                Ok(Arc::new(FullNodeClient::new(&node.url, key)) as Arc<dyn CommitmentAttestor>)
+            })
+            .collect()
+    }
 }
 
 impl Default for Config {
@@ -104,18 +261,63 @@ impl Default for Config {
         Config {
             webserver: Some(WebServerConfig::default()),
             celestia_config: Some(CelestiaConfig::default()),
+            devnet_config: None,
             da_layer: DALayerOption::default(),
+            db_type: None,
             redis_config: Some(RedisConfig::default()),
+            rocksdb_config: Some(RocksDBConfig::new(&default_rocksdb_path())),
             verifying_key: None,
+            proving_backend: ProvingBackendConfig::default(),
+            checkpoint_interval: None,
+            trusted_checkpoint: None,
+            proving_queue_size: default_proving_queue_size(),
+            da_retry_count: default_da_retry_count(),
+            da_retry_interval_secs: default_da_retry_interval_secs(),
+            mempool: MempoolConfig::default(),
+            epoch_policy: EpochPolicy::default(),
         }
     }
 }
 
+fn default_proving_queue_size() -> usize {
+    PROVING_QUEUE_SIZE
+}
+
+fn default_da_retry_count() -> u64 {
+    DA_RETRY_COUNT
+}
+
+fn default_da_retry_interval_secs() -> u64 {
+    DA_RETRY_INTERVAL.as_secs()
+}
+
+fn default_rocksdb_path() -> String {
+    home_dir()
+        .map(|path| format!("{}/.prism/data", path.to_string_lossy()))
+        .unwrap_or_else(|| ".prism/data".to_string())
+}
+
+fn default_light_client_rocksdb_path() -> String {
+    home_dir()
+        .map(|path| format!("{}/.prism/lightclient", path.to_string_lossy()))
+        .unwrap_or_else(|| ".prism/lightclient".to_string())
+}
+
 #[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum DALayerOption {
     #[default]
     Celestia,
     InMemory,
+    Devnet,
+}
+
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize, ValueEnum)]
+pub enum StorageBackend {
+    #[default]
+    Redis,
+    #[value(name = "rocksdb")]
+    RocksDB,
+    InMemory,
 }
 
 pub fn load_config(args: CommandArgs) -> Result<Config> {
@@ -135,6 +337,14 @@ pub fn load_config(args: CommandArgs) -> Result<Config> {
         config_source.try_deserialize().context("Failed to deserialize config file")?;
 
     let final_config = apply_command_line_args(loaded_config, args);
+    ensure!(
+        final_config.proving_queue_size > 0,
+        "proving queue size must be at least 1"
+    );
+    ensure!(
+        final_config.da_retry_count > 0,
+        "DA retry count must be at least 1"
+    );
 
     if final_config.verifying_key.is_none() {
         warn!("prover's public key was not provided. this is not recommended and epoch signatures will not be verified.");
@@ -171,6 +381,10 @@ fn apply_command_line_args(config: Config, args: CommandArgs) -> Config {
     let webserver_config = &config.webserver.unwrap_or_default();
     let redis_config = &config.redis_config.unwrap_or_default();
     let celestia_config = &config.celestia_config.unwrap_or_default();
+    let rocksdb_config =
+        &config.rocksdb_config.unwrap_or_else(|| RocksDBConfig::new(&default_rocksdb_path()));
+    let mempool = &config.mempool;
+    let epoch_policy = &config.epoch_policy;
 
     Config {
         webserver: Some(WebServerConfig {
@@ -178,9 +392,13 @@ fn apply_command_line_args(config: Config, args: CommandArgs) -> Config {
             host: args.webserver.host.unwrap_or(webserver_config.host.clone()),
             port: args.webserver.port.unwrap_or(webserver_config.port),
         }),
+        db_type: args.db_type.or(config.db_type),
         redis_config: Some(RedisConfig {
             connection_string: args.redis_client.unwrap_or(redis_config.connection_string.clone()),
         }),
+        rocksdb_config: Some(RocksDBConfig {
+            path: args.rocksdb_path.unwrap_or(rocksdb_config.path.clone()),
+        }),
         celestia_config: Some(CelestiaConfig {
             connection_string: args
                 .celestia
@@ -198,9 +416,51 @@ fn apply_command_line_args(config: Config, args: CommandArgs) -> Config {
                 .celestia
                 .operation_namespace_id
                 .unwrap_or(celestia_config.operation_namespace_id.clone()),
+            checkpoint_namespace_id: args
+                .celestia
+                .checkpoint_namespace_id
+                .unwrap_or(celestia_config.checkpoint_namespace_id.clone()),
+            reconnect_backoff_ms: celestia_config.reconnect_backoff_ms,
+            max_reconnect_backoff_ms: celestia_config.max_reconnect_backoff_ms,
+            auth_token: celestia_config.auth_token.clone(),
+            epoch_submission: celestia_config.epoch_submission.clone(),
+            transaction_submission: celestia_config.transaction_submission.clone(),
         }),
+        devnet_config: config.devnet_config,
         da_layer: config.da_layer,
         verifying_key: args.verifying_key.or(config.verifying_key),
+        proving_backend: config.proving_backend,
+        checkpoint_interval: config.checkpoint_interval,
+        trusted_checkpoint: config.trusted_checkpoint,
+        proving_queue_size: args.prover.proving_queue_size.unwrap_or(config.proving_queue_size),
+        da_retry_count: args.prover.da_retry_count.unwrap_or(config.da_retry_count),
+        da_retry_interval_secs: args
+            .prover
+            .da_retry_interval_secs
+            .unwrap_or(config.da_retry_interval_secs),
+        mempool: MempoolConfig {
+            max_transactions: args
+                .prover
+                .mempool_max_transactions
+                .unwrap_or(mempool.max_transactions),
+            max_transactions_per_account: args
+                .prover
+                .mempool_max_transactions_per_account
+                .unwrap_or(mempool.max_transactions_per_account),
+        },
+        epoch_policy: EpochPolicy {
+            max_transactions: args
+                .prover
+                .epoch_max_transactions
+                .unwrap_or(epoch_policy.max_transactions),
+            min_da_heights: args.prover.epoch_min_da_heights.unwrap_or(epoch_policy.min_da_heights),
+            max_da_heights: args.prover.epoch_max_da_heights.unwrap_or(epoch_policy.max_da_heights),
+            max_cycles: args.prover.epoch_max_cycles.unwrap_or(epoch_policy.max_cycles),
+            heartbeat_interval: args
+                .prover
+                .epoch_heartbeat_interval
+                .or(epoch_policy.heartbeat_interval),
+        },
     }
 }
 
@@ -212,19 +472,19 @@ pub async fn initialize_da_layer(
             let celestia_conf =
                 config.celestia_config.clone().context("Celestia configuration not found")?;
 
-            for attempt in 1..=DA_RETRY_COUNT {
-                match CelestiaConnection::new(&celestia_conf, None).await {
+            for attempt in 1..=config.da_retry_count {
+                match CelestiaConnection::new(&celestia_conf).await {
                     Ok(da) => return Ok(Arc::new(da) as Arc<dyn DataAvailabilityLayer + 'static>),
                     Err(e) => {
-                        if attempt == DA_RETRY_COUNT {
+                        if attempt == config.da_retry_count {
                             return Err(DataAvailabilityError::NetworkError(format!(
                                 "failed to connect to celestia node after {} attempts: {}",
-                                DA_RETRY_COUNT, e
+                                config.da_retry_count, e
                             ))
                             .into());
                         }
-                        error!("Attempt {} to connect to celestia node failed: {}. Retrying in {} seconds...", attempt, e, DA_RETRY_INTERVAL.as_secs());
-                        tokio::time::sleep(DA_RETRY_INTERVAL).await;
+                        error!("Attempt {} to connect to celestia node failed: {}. Retrying in {} seconds...", attempt, e, config.da_retry_interval_secs);
+                        tokio::time::sleep(config.da_retry_interval()).await;
                     }
                 }
             }
@@ -234,5 +494,45 @@ pub async fn initialize_da_layer(
             let (da_layer, _height_rx, _block_rx) = InMemoryDataAvailabilityLayer::new(30);
             Ok(Arc::new(da_layer) as Arc<dyn DataAvailabilityLayer + 'static>)
         }
+        DALayerOption::Devnet => {
+            let devnet_conf = config.devnet_config.clone().unwrap_or_default();
+            Ok(Arc::new(DevnetConnection::new(&devnet_conf))
+                as Arc<dyn DataAvailabilityLayer + 'static>)
+        }
+    }
+}
+
+/// Opens the configured database, which is Redis unless set otherwise.
+pub fn initialize_db(config: &Config) -> Result<Arc<Box<dyn Database>>> {
+    match config.db_type.clone().unwrap_or_default() {
+        StorageBackend::Redis => {
+            let redis_config =
+                config.redis_config.clone().context("Redis configuration not found")?;
+            let db = RedisConnection::new(&redis_config).context("Failed to connect to redis")?;
+            Ok(Arc::new(Box::new(db) as Box<dyn Database>))
+        }
+        StorageBackend::RocksDB => {
+            let rocksdb_config =
+                config.rocksdb_config.clone().context("RocksDB configuration not found")?;
+            open_rocksdb(&rocksdb_config.path)
+        }
+        StorageBackend::InMemory => Ok(Arc::new(
+            Box::new(InMemoryDatabase::new()) as Box<dyn Database>
+        )),
     }
 }
+
+/// Opens the database of a light client. Unless a backend is configured, the state is kept in
+/// an embedded RocksDB under `~/.prism/lightclient`, so no Redis server is needed.
+pub fn initialize_light_client_db(config: &Config) -> Result<Arc<Box<dyn Database>>> {
+    match config.db_type {
+        Some(_) => initialize_db(config),
+        None => open_rocksdb(&default_light_client_rocksdb_path()),
+    }
+}
+
+fn open_rocksdb(path: &str) -> Result<Arc<Box<dyn Database>>> {
+    fs::create_dir_all(path).context("Failed to create RocksDB data directory")?;
+    let db = RocksDBConnection::new(path).context("Failed to open RocksDB database")?;
+    Ok(Arc::new(Box::new(db) as Box<dyn Database>))
+}
@@ -1,7 +1,9 @@
 mod cfg;
 mod node_types;
 
-use cfg::{initialize_da_layer, load_config, Cli, Commands};
+use cfg::{
+    initialize_da_layer, initialize_db, initialize_light_client_db, load_config, Cli, Commands,
+};
 use clap::Parser;
 use keystore_rs::{KeyChain, KeyStore, KeyStoreType};
 use prism_common::keys::VerifyingKey;
@@ -9,7 +11,6 @@ use prism_common::keys::VerifyingKey;
 use node_types::NodeType;
 use prism_lightclient::LightClient;
 use prism_prover::Prover;
-use prism_storage::RedisConnection;
 use std::sync::Arc;
 
 #[macro_use]
@@ -30,6 +31,9 @@ async fn main() -> std::io::Result<()> {
                 .await
                 .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
 
+            let db = initialize_light_client_db(&config)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+
             let celestia_config = config.celestia_config.ok_or_else(|| {
                 std::io::Error::new(
                     std::io::ErrorKind::NotFound,
@@ -37,6 +41,13 @@ async fn main() -> std::io::Result<()> {
                 )
             })?;
 
+            let webserver = config.webserver.unwrap_or_default();
+            let light_client_webserver = prism_lightclient::webserver::WebServerConfig {
+                enabled: webserver.enabled,
+                host: webserver.host,
+                port: webserver.port,
+            };
+
             let prover_vk = config.verifying_key.and_then(|s| s.try_into().ok()).and_then(
                 |vk: VerifyingKey| match vk {
                     VerifyingKey::Ed25519(key) => Some(key),
@@ -44,7 +55,35 @@ async fn main() -> std::io::Result<()> {
                 },
             );
 
-            Arc::new(LightClient::new(da, celestia_config, prover_vk))
+            let mut builder = LightClient::builder(da, db, config.proving_backend.verifier())
+                .start_height(celestia_config.start_height)
+                .webserver(light_client_webserver);
+            if let Some(prover_vk) = prover_vk {
+                builder = builder.prover_pubkey(prover_vk);
+            }
+            if let Some(trusted_checkpoint) = config.trusted_checkpoint {
+                let checkpoint = trusted_checkpoint.checkpoint().map_err(|e| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string())
+                })?;
+                let attestors = trusted_checkpoint.attestors().map_err(|e| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string())
+                })?;
+                builder = builder.trusted_checkpoint(checkpoint, attestors);
+            }
+
+            let light_client = Arc::new(builder.build().map_err(|e| {
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string())
+            })?);
+
+            // stops syncing and the webserver on ctrl-c, so that the node exits cleanly
+            let stopping = light_client.clone();
+            tokio::spawn(async move {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    stopping.stop();
+                }
+            });
+
+            light_client
         }
         Commands::Prover(args) => {
             let config = load_config(args.clone())
@@ -54,13 +93,7 @@ async fn main() -> std::io::Result<()> {
                 .await
                 .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
 
-            let redis_config = config.clone().redis_config.ok_or_else(|| {
-                std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    "redis configuration not found",
-                )
-            })?;
-            let redis_connections = RedisConnection::new(&redis_config)
+            let db = initialize_db(&config)
                 .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
 
             let signing_key = KeyStoreType::KeyChain(KeyChain)
@@ -74,6 +107,13 @@ async fn main() -> std::io::Result<()> {
                 signing_key: signing_key.clone(),
                 verifying_key: signing_key.verification_key(),
                 start_height: config.celestia_config.unwrap_or_default().start_height,
+                proving_backend: config.proving_backend.clone(),
+                proving_queue_size: config.proving_queue_size,
+                checkpoint_interval: config.checkpoint_interval,
+                da_retry_count: config.da_retry_count,
+                da_retry_interval: config.da_retry_interval(),
+                mempool: config.mempool.clone(),
+                epoch_policy: config.epoch_policy.clone(),
             };
 
             info!(
@@ -81,14 +121,10 @@ async fn main() -> std::io::Result<()> {
                 VerifyingKey::from(prover_cfg.verifying_key)
             );
 
-            Arc::new(
-                Prover::new(Arc::new(Box::new(redis_connections)), da, &prover_cfg).map_err(
-                    |e| {
-                        error!("error initializing prover: {}", e);
-                        std::io::Error::new(std::io::ErrorKind::Other, e.to_string())
-                    },
-                )?,
-            )
+            Arc::new(Prover::new(db, da, &prover_cfg).map_err(|e| {
+                error!("error initializing prover: {}", e);
+                std::io::Error::new(std::io::ErrorKind::Other, e.to_string())
+            })?)
         }
         Commands::FullNode(args) => {
             let config = load_config(args.clone())
@@ -98,13 +134,7 @@ async fn main() -> std::io::Result<()> {
                 .await
                 .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
 
-            let redis_config = config.clone().redis_config.ok_or_else(|| {
-                std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    "redis configuration not found",
-                )
-            })?;
-            let redis_connections = RedisConnection::new(&redis_config)
+            let db = initialize_db(&config)
                 .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
 
             let signing_key = KeyStoreType::KeyChain(KeyChain)
@@ -132,16 +162,19 @@ async fn main() -> std::io::Result<()> {
                 signing_key: signing_key.clone(),
                 verifying_key: prover_vk,
                 start_height: config.celestia_config.unwrap_or_default().start_height,
+                proving_backend: config.proving_backend.clone(),
+                proving_queue_size: config.proving_queue_size,
+                checkpoint_interval: None,
+                da_retry_count: config.da_retry_count,
+                da_retry_interval: config.da_retry_interval(),
+                mempool: config.mempool.clone(),
+                epoch_policy: config.epoch_policy.clone(),
             };
 
-            Arc::new(
-                Prover::new(Arc::new(Box::new(redis_connections)), da, &prover_cfg).map_err(
-                    |e| {
-                        error!("error initializing prover: {}", e);
-                        std::io::Error::new(std::io::ErrorKind::Other, e.to_string())
-                    },
-                )?,
-            )
+            Arc::new(Prover::new(db, da, &prover_cfg).map_err(|e| {
+                error!("error initializing prover: {}", e);
+                std::io::Error::new(std::io::ErrorKind::Other, e.to_string())
+            })?)
         }
     };
 
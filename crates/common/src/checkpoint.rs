@@ -0,0 +1,159 @@
+use anyhow::{bail, ensure, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::digest::Digest;
+
+/// Input of the checkpoint program, which aggregates the proofs of consecutive epochs on top
+/// of the previous checkpoint. The proofs themselves are passed to the program separately.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointInput {
+    /// Verifying key digest of the epoch program.
+    pub epoch_vkey: [u32; 8],
+    /// Verifying key digest of the checkpoint program itself, which cannot be embedded in it.
+    pub checkpoint_vkey: [u32; 8],
+    /// Public values of the previous checkpoint, if there is one.
+    pub previous: Option<CheckpointOutput>,
+    /// Public values of the epochs since the previous checkpoint, in order.
+    pub epoch_public_values: Vec<Vec<u8>>,
+}
+
+/// Public values of a checkpoint proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointOutput {
+    pub epoch_vkey: [u32; 8],
+    pub checkpoint_vkey: [u32; 8],
+    /// Commitment before the first epoch covered by the checkpoint chain.
+    pub first_commitment: Digest,
+    /// Commitment after the last epoch covered by the checkpoint.
+    pub last_commitment: Digest,
+    /// Number of epochs covered by the checkpoint chain, which is the height of the next epoch.
+    pub epoch_count: u64,
+}
+
+impl CheckpointInput {
+    /// Chains the epochs onto the previous checkpoint. `verify_proof` is called with the
+    /// verifying key digest and the public values of every proof the checkpoint relies on.
+    pub fn aggregate(
+        &self,
+        mut verify_proof: impl FnMut(&[u32; 8], &[u8]),
+    ) -> Result<CheckpointOutput> {
+        ensure!(
+            !self.epoch_public_values.is_empty(),
+            "checkpoint must include at least one epoch"
+        );
+
+        let mut output = match &self.previous {
+            Some(previous) => {
+                ensure!(
+                    previous.epoch_vkey == self.epoch_vkey
+                        && previous.checkpoint_vkey == self.checkpoint_vkey,
+                    "previous checkpoint was proven for different programs"
+                );
+                verify_proof(&self.checkpoint_vkey, &bincode::serialize(previous)?);
+                *previous
+            }
+            None => {
+                let (first_commitment, _) = epoch_commitments(&self.epoch_public_values[0])?;
+                CheckpointOutput {
+                    epoch_vkey: self.epoch_vkey,
+                    checkpoint_vkey: self.checkpoint_vkey,
+                    first_commitment,
+                    last_commitment: first_commitment,
+                    epoch_count: 0,
+                }
+            }
+        };
+
+        for public_values in &self.epoch_public_values {
+            verify_proof(&self.epoch_vkey, public_values);
+
+            let (prev_commitment, current_commitment) = epoch_commitments(public_values)?;
+            ensure!(
+                prev_commitment == output.last_commitment,
+                "epoch {} does not continue from the previous commitment",
+                output.epoch_count
+            );
+            output.last_commitment = current_commitment;
+            output.epoch_count += 1;
+        }
+
+        Ok(output)
+    }
+}
+
+/// Reads the previous and current commitment committed by the epoch program.
+pub fn epoch_commitments(public_values: &[u8]) -> Result<(Digest, Digest)> {
+    if public_values.len() != 64 {
+        bail!(
+            "epoch public values have {} bytes, expected 64",
+            public_values.len()
+        );
+    }
+
+    let mut prev_commitment = [0u8; 32];
+    let mut current_commitment = [0u8; 32];
+    prev_commitment.copy_from_slice(&public_values[..32]);
+    current_commitment.copy_from_slice(&public_values[32..]);
+    Ok((Digest(prev_commitment), Digest(current_commitment)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn public_values(prev: u8, current: u8) -> Vec<u8> {
+        [[prev; 32], [current; 32]].concat()
+    }
+
+    fn input(previous: Option<CheckpointOutput>, epochs: Vec<Vec<u8>>) -> CheckpointInput {
+        CheckpointInput {
+            epoch_vkey: [1; 8],
+            checkpoint_vkey: [2; 8],
+            previous,
+            epoch_public_values: epochs,
+        }
+    }
+
+    #[test]
+    fn test_aggregate_chains_epochs() {
+        let mut verified = Vec::new();
+        let first = input(None, vec![public_values(0, 1), public_values(1, 2)])
+            .aggregate(|vkey, _| verified.push(*vkey))
+            .unwrap();
+
+        assert_eq!(first.first_commitment, Digest([0; 32]));
+        assert_eq!(first.last_commitment, Digest([2; 32]));
+        assert_eq!(first.epoch_count, 2);
+        assert_eq!(verified, vec![[1; 8], [1; 8]]);
+
+        // the next checkpoint verifies the previous one instead of its epochs
+        verified.clear();
+        let second = input(Some(first), vec![public_values(2, 3)])
+            .aggregate(|vkey, _| verified.push(*vkey))
+            .unwrap();
+
+        assert_eq!(second.first_commitment, Digest([0; 32]));
+        assert_eq!(second.last_commitment, Digest([3; 32]));
+        assert_eq!(second.epoch_count, 3);
+        assert_eq!(verified, vec![[2; 8], [1; 8]]);
+    }
+
+    #[test]
+    fn test_aggregate_rejects_gaps() {
+        let result =
+            input(None, vec![public_values(0, 1), public_values(2, 3)]).aggregate(|_, _| {});
+        assert!(result.is_err());
+
+        let first = input(None, vec![public_values(0, 1)]).aggregate(|_, _| {}).unwrap();
+        let result = input(Some(first), vec![public_values(2, 3)]).aggregate(|_, _| {});
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_aggregate_rejects_foreign_checkpoint() {
+        let first = input(None, vec![public_values(0, 1)]).aggregate(|_, _| {}).unwrap();
+        let mut next = input(Some(first), vec![public_values(1, 2)]);
+        next.epoch_vkey = [3; 8];
+        assert!(next.aggregate(|_, _| {}).is_err());
+    }
+}
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::hasher::Hasher;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Copy)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord, Copy)]
 pub struct Digest(pub [u8; 32]);
 
 impl Digest {
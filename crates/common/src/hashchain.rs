@@ -1,4 +1,5 @@
-use anyhow::{anyhow, bail, ensure, Result};
+use anyhow::{bail, ensure, Result};
+use prism_errors::TransactionError;
 use serde::{Deserialize, Serialize};
 use std::ops::{Deref, DerefMut};
 
@@ -71,10 +72,9 @@ impl Hashchain {
     }
 
     pub fn get_key_at_index(&self, idx: usize) -> Result<&VerifyingKey> {
-        self.entries
-            .get(idx)
-            .and_then(|entry| entry.operation.get_public_key())
-            .ok_or_else(|| anyhow!("No public key found at index {}", idx))
+        self.entries.get(idx).and_then(|entry| entry.operation.get_public_key()).ok_or_else(|| {
+            TransactionError::InvalidKey(format!("No public key found at index {}", idx)).into()
+        })
     }
 
     pub fn is_key_invalid(&self, key: &VerifyingKey) -> bool {
@@ -174,15 +174,16 @@ impl Hashchain {
     }
 
     fn validate_new_entry(&self, entry: &HashchainEntry) -> Result<()> {
-        entry.validate_operation()?;
+        entry
+            .validate_operation()
+            .map_err(|e| TransactionError::InvalidOperation(e.to_string()))?;
 
         let last_hash = self.last_hash();
         if entry.previous_hash != last_hash {
-            bail!(
-                "Previous hash for new entry must be the last hash - prev: {}, last: {}",
-                entry.previous_hash,
-                last_hash
-            )
+            bail!(TransactionError::InvalidPreviousHash(
+                entry.previous_hash.to_string(),
+                last_hash.to_string()
+            ))
         }
 
         let verifying_key = self.verifying_key_for_entry(entry)?;
@@ -190,22 +191,31 @@ impl Hashchain {
         match entry.operation {
             Operation::CreateAccount { .. } | Operation::RegisterService { .. } => {
                 if !self.entries.is_empty() {
-                    bail!("CreateAccount/RegisterService must be the first entry");
+                    bail!(TransactionError::InvalidOperation(
+                        "CreateAccount/RegisterService must be the first entry".to_string()
+                    ));
                 }
             }
             Operation::AddData { .. } | Operation::AddKey { .. } | Operation::RevokeKey { .. } => {
                 if self.entries.is_empty() {
-                    bail!("CreateAccount/RegisterService must be the first entry");
+                    bail!(TransactionError::InvalidOperation(
+                        "CreateAccount/RegisterService must be the first entry".to_string()
+                    ));
                 }
 
                 if self.is_key_invalid(verifying_key) {
-                    bail!("Invalid key at index {}", &entry.signature_bundle.key_idx);
+                    bail!(TransactionError::InvalidKey(format!(
+                        "Invalid key at index {}",
+                        &entry.signature_bundle.key_idx
+                    )));
                 }
             }
         }
 
         entry.validate_hash()?;
-        entry.validate_signature(verifying_key)
+        entry
+            .validate_signature(verifying_key)
+            .map_err(|e| TransactionError::InvalidSignature(e.to_string()).into())
     }
 
     fn verifying_key_for_entry<'a>(
@@ -345,7 +355,7 @@ impl HashchainEntry {
 
         ensure!(
             self.hash == pristine_entry_hash,
-            "Hashchain entry has incorrect hash"
+            TransactionError::InvalidHash
         );
         Ok(())
     }
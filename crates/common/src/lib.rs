@@ -1,3 +1,4 @@
+pub mod checkpoint;
 pub mod digest;
 pub mod hashchain;
 pub mod hasher;
@@ -1,8 +1,9 @@
-use anyhow::anyhow;
+use anyhow::{anyhow, Result};
 use celestia_types::Blob;
+use prism_errors::TransactionError;
 use serde::{Deserialize, Serialize};
 
-use crate::hashchain::HashchainEntry;
+use crate::{digest::Digest, hashchain::HashchainEntry};
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub struct Transaction {
@@ -10,6 +11,66 @@ pub struct Transaction {
     pub entry: HashchainEntry,
 }
 
+impl Transaction {
+    /// Returns the hash identifying this transaction, computed over its serialized form.
+    pub fn hash(&self) -> Result<Digest> {
+        Ok(Digest::hash(bincode::serialize(self)?))
+    }
+}
+
+/// Lifecycle of a transaction accepted by the prover.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum TransactionStatus {
+    /// Accepted, waiting to be posted to the DA layer.
+    Queued,
+    /// Posted to the DA layer at `da_height`.
+    Posted { da_height: u64 },
+    /// Applied to the state in the epoch with the given height.
+    Included { epoch: u64 },
+    /// Failed to apply when the epoch was executed.
+    Rejected(RejectionReceipt),
+}
+
+/// Machine-readable reason for rejecting a transaction, derived from [`TransactionError`].
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum RejectionKind {
+    InvalidOperation,
+    AlreadyExists,
+    NotFound,
+    InvalidPreviousHash,
+    InvalidHash,
+    InvalidSignature,
+    InvalidKey,
+}
+
+impl From<&TransactionError> for RejectionKind {
+    fn from(error: &TransactionError) -> Self {
+        match error {
+            TransactionError::InvalidOperation(_) => RejectionKind::InvalidOperation,
+            TransactionError::AlreadyExists(_) => RejectionKind::AlreadyExists,
+            TransactionError::NotFound(_) => RejectionKind::NotFound,
+            TransactionError::InvalidPreviousHash(..) => RejectionKind::InvalidPreviousHash,
+            TransactionError::InvalidHash => RejectionKind::InvalidHash,
+            TransactionError::InvalidSignature(_) => RejectionKind::InvalidSignature,
+            TransactionError::InvalidKey(_) => RejectionKind::InvalidKey,
+        }
+    }
+}
+
+/// Record of a transaction that could not be applied. Receipts only depend on the
+/// transactions of an epoch, so every node executing the epoch produces the same ones.
+/// Errors that are not caused by the transaction, e.g. storage failures, don't produce
+/// receipts but stop the execution of the epoch.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct RejectionReceipt {
+    pub transaction_hash: Digest,
+    /// DA height the transaction was read from.
+    pub da_height: u64,
+    pub epoch: u64,
+    pub kind: RejectionKind,
+    pub message: String,
+}
+
 impl TryFrom<&Blob> for Transaction {
     type Error = anyhow::Error;
 
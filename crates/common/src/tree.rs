@@ -5,7 +5,7 @@ use jmt::{
     storage::{NodeBatch, TreeReader, TreeUpdateBatch, TreeWriter},
     JellyfishMerkleTree, KeyHash, RootHash,
 };
-use prism_errors::DatabaseError;
+use prism_errors::TransactionError;
 use serde::{Deserialize, Serialize};
 use std::{convert::Into, sync::Arc};
 
@@ -22,7 +22,7 @@ use HashchainResponse::*;
 pub const SPARSE_MERKLE_PLACEHOLDER_HASH: Digest =
     Digest::new(*b"SPARSE_MERKLE_PLACEHOLDER_HASH__");
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Batch {
     pub prev_root: Digest,
     pub new_root: Digest,
@@ -243,7 +243,9 @@ where
             } => {
                 ensure!(
                     transaction.id == id.as_str(),
-                    "Id of transaction needs to be equal to operation id"
+                    TransactionError::InvalidOperation(
+                        "Id of transaction needs to be equal to operation id".to_string()
+                    )
                 );
 
                 let hashed_id = Digest::hash(id);
@@ -251,8 +253,8 @@ where
 
                 // Verify that the account doesn't already exist
                 if matches!(self.get(account_key_hash)?, Found(_, _)) {
-                    bail!(DatabaseError::NotFoundError(format!(
-                        "Account already exists for ID {}",
+                    bail!(TransactionError::AlreadyExists(format!(
+                        "Account for ID {}",
                         id
                     )));
                 }
@@ -260,17 +262,25 @@ where
                 let service_key_hash = KeyHash::with::<Hasher>(Digest::hash(service_id.as_bytes()));
 
                 let Found(service_hashchain, _) = self.get(service_key_hash)? else {
-                    bail!("Failed to get hashchain for service ID {}", service_id);
+                    bail!(TransactionError::NotFound(format!(
+                        "Hashchain for service ID {}",
+                        service_id
+                    )));
                 };
 
                 let Some(service_last_entry) = service_hashchain.last() else {
-                    bail!("Service hashchain is empty, could not retrieve challenge key");
+                    bail!(TransactionError::InvalidOperation(
+                        "Service hashchain is empty, could not retrieve challenge key".to_string()
+                    ));
                 };
 
                 let creation_gate = match &service_last_entry.operation {
                     Operation::RegisterService { creation_gate, .. } => creation_gate,
                     _ => {
-                        bail!("Service hashchain's last entry was not a RegisterService operation")
+                        bail!(TransactionError::InvalidOperation(
+                            "Service hashchain's last entry was not a RegisterService operation"
+                                .to_string()
+                        ))
                     }
                 };
 
@@ -281,7 +291,9 @@ where
                 let ServiceChallenge::Signed(service_pubkey) = creation_gate;
                 let ServiceChallengeInput::Signed(challenge_signature) = &challenge;
 
-                service_pubkey.verify_signature(&hash.to_bytes(), challenge_signature)?;
+                service_pubkey
+                    .verify_signature(&hash.to_bytes(), challenge_signature)
+                    .map_err(|e| TransactionError::InvalidSignature(e.to_string()))?;
 
                 debug!("creating new hashchain for user ID {}", id);
 
@@ -291,7 +303,9 @@ where
             Operation::RegisterService { id, .. } => {
                 ensure!(
                     transaction.id == id.as_str(),
-                    "Id of transaction needs to be equal to operation id"
+                    TransactionError::InvalidOperation(
+                        "Id of transaction needs to be equal to operation id".to_string()
+                    )
                 );
 
                 let hashed_id = Digest::hash(id);
@@ -308,7 +322,7 @@ where
     fn insert(&mut self, key: KeyHash, entry: HashchainEntry) -> Result<InsertProof> {
         let old_root = self.get_current_root()?;
         let (None, non_membership_merkle_proof) = self.jmt.get_with_proof(key, self.epoch)? else {
-            bail!("Key already exists");
+            bail!(TransactionError::AlreadyExists("Key".to_string()));
         };
 
         let non_membership_proof = NonMembershipProof {
@@ -342,7 +356,7 @@ where
         let (Some(old_serialized_hashchain), inclusion_proof) =
             self.jmt.get_with_proof(key, self.epoch)?
         else {
-            bail!("Key does not exist");
+            bail!(TransactionError::NotFound("Key".to_string()));
         };
 
         let old_hashchain: Hashchain = bincode::deserialize(old_serialized_hashchain.as_slice())?;
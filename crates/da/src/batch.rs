@@ -0,0 +1,208 @@
+use crate::consts::MAX_BLOB_SIZE;
+use anyhow::{anyhow, bail, ensure, Result};
+use prism_common::transaction::Transaction;
+use prism_errors::GeneralError;
+use std::borrow::Cow;
+
+/// Marks a blob in the operation namespace as a batch envelope. Blobs without it hold a single
+/// bincode encoded transaction, as posted before batches were introduced. Those start with the
+/// length of the transaction's id, which can't match the magic for any id that fits in a blob.
+pub const BATCH_MAGIC: [u8; 4] = *b"PRSB";
+/// Version of the batch format written by [`encode_transaction_blobs`].
+pub const BATCH_VERSION: u8 = 1;
+
+/// Magic, version and compression flag.
+const HEADER_LEN: usize = BATCH_MAGIC.len() + 2;
+/// Length prefix of the bincode encoded transaction list.
+const LIST_PREFIX_LEN: usize = 8;
+const ZSTD_LEVEL: i32 = 3;
+
+/// How the transactions in a batch envelope are compressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Compression {
+    None = 0,
+    Zstd = 1,
+}
+
+impl TryFrom<u8> for Compression {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(Compression::None),
+            1 => Ok(Compression::Zstd),
+            _ => bail!("unknown batch compression {}", value),
+        }
+    }
+}
+
+/// Packs `transactions` into batch envelopes of at most `max_blob_size` bytes, keeping their
+/// order. Each envelope holds the bincode encoded transactions, compressed with zstd unless
+/// that doesn't make them smaller.
+pub fn encode_transaction_blobs(
+    transactions: &[Transaction],
+    max_blob_size: usize,
+) -> Result<Vec<Vec<u8>>> {
+    ensure!(
+        max_blob_size <= MAX_BLOB_SIZE,
+        "blob size {} exceeds the maximum of {} bytes",
+        max_blob_size,
+        MAX_BLOB_SIZE
+    );
+    // batches are split by their uncompressed size, so that every blob fits even if the
+    // transactions don't compress
+    let max_payload = max_blob_size.saturating_sub(HEADER_LEN + LIST_PREFIX_LEN);
+
+    let mut blobs = Vec::new();
+    let mut start = 0;
+    let mut payload = 0;
+    for (i, transaction) in transactions.iter().enumerate() {
+        let size = bincode::serialized_size(transaction)
+            .map_err(|e| GeneralError::EncodingError(e.to_string()))? as usize;
+        if size > max_payload {
+            bail!(GeneralError::EncodingError(format!(
+                "transaction for {} takes {} bytes, more than fit into a blob",
+                transaction.id, size
+            )));
+        }
+
+        if payload + size > max_payload {
+            blobs.push(encode_batch(&transactions[start..i])?);
+            start = i;
+            payload = 0;
+        }
+        payload += size;
+    }
+    if start < transactions.len() {
+        blobs.push(encode_batch(&transactions[start..])?);
+    }
+
+    Ok(blobs)
+}
+
+fn encode_batch(transactions: &[Transaction]) -> Result<Vec<u8>> {
+    let payload =
+        bincode::serialize(transactions).map_err(|e| GeneralError::EncodingError(e.to_string()))?;
+    let compressed = zstd::bulk::compress(&payload, ZSTD_LEVEL)
+        .map_err(|e| GeneralError::EncodingError(e.to_string()))?;
+
+    let (compression, body) = if compressed.len() < payload.len() {
+        (Compression::Zstd, compressed)
+    } else {
+        (Compression::None, payload)
+    };
+
+    let mut blob = Vec::with_capacity(HEADER_LEN + body.len());
+    blob.extend_from_slice(&BATCH_MAGIC);
+    blob.push(BATCH_VERSION);
+    blob.push(compression as u8);
+    blob.extend_from_slice(&body);
+    Ok(blob)
+}
+
+/// Decodes the transactions in a blob from the operation namespace, which is either a batch
+/// envelope or a single transaction posted before batches were introduced.
+pub fn decode_transaction_blob(data: &[u8]) -> Result<Vec<Transaction>> {
+    let Some(envelope) = data.strip_prefix(&BATCH_MAGIC) else {
+        let transaction =
+            bincode::deserialize(data).map_err(|e| GeneralError::DecodingError(e.to_string()))?;
+        return Ok(vec![transaction]);
+    };
+
+    let [version, compression, body @ ..] = envelope else {
+        bail!(GeneralError::DecodingError(
+            "truncated batch header".to_string()
+        ));
+    };
+    if *version != BATCH_VERSION {
+        bail!(GeneralError::DecodingError(format!(
+            "unsupported batch version {}",
+            version
+        )));
+    }
+
+    let payload = match Compression::try_from(*compression)? {
+        Compression::None => Cow::Borrowed(body),
+        // batches are never larger than a blob before compression
+        Compression::Zstd => Cow::Owned(
+            zstd::bulk::decompress(body, MAX_BLOB_SIZE)
+                .map_err(|e| anyhow!(GeneralError::DecodingError(e.to_string())))?,
+        ),
+    };
+    bincode::deserialize(&payload).map_err(|e| GeneralError::DecodingError(e.to_string()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_common::transaction_builder::TransactionBuilder;
+
+    fn transactions(count: usize) -> Vec<Transaction> {
+        let mut builder = TransactionBuilder::new();
+        let mut transactions =
+            vec![builder.register_service_with_random_keys("test_service").commit()];
+        for i in 1..count {
+            transactions.push(
+                builder
+                    .create_account_with_random_key(&format!("user_{}", i), "test_service")
+                    .commit(),
+            );
+        }
+        transactions
+    }
+
+    #[test]
+    fn test_batch_roundtrip() {
+        let transactions = transactions(20);
+        let blobs = encode_transaction_blobs(&transactions, MAX_BLOB_SIZE).unwrap();
+        assert_eq!(blobs.len(), 1);
+        assert!(blobs[0].starts_with(&BATCH_MAGIC));
+        assert_eq!(blobs[0][4], BATCH_VERSION);
+        assert_eq!(decode_transaction_blob(&blobs[0]).unwrap(), transactions);
+
+        assert!(encode_transaction_blobs(&[], MAX_BLOB_SIZE).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_large_batches_are_split() {
+        let transactions = transactions(20);
+        let size = transactions
+            .iter()
+            .map(|transaction| bincode::serialized_size(transaction).unwrap() as usize)
+            .max()
+            .unwrap();
+        let max_blob_size = HEADER_LEN + LIST_PREFIX_LEN + 3 * size;
+
+        let blobs = encode_transaction_blobs(&transactions, max_blob_size).unwrap();
+        assert!(blobs.len() > 1);
+        assert!(blobs.iter().all(|blob| blob.len() <= max_blob_size));
+
+        let decoded: Vec<Transaction> =
+            blobs.iter().flat_map(|blob| decode_transaction_blob(blob).unwrap()).collect();
+        assert_eq!(decoded, transactions);
+
+        // a transaction that doesn't fit into any blob
+        assert!(encode_transaction_blobs(&transactions, HEADER_LEN + LIST_PREFIX_LEN).is_err());
+        assert!(encode_transaction_blobs(&transactions, MAX_BLOB_SIZE + 1).is_err());
+    }
+
+    #[test]
+    fn test_decodes_single_transaction_blobs() {
+        let transaction = transactions(1).remove(0);
+        let blob = bincode::serialize(&transaction).unwrap();
+        assert_eq!(decode_transaction_blob(&blob).unwrap(), vec![transaction]);
+    }
+
+    #[test]
+    fn test_rejects_unknown_envelopes() {
+        let mut blob = encode_transaction_blobs(&transactions(2), MAX_BLOB_SIZE).unwrap().remove(0);
+        assert!(decode_transaction_blob(&blob[..5]).is_err());
+
+        blob[5] = 7;
+        assert!(decode_transaction_blob(&blob).is_err());
+
+        blob[4] = BATCH_VERSION + 1;
+        assert!(decode_transaction_blob(&blob).is_err());
+    }
+}
@@ -1,39 +1,198 @@
-use crate::{DataAvailabilityLayer, FinalizedEpoch};
-use anyhow::{anyhow, Context, Result};
+use crate::{
+    batch::{decode_transaction_blob, encode_transaction_blobs},
+    consts::{MAX_BLOB_SIZE, MAX_SUBMISSION_SIZE},
+    fetch_epochs, publish_heights_up_to, submit_epoch_blobs, Checkpoint, DaHealth,
+    DataAvailabilityLayer, FinalizedEpoch, SkippedEpochBlobCounter, SkippedEpochBlobs,
+};
+use anyhow::{anyhow, ensure, Context, Result};
 use async_trait::async_trait;
 use celestia_rpc::{BlobClient, Client, HeaderClient};
 use celestia_types::{nmt::Namespace, Blob, TxConfig};
-use log::{debug, error, trace, warn};
+use ed25519_consensus::VerificationKey as VerifyingKey;
+use log::{debug, error, info, trace, warn};
 use prism_common::transaction::Transaction;
 use prism_errors::{DataAvailabilityError, GeneralError};
 use serde::{Deserialize, Serialize};
 use std::{
-    self,
+    self, env, fmt, fs,
+    path::PathBuf,
     sync::{
         atomic::{AtomicU64, Ordering},
-        Arc,
+        Arc, Mutex, RwLock,
     },
+    time::Duration,
 };
-use tokio::{sync::broadcast, task::spawn};
+use tokio::{sync::broadcast, task::spawn, time::sleep};
 
 use bincode;
 
-impl TryFrom<&Blob> for FinalizedEpoch {
-    type Error = anyhow::Error;
-
-    fn try_from(value: &Blob) -> Result<Self, Self::Error> {
-        bincode::deserialize(&value.data).context(format!(
-            "Failed to decode blob into FinalizedEpoch: {value:?}"
-        ))
-    }
-}
-
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CelestiaConfig {
     pub connection_string: String,
     pub start_height: u64,
     pub snark_namespace_id: String,
     pub operation_namespace_id: String,
+    #[serde(default = "default_checkpoint_namespace_id")]
+    pub checkpoint_namespace_id: String,
+    /// Delay before reconnecting after the connection to the node dropped. It doubles with
+    /// every failed attempt, up to `max_reconnect_backoff_ms`.
+    #[serde(default = "default_reconnect_backoff_ms")]
+    pub reconnect_backoff_ms: u64,
+    #[serde(default = "default_max_reconnect_backoff_ms")]
+    pub max_reconnect_backoff_ms: u64,
+    /// Token for nodes that require authentication, like bridge nodes by default.
+    #[serde(default)]
+    pub auth_token: Option<AuthToken>,
+    /// Fees and retries for epochs and checkpoints.
+    #[serde(default)]
+    pub epoch_submission: SubmissionConfig,
+    /// Fees and retries for transaction batches.
+    #[serde(default)]
+    pub transaction_submission: SubmissionConfig,
+}
+
+/// Where the node's auth token is read from, when connecting to it.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthToken {
+    Inline(String),
+    /// Path to a file holding the token, e.g. as written by `celestia bridge auth write`.
+    File(PathBuf),
+    /// Name of the environment variable holding the token.
+    Env(String),
+}
+
+impl AuthToken {
+    pub fn resolve(&self) -> Result<String> {
+        let token = match self {
+            AuthToken::Inline(token) => token.clone(),
+            AuthToken::File(path) => fs::read_to_string(path)
+                .with_context(|| format!("Failed to read auth token from {}", path.display()))?,
+            AuthToken::Env(var) => {
+                env::var(var).with_context(|| format!("Failed to read auth token from ${}", var))?
+            }
+        };
+        let token = token.trim();
+        ensure!(!token.is_empty(), "auth token is empty");
+        Ok(token.to_string())
+    }
+}
+
+// keeps inline tokens out of logged configs
+impl fmt::Debug for AuthToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthToken::Inline(_) => f.write_str("Inline(<redacted>)"),
+            AuthToken::File(path) => f.debug_tuple("File").field(path).finish(),
+            AuthToken::Env(var) => f.debug_tuple("Env").field(var).finish(),
+        }
+    }
+}
+
+/// Gas price of a submission, in utia per gas unit.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum GasPrice {
+    /// Lets the node estimate the gas price.
+    #[default]
+    Estimate,
+    Fixed(f64),
+    /// Starts at `initial` and multiplies the price by `multiplier` after every failed
+    /// attempt, up to `max`.
+    Escalating {
+        initial: f64,
+        multiplier: f64,
+        max: f64,
+    },
+}
+
+impl GasPrice {
+    /// Returns the gas price for the `attempt`th attempt, counted from 1, or `None` if the node
+    /// should estimate it.
+    pub fn for_attempt(&self, attempt: u32) -> Option<f64> {
+        match *self {
+            GasPrice::Estimate => None,
+            GasPrice::Fixed(price) => Some(price),
+            GasPrice::Escalating {
+                initial,
+                multiplier,
+                max,
+            } => Some((initial * multiplier.powi(attempt.saturating_sub(1) as i32)).min(max)),
+        }
+    }
+}
+
+/// How blobs are submitted to the DA layer. Without further configuration, the node estimates
+/// the fees and pays them from its default key.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SubmissionConfig {
+    #[serde(default)]
+    pub gas_price: GasPrice,
+    /// Gas limit of the submission. Estimated by the node if not set.
+    #[serde(default)]
+    pub gas_limit: Option<u64>,
+    /// Account paying the fees through a fee grant to the submitting key.
+    #[serde(default)]
+    pub fee_granter_address: Option<String>,
+    /// Key in the node's keyring that signs the submission.
+    #[serde(default)]
+    pub key_name: Option<String>,
+    #[serde(default = "default_submission_attempts")]
+    pub attempts: u32,
+    #[serde(default = "default_submission_retry_interval_ms")]
+    pub retry_interval_ms: u64,
+}
+
+fn default_submission_attempts() -> u32 {
+    1
+}
+
+fn default_submission_retry_interval_ms() -> u64 {
+    1_000
+}
+
+impl Default for SubmissionConfig {
+    fn default() -> Self {
+        SubmissionConfig {
+            gas_price: GasPrice::default(),
+            gas_limit: None,
+            fee_granter_address: None,
+            key_name: None,
+            attempts: default_submission_attempts(),
+            retry_interval_ms: default_submission_retry_interval_ms(),
+        }
+    }
+}
+
+impl SubmissionConfig {
+    pub fn tx_config(&self, attempt: u32) -> TxConfig {
+        let mut config = TxConfig::default();
+        if let Some(gas_price) = self.gas_price.for_attempt(attempt) {
+            config.with_gas_price(gas_price);
+        }
+        if let Some(gas_limit) = self.gas_limit {
+            config.with_gas(gas_limit);
+        }
+        if let Some(fee_granter_address) = &self.fee_granter_address {
+            config.with_fee_granter_address(fee_granter_address.clone());
+        }
+        if let Some(key_name) = &self.key_name {
+            config.with_key_name(key_name.clone());
+        }
+        config
+    }
+}
+
+fn default_checkpoint_namespace_id() -> String {
+    "00000000000000de100a".to_string()
+}
+
+fn default_reconnect_backoff_ms() -> u64 {
+    500
+}
+
+fn default_max_reconnect_backoff_ms() -> u64 {
+    30_000
 }
 
 impl Default for CelestiaConfig {
@@ -43,22 +202,39 @@ impl Default for CelestiaConfig {
             start_height: 1,
             snark_namespace_id: "00000000000000de1008".to_string(),
             operation_namespace_id: "00000000000000de1009".to_string(),
+            checkpoint_namespace_id: default_checkpoint_namespace_id(),
+            reconnect_backoff_ms: default_reconnect_backoff_ms(),
+            max_reconnect_backoff_ms: default_max_reconnect_backoff_ms(),
+            auth_token: None,
+            epoch_submission: SubmissionConfig::default(),
+            transaction_submission: SubmissionConfig::default(),
         }
     }
 }
 
 pub struct CelestiaConnection {
-    pub client: celestia_rpc::Client,
+    /// Replaced by the header follower whenever it reconnects to the node.
+    client: Arc<RwLock<Arc<Client>>>,
+    connection_string: String,
+    auth_token: Option<String>,
+    reconnect_backoff: Duration,
+    max_reconnect_backoff: Duration,
+    epoch_submission: SubmissionConfig,
+    transaction_submission: SubmissionConfig,
     pub snark_namespace: Namespace,
     pub operation_namespace: Namespace,
+    pub checkpoint_namespace: Namespace,
 
     height_update_tx: broadcast::Sender<u64>,
     sync_target: Arc<AtomicU64>,
+    skipped_epoch_blobs: SkippedEpochBlobCounter,
+    health: Arc<Mutex<DaHealth>>,
 }
 
 impl CelestiaConnection {
-    pub async fn new(config: &CelestiaConfig, auth_token: Option<&str>) -> Result<Self> {
-        let client = Client::new(&config.connection_string, auth_token)
+    pub async fn new(config: &CelestiaConfig) -> Result<Self> {
+        let auth_token = config.auth_token.as_ref().map(AuthToken::resolve).transpose()?;
+        let client = Client::new(&config.connection_string, auth_token.as_deref())
             .await
             .context("Failed to initialize websocket connection")
             .map_err(|e| DataAvailabilityError::NetworkError(e.to_string()))?;
@@ -74,16 +250,191 @@ impl CelestiaConnection {
                 &config.operation_namespace_id
             ))?;
 
+        let checkpoint_namespace =
+            create_namespace(&config.checkpoint_namespace_id).context(format!(
+                "Failed to create checkpoint namespace from: '{}'",
+                &config.checkpoint_namespace_id
+            ))?;
+
         let (height_update_tx, _) = broadcast::channel(100);
 
         Ok(CelestiaConnection {
-            client,
+            client: Arc::new(RwLock::new(Arc::new(client))),
+            connection_string: config.connection_string.clone(),
+            auth_token,
+            reconnect_backoff: Duration::from_millis(config.reconnect_backoff_ms),
+            max_reconnect_backoff: Duration::from_millis(config.max_reconnect_backoff_ms),
+            epoch_submission: config.epoch_submission.clone(),
+            transaction_submission: config.transaction_submission.clone(),
             snark_namespace,
             operation_namespace,
+            checkpoint_namespace,
             height_update_tx,
             sync_target: Arc::new(AtomicU64::new(0)),
+            skipped_epoch_blobs: SkippedEpochBlobCounter::default(),
+            health: Arc::new(Mutex::new(DaHealth::default())),
         })
     }
+
+    fn client(&self) -> Arc<Client> {
+        self.client.read().unwrap().clone()
+    }
+
+    /// Submits `blobs` in one transaction, retrying as configured in `submission`. Every
+    /// failed attempt is logged and included in the returned error.
+    async fn submit_blobs(
+        &self,
+        blobs: &[Blob],
+        submission: &SubmissionConfig,
+        what: &str,
+    ) -> Result<u64> {
+        let attempts = submission.attempts.max(1);
+        let mut failures = Vec::new();
+        for attempt in 1..=attempts {
+            if attempt > 1 {
+                sleep(Duration::from_millis(submission.retry_interval_ms)).await;
+            }
+
+            let tx_config = submission.tx_config(attempt);
+            match self.client().blob_submit(blobs, tx_config.clone()).await {
+                Ok(height) => return Ok(height),
+                Err(e) => {
+                    warn!(
+                        "submitting {} failed (attempt {}/{}, {:?}): {}",
+                        what, attempt, attempts, tx_config, e
+                    );
+                    failures.push(format!("attempt {}: {}", attempt, e));
+                }
+            }
+        }
+
+        Err(anyhow!(DataAvailabilityError::SubmissionError(format!(
+            "{}: {}",
+            what,
+            failures.join("; ")
+        ))))
+    }
+
+    async fn submit_snark_blobs(&self, data: Vec<Vec<u8>>, what: &str) -> Result<u64> {
+        let blobs = data
+            .into_iter()
+            .map(|data| {
+                Blob::new(self.snark_namespace, data).map_err(|e| {
+                    DataAvailabilityError::GeneralError(GeneralError::BlobCreationError(
+                        e.to_string(),
+                    ))
+                })
+            })
+            .collect::<Result<Vec<Blob>, _>>()?;
+        self.submit_blobs(&blobs, &self.epoch_submission, what).await
+    }
+
+    /// Returns the raw blobs in the snark namespace at `height`.
+    async fn get_snark_blobs(&self, height: u64) -> Result<Vec<Vec<u8>>> {
+        match BlobClient::blob_get_all(&*self.client(), height, &[self.snark_namespace]).await {
+            Ok(maybe_blobs) => {
+                Ok(maybe_blobs.unwrap_or_default().into_iter().map(|blob| blob.data).collect())
+            }
+            Err(err) => {
+                if err.to_string().contains("blob: not found") {
+                    Ok(Vec::new())
+                } else {
+                    Err(anyhow!(DataAvailabilityError::DataRetrievalError(
+                        height,
+                        format!("getting epoch from da layer: {}", err)
+                    )))
+                }
+            }
+        }
+    }
+}
+
+/// Follows the node's headers and publishes every new height in order. When the connection
+/// drops, it reconnects with exponential backoff and publishes the heights it missed.
+struct HeaderFollower {
+    client: Arc<RwLock<Arc<Client>>>,
+    connection_string: String,
+    auth_token: Option<String>,
+    reconnect_backoff: Duration,
+    max_reconnect_backoff: Duration,
+    height_update_tx: broadcast::Sender<u64>,
+    sync_target: Arc<AtomicU64>,
+    health: Arc<Mutex<DaHealth>>,
+}
+
+impl HeaderFollower {
+    async fn run(self) {
+        let mut backoff = self.reconnect_backoff;
+        loop {
+            match self.follow_headers().await {
+                Ok(()) => {
+                    backoff = self.reconnect_backoff;
+                    self.disconnected("header subscription ended".to_string());
+                }
+                Err(e) => self.disconnected(format!("{:#}", e)),
+            }
+
+            loop {
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(self.max_reconnect_backoff);
+
+                match Client::new(&self.connection_string, self.auth_token.as_deref()).await {
+                    Ok(client) => {
+                        *self.client.write().unwrap() = Arc::new(client);
+                        self.health.lock().unwrap().reconnects += 1;
+                        info!("reconnected to DA layer at {}", self.connection_string);
+                        break;
+                    }
+                    Err(e) => self.disconnected(format!("reconnecting to DA layer: {}", e)),
+                }
+            }
+        }
+    }
+
+    /// Publishes new heights until the header subscription ends, which happens once the
+    /// connection to the node dropped.
+    async fn follow_headers(&self) -> Result<()> {
+        let client = self.client.read().unwrap().clone();
+        let mut header_sub = HeaderClient::header_subscribe(&*client)
+            .await
+            .context("Failed to subscribe to headers from DA layer")?;
+        // catches up on heights produced while the connection was down
+        let head = HeaderClient::header_network_head(&*client)
+            .await
+            .context("Failed to get network head from DA layer")?
+            .header
+            .height
+            .value();
+        self.connected();
+        publish_heights_up_to(&self.sync_target, &self.height_update_tx, head);
+
+        while let Some(extended_header_result) = header_sub.next().await {
+            match extended_header_result {
+                Ok(extended_header) => publish_heights_up_to(
+                    &self.sync_target,
+                    &self.height_update_tx,
+                    extended_header.header.height.value(),
+                ),
+                Err(e) => {
+                    error!("Error retrieving header from DA layer: {}", e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn connected(&self) {
+        let mut health = self.health.lock().unwrap();
+        health.connected = true;
+        health.last_error = None;
+    }
+
+    fn disconnected(&self, reason: String) {
+        warn!("lost connection to DA layer: {}", reason);
+        let mut health = self.health.lock().unwrap();
+        health.connected = false;
+        health.last_error = Some(reason);
+    }
 }
 
 fn create_namespace(namespace_hex: &str) -> Result<Namespace> {
@@ -105,7 +456,7 @@ impl DataAvailabilityLayer for CelestiaConnection {
     }
 
     async fn initialize_sync_target(&self) -> Result<u64> {
-        let height = HeaderClient::header_network_head(&self.client)
+        let height = HeaderClient::header_network_head(&*self.client())
             .await
             .context("Failed to get network head from DA layer")
             .map(|extended_header| extended_header.header.height.value())?;
@@ -114,56 +465,90 @@ impl DataAvailabilityLayer for CelestiaConnection {
         Ok(height)
     }
 
-    async fn get_finalized_epoch(&self, height: u64) -> Result<Option<FinalizedEpoch>> {
-        trace!("searching for epoch on da layer at height {}", height);
+    async fn get_finalized_epochs(
+        &self,
+        height: u64,
+        provers: &[VerifyingKey],
+    ) -> Result<Vec<FinalizedEpoch>> {
+        trace!("searching for epochs on da layer at height {}", height);
+
+        let blobs = self.get_snark_blobs(height).await?;
+        let epochs = fetch_epochs(height, &blobs, |chunk_height| {
+            self.get_snark_blobs(chunk_height)
+        })
+        .await?;
+        Ok(self.skipped_epoch_blobs.filter(height, epochs, provers))
+    }
+
+    fn skipped_epoch_blobs(&self) -> SkippedEpochBlobs {
+        self.skipped_epoch_blobs.get()
+    }
+
+    async fn submit_finalized_epoch(&self, epoch: FinalizedEpoch) -> Result<u64> {
+        debug!("posting {}th epoch to da layer", epoch.height);
 
-        match BlobClient::blob_get_all(&self.client, height, &[self.snark_namespace]).await {
+        // epochs larger than a blob are posted in chunks, which may take several transactions
+        // of at most MAX_SUBMISSION_SIZE
+        let what = format!("epoch {}", epoch.height);
+        submit_epoch_blobs(&epoch, MAX_BLOB_SIZE, MAX_SUBMISSION_SIZE, |data| {
+            self.submit_snark_blobs(data, &what)
+        })
+        .await
+    }
+
+    async fn get_checkpoints(
+        &self,
+        height: u64,
+        provers: &[VerifyingKey],
+    ) -> Result<Vec<Checkpoint>> {
+        trace!("searching for checkpoints on da layer at height {}", height);
+
+        match BlobClient::blob_get_all(&*self.client(), height, &[self.checkpoint_namespace]).await
+        {
             Ok(maybe_blobs) => match maybe_blobs {
-                Some(blobs) => blobs
-                    .into_iter()
-                    .next()
-                    .map(|blob| {
-                        FinalizedEpoch::try_from(&blob).map_err(|_| {
-                            anyhow!(GeneralError::ParsingError(format!(
-                                "marshalling blob from height {} to epoch json: {:?}",
-                                height, &blob
-                            )))
-                        })
-                    })
-                    .transpose(),
-                None => Ok(None),
+                Some(blobs) => Ok(self.skipped_epoch_blobs.filter(
+                    height,
+                    blobs.iter().map(|blob| Checkpoint::from_blob(&blob.data)),
+                    provers,
+                )),
+                None => Ok(Vec::new()),
             },
             Err(err) => {
                 if err.to_string().contains("blob: not found") {
-                    Ok(None)
+                    Ok(Vec::new())
                 } else {
                     Err(anyhow!(DataAvailabilityError::DataRetrievalError(
                         height,
-                        format!("getting epoch from da layer: {}", err)
+                        format!("getting checkpoints from da layer: {}", err)
                     )))
                 }
             }
         }
     }
 
-    async fn submit_finalized_epoch(&self, epoch: FinalizedEpoch) -> Result<u64> {
-        debug!("posting {}th epoch to da layer", epoch.height);
+    async fn submit_checkpoint(&self, checkpoint: Checkpoint) -> Result<u64> {
+        debug!(
+            "posting checkpoint up to epoch {} to da layer",
+            checkpoint.epoch_height
+        );
 
-        let data = bincode::serialize(&epoch).map_err(|e| {
+        let data = bincode::serialize(&checkpoint).map_err(|e| {
             DataAvailabilityError::GeneralError(GeneralError::ParsingError(format!(
-                "serializing epoch {}: {}",
-                epoch.height, e
+                "serializing checkpoint up to epoch {}: {}",
+                checkpoint.epoch_height, e
             )))
         })?;
 
-        let blob = Blob::new(self.snark_namespace, data).map_err(|e| {
+        let blob = Blob::new(self.checkpoint_namespace, data).map_err(|e| {
             DataAvailabilityError::GeneralError(GeneralError::BlobCreationError(e.to_string()))
         })?;
 
-        self.client
-            .blob_submit(&[blob], TxConfig::default())
-            .await
-            .map_err(|e| anyhow!(DataAvailabilityError::SubmissionError(e.to_string())))
+        self.submit_blobs(
+            &[blob],
+            &self.epoch_submission,
+            &format!("checkpoint up to epoch {}", checkpoint.epoch_height),
+        )
+        .await
     }
 
     async fn get_transactions(&self, height: u64) -> Result<Vec<Transaction>> {
@@ -172,7 +557,7 @@ impl DataAvailabilityLayer for CelestiaConnection {
             height
         );
         let maybe_blobs =
-            BlobClient::blob_get_all(&self.client, height, &[self.operation_namespace])
+            BlobClient::blob_get_all(&*self.client(), height, &[self.operation_namespace])
                 .await
                 .map_err(|e| {
                     anyhow!(DataAvailabilityError::DataRetrievalError(
@@ -188,14 +573,14 @@ impl DataAvailabilityLayer for CelestiaConnection {
 
         let transactions = blobs
             .iter()
-            .filter_map(|blob| match Transaction::try_from(blob) {
-                Ok(transaction) => Some(transaction),
+            .flat_map(|blob| match decode_transaction_blob(&blob.data) {
+                Ok(transactions) => transactions,
                 Err(e) => {
                     warn!(
-                        "Failed to parse blob from height {} to transaction: {:?}",
+                        "Failed to parse blob from height {} to transactions: {:?}",
                         height, e
                     );
-                    None
+                    Vec::new()
                 }
             })
             .collect();
@@ -205,22 +590,11 @@ impl DataAvailabilityLayer for CelestiaConnection {
 
     async fn submit_transactions(&self, transactions: Vec<Transaction>) -> Result<u64> {
         debug!("posting {} transactions to DA layer", transactions.len());
-        let blobs: Result<Vec<Blob>, _> = transactions
-            .iter()
-            .map(|transaction| {
-                let data = bincode::serialize(transaction)
-                    .context(format!("Failed to serialize transaction {:?}", transaction))
-                    .map_err(|e| {
-                        DataAvailabilityError::GeneralError(GeneralError::ParsingError(
-                            e.to_string(),
-                        ))
-                    })?;
-
+        let blobs: Result<Vec<Blob>, _> = encode_transaction_blobs(&transactions, MAX_BLOB_SIZE)?
+            .into_iter()
+            .map(|data| {
                 Blob::new(self.operation_namespace, data)
-                    .context(format!(
-                        "Failed to create blob for transaction {:?}",
-                        transaction
-                    ))
+                    .context("Failed to create blob for transaction batch")
                     .map_err(|e| {
                         DataAvailabilityError::GeneralError(GeneralError::BlobCreationError(
                             e.to_string(),
@@ -230,15 +604,22 @@ impl DataAvailabilityLayer for CelestiaConnection {
             .collect();
 
         let blobs = blobs?;
+        debug!(
+            "packed {} transactions into {} blobs",
+            transactions.len(),
+            blobs.len()
+        );
 
         for (i, blob) in blobs.iter().enumerate() {
             trace!("blob {}: {:?}", i, blob);
         }
 
-        self.client
-            .blob_submit(&blobs, TxConfig::default())
-            .await
-            .map_err(|e| anyhow!(DataAvailabilityError::SubmissionError(e.to_string())))
+        self.submit_blobs(
+            &blobs,
+            &self.transaction_submission,
+            &format!("{} transactions", transactions.len()),
+        )
+        .await
     }
 
     fn subscribe_to_heights(&self) -> broadcast::Receiver<u64> {
@@ -246,29 +627,252 @@ impl DataAvailabilityLayer for CelestiaConnection {
     }
 
     async fn start(&self) -> Result<()> {
-        let mut header_sub = HeaderClient::header_subscribe(&self.client)
-            .await
-            .context("Failed to subscribe to headers from DA layer")?;
+        let follower = HeaderFollower {
+            client: self.client.clone(),
+            connection_string: self.connection_string.clone(),
+            auth_token: self.auth_token.clone(),
+            reconnect_backoff: self.reconnect_backoff,
+            max_reconnect_backoff: self.max_reconnect_backoff,
+            height_update_tx: self.height_update_tx.clone(),
+            sync_target: self.sync_target.clone(),
+            health: self.health.clone(),
+        };
+        spawn(follower.run());
+        Ok(())
+    }
 
-        let sync_target = self.sync_target.clone();
-        let height_update_tx = self.height_update_tx.clone();
-
-        spawn(async move {
-            while let Some(extended_header_result) = header_sub.next().await {
-                match extended_header_result {
-                    Ok(extended_header) => {
-                        let height = extended_header.header.height.value();
-                        sync_target.store(height, Ordering::Relaxed);
-                        // todo: correct error handling
-                        let _ = height_update_tx.send(height);
-                        trace!("updated sync target for height {}", height);
-                    }
-                    Err(e) => {
-                        error!("Error retrieving header from DA layer: {}", e);
-                    }
+    fn health(&self) -> DaHealth {
+        self.health.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use celestia_types::{test_utils::ExtendedHeaderGenerator, ExtendedHeader};
+    use jsonrpsee::{
+        core::SubscriptionResult,
+        server::{Server, ServerHandle},
+        types::ErrorObjectOwned,
+        PendingSubscriptionSink, RpcModule, SubscriptionMessage,
+    };
+    use std::net::SocketAddr;
+    use tokio::time::timeout;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Stands in for a celestia node, serving the header methods followed by the connection
+    /// and rejecting every blob submission. It keeps producing headers while it is stopped.
+    #[derive(Clone)]
+    struct StandInNode {
+        generator: Arc<Mutex<ExtendedHeaderGenerator>>,
+        head: Arc<Mutex<Option<ExtendedHeader>>>,
+        headers: broadcast::Sender<ExtendedHeader>,
+        submissions: Arc<AtomicU64>,
+    }
+
+    impl StandInNode {
+        fn new() -> Self {
+            StandInNode {
+                generator: Arc::new(Mutex::new(ExtendedHeaderGenerator::new())),
+                head: Arc::new(Mutex::new(None)),
+                headers: broadcast::channel(16).0,
+                submissions: Arc::new(AtomicU64::new(0)),
+            }
+        }
+
+        fn produce(&self) {
+            let header = self.generator.lock().unwrap().next();
+            *self.head.lock().unwrap() = Some(header.clone());
+            let _ = self.headers.send(header);
+        }
+
+        async fn serve(&self, addr: SocketAddr) -> (SocketAddr, ServerHandle) {
+            let mut module = RpcModule::new(self.clone());
+            module
+                .register_method("header.NetworkHead", |_, node, _| {
+                    Ok::<_, ErrorObjectOwned>(node.head.lock().unwrap().clone().unwrap())
+                })
+                .unwrap();
+            module
+                .register_subscription(
+                    "header.Subscribe",
+                    "header.Subscribe",
+                    "header.Unsubscribe",
+                    |_, pending, node, _| forward_headers(pending, node),
+                )
+                .unwrap();
+            module
+                .register_method("blob.Submit", |_, node, _| {
+                    let attempt = node.submissions.fetch_add(1, Ordering::Relaxed) + 1;
+                    Err::<u64, _>(ErrorObjectOwned::owned(
+                        1,
+                        format!("insufficient fees ({})", attempt),
+                        None::<()>,
+                    ))
+                })
+                .unwrap();
+
+            let server = Server::builder().build(addr).await.unwrap();
+            let addr = server.local_addr().unwrap();
+            (addr, server.start(module))
+        }
+    }
+
+    async fn forward_headers(
+        pending: PendingSubscriptionSink,
+        node: Arc<StandInNode>,
+    ) -> SubscriptionResult {
+        let mut headers = node.headers.subscribe();
+        let sink = pending.accept().await?;
+        loop {
+            tokio::select! {
+                _ = sink.closed() => return Ok(()),
+                header = headers.recv() => {
+                    sink.send(SubscriptionMessage::from_json(&header?)?).await?;
                 }
             }
-        });
-        Ok(())
+        }
+    }
+
+    async fn wait_for(condition: impl Fn() -> bool) {
+        timeout(TIMEOUT, async {
+            while !condition() {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    async fn next_height(heights: &mut broadcast::Receiver<u64>) -> u64 {
+        timeout(TIMEOUT, heights.recv()).await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_reconnects_and_backfills_missed_heights() {
+        let node = StandInNode::new();
+        node.produce();
+        node.produce();
+        let (addr, server) = node.serve("127.0.0.1:0".parse().unwrap()).await;
+
+        let config = CelestiaConfig {
+            connection_string: format!("ws://{}", addr),
+            reconnect_backoff_ms: 10,
+            max_reconnect_backoff_ms: 100,
+            ..CelestiaConfig::default()
+        };
+        let connection = CelestiaConnection::new(&config).await.unwrap();
+        assert_eq!(connection.initialize_sync_target().await.unwrap(), 2);
+        let mut heights = connection.subscribe_to_heights();
+        connection.start().await.unwrap();
+        wait_for(|| connection.health().connected).await;
+
+        node.produce();
+        assert_eq!(next_height(&mut heights).await, 3);
+
+        server.stop().unwrap();
+        server.stopped().await;
+        wait_for(|| !connection.health().connected).await;
+        assert!(connection.health().last_error.is_some());
+
+        // produced while the node is unreachable
+        node.produce();
+        node.produce();
+        let (_, server) = node.serve(addr).await;
+        wait_for(|| connection.health().connected).await;
+        node.produce();
+
+        for expected in 4..=6 {
+            assert_eq!(next_height(&mut heights).await, expected);
+        }
+        assert_eq!(connection.get_latest_height().await.unwrap(), 6);
+        assert_eq!(
+            connection.health(),
+            DaHealth {
+                connected: true,
+                reconnects: 1,
+                last_error: None,
+            }
+        );
+        server.stop().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_submission_errors_are_reported_per_attempt() {
+        let node = StandInNode::new();
+        node.produce();
+        let (addr, server) = node.serve("127.0.0.1:0".parse().unwrap()).await;
+
+        let submission = SubmissionConfig {
+            gas_price: GasPrice::Fixed(0.002),
+            attempts: 3,
+            retry_interval_ms: 10,
+            ..SubmissionConfig::default()
+        };
+        let config = CelestiaConfig {
+            connection_string: format!("ws://{}", addr),
+            transaction_submission: submission.clone(),
+            ..CelestiaConfig::default()
+        };
+        let connection = CelestiaConnection::new(&config).await.unwrap();
+        let blob = Blob::new(connection.operation_namespace, vec![1, 2, 3]).unwrap();
+
+        let err = connection
+            .submit_blobs(&[blob], &submission, "test blob")
+            .await
+            .unwrap_err()
+            .to_string();
+        assert_eq!(node.submissions.load(Ordering::Relaxed), 3);
+        for attempt in 1..=3 {
+            assert!(err.contains(&format!("attempt {}: ", attempt)), "{}", err);
+            assert!(
+                err.contains(&format!("insufficient fees ({})", attempt)),
+                "{}",
+                err
+            );
+        }
+        server.stop().unwrap();
+    }
+
+    #[test]
+    fn test_gas_price_strategies() {
+        assert_eq!(GasPrice::Estimate.for_attempt(1), None);
+        assert_eq!(GasPrice::Fixed(0.002).for_attempt(3), Some(0.002));
+
+        let escalating = GasPrice::Escalating {
+            initial: 0.002,
+            multiplier: 2.0,
+            max: 0.005,
+        };
+        assert_eq!(escalating.for_attempt(1), Some(0.002));
+        assert_eq!(escalating.for_attempt(2), Some(0.004));
+        assert_eq!(escalating.for_attempt(3), Some(0.005));
+    }
+
+    #[test]
+    fn test_resolves_auth_token() {
+        assert_eq!(
+            AuthToken::Inline("token".to_string()).resolve().unwrap(),
+            "token"
+        );
+        assert!(!format!("{:?}", AuthToken::Inline("token".to_string())).contains("token"));
+
+        let path = env::temp_dir().join(format!("prism-auth-token-{}", std::process::id()));
+        fs::write(&path, "file-token\n").unwrap();
+        assert_eq!(
+            AuthToken::File(path.clone()).resolve().unwrap(),
+            "file-token"
+        );
+        fs::remove_file(&path).unwrap();
+        assert!(AuthToken::File(path).resolve().is_err());
+
+        env::set_var("PRISM_TEST_AUTH_TOKEN", "env-token");
+        assert_eq!(
+            AuthToken::Env("PRISM_TEST_AUTH_TOKEN".to_string()).resolve().unwrap(),
+            "env-token"
+        );
+        assert!(AuthToken::Env("PRISM_TEST_MISSING_AUTH_TOKEN".to_string()).resolve().is_err());
+        assert!(AuthToken::Inline(" ".to_string()).resolve().is_err());
     }
 }
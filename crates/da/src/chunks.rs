@@ -0,0 +1,403 @@
+use crate::consts::MAX_CHUNK_DISTANCE;
+use anyhow::{bail, Result};
+use prism_common::digest::Digest;
+use prism_errors::GeneralError;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// Marks the header blob of a payload that is split into chunks. Payloads that fit into one
+/// blob are posted as is. For bincode encoded epochs, the magic could only collide with an
+/// epoch height beyond a billion.
+pub const CHUNKED_HEADER_MAGIC: [u8; 4] = *b"PRSH";
+/// Marks a chunk blob, which is referenced by the hash of the data following the magic.
+pub const CHUNK_MAGIC: [u8; 4] = *b"PRSC";
+/// Version of the chunked header format written by [`Chunks::header`].
+pub const CHUNKED_VERSION: u8 = 2;
+
+/// Where a chunk was included. Chunks are found by the hash of the data following the magic.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChunkRef {
+    pub height: u64,
+    pub hash: Digest,
+}
+
+/// Lists the chunks a payload was split into. It is posted after the chunks, at most
+/// [`MAX_CHUNK_DISTANCE`] heights after them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct ChunkedHeader {
+    length: u64,
+    hash: Digest,
+    chunks: Vec<ChunkRef>,
+}
+
+/// A payload encoded by [`encode_chunked`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Encoded {
+    /// The payload fits into one blob, which is posted as is.
+    Blob(Vec<u8>),
+    /// The payload was split into chunks, see [`Chunks`].
+    Chunks(Chunks),
+}
+
+/// The chunk blobs of a payload. They can be submitted across several transactions, see
+/// [`Chunks::submissions`]. Once they are included, the header blob referencing them is
+/// built with [`Chunks::header`] and posted last.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunks {
+    length: u64,
+    hash: Digest,
+    blobs: Vec<Vec<u8>>,
+}
+
+impl Chunks {
+    pub fn blobs(&self) -> &[Vec<u8>] {
+        &self.blobs
+    }
+
+    /// Groups the chunk blobs, in order, into submissions of at most `max_submission_size`
+    /// bytes.
+    pub fn submissions(&self, max_submission_size: usize) -> Result<Vec<Vec<Vec<u8>>>> {
+        let mut submissions: Vec<Vec<Vec<u8>>> = Vec::new();
+        let mut size = 0;
+        for blob in &self.blobs {
+            if blob.len() > max_submission_size {
+                bail!(GeneralError::EncodingError(format!(
+                    "chunk of {} bytes doesn't fit into a submission of {} bytes",
+                    blob.len(),
+                    max_submission_size
+                )));
+            }
+            match submissions.last_mut() {
+                Some(submission) if size + blob.len() <= max_submission_size => {
+                    submission.push(blob.clone());
+                    size += blob.len();
+                }
+                _ => {
+                    submissions.push(vec![blob.clone()]);
+                    size = blob.len();
+                }
+            }
+        }
+        Ok(submissions)
+    }
+
+    /// Encodes the header blob, given the height each chunk blob was included at.
+    pub fn header(&self, heights: &[u64]) -> Result<Vec<u8>> {
+        if heights.len() != self.blobs.len() {
+            bail!(GeneralError::EncodingError(format!(
+                "got heights for {} of {} chunks",
+                heights.len(),
+                self.blobs.len()
+            )));
+        }
+        let header = ChunkedHeader {
+            length: self.length,
+            hash: self.hash,
+            chunks: self
+                .blobs
+                .iter()
+                .zip(heights)
+                .map(|(blob, height)| ChunkRef {
+                    height: *height,
+                    hash: Digest::hash(&blob[CHUNK_MAGIC.len()..]),
+                })
+                .collect(),
+        };
+
+        let mut header_blob = CHUNKED_HEADER_MAGIC.to_vec();
+        header_blob.push(CHUNKED_VERSION);
+        header_blob.extend(
+            bincode::serialize(&header).map_err(|e| GeneralError::EncodingError(e.to_string()))?,
+        );
+        Ok(header_blob)
+    }
+}
+
+/// Returns `data` as a single blob if it fits into `max_blob_size` bytes. Otherwise it is
+/// split into chunk blobs, whose header has to fit into a blob as well.
+pub fn encode_chunked(data: Vec<u8>, max_blob_size: usize) -> Result<Encoded> {
+    if data.len() <= max_blob_size {
+        return Ok(Encoded::Blob(data));
+    }
+
+    let chunk_size = max_blob_size.saturating_sub(CHUNK_MAGIC.len());
+    if chunk_size == 0 {
+        bail!(GeneralError::EncodingError(format!(
+            "blob size {} is too small for chunks",
+            max_blob_size
+        )));
+    }
+
+    let chunks = Chunks {
+        length: data.len() as u64,
+        hash: Digest::hash(&data),
+        blobs: data
+            .chunks(chunk_size)
+            .map(|chunk| [CHUNK_MAGIC.as_slice(), chunk].concat())
+            .collect(),
+    };
+    // the header's size doesn't depend on the heights
+    let header_size = chunks.header(&vec![0; chunks.blobs.len()])?.len();
+    if header_size > max_blob_size {
+        bail!(GeneralError::EncodingError(format!(
+            "{} bytes need {} chunks, whose header doesn't fit into a blob",
+            data.len(),
+            chunks.blobs.len()
+        )));
+    }
+    Ok(Encoded::Chunks(chunks))
+}
+
+/// Chunk blobs found on the DA layer, by where they were included.
+#[derive(Debug, Default)]
+pub struct ChunkIndex(HashMap<ChunkRef, Vec<u8>>);
+
+impl ChunkIndex {
+    /// Adds the chunks among the blobs found at `height`, other blobs are ignored.
+    pub fn insert<'a>(&mut self, height: u64, blobs: impl IntoIterator<Item = &'a [u8]>) {
+        for chunk in blobs.into_iter().filter_map(|blob| blob.strip_prefix(&CHUNK_MAGIC)) {
+            let hash = Digest::hash(chunk);
+            self.0.insert(ChunkRef { height, hash }, chunk.to_vec());
+        }
+    }
+}
+
+/// Returns the heights other than `height` whose chunks the headers among `blobs`, found at
+/// `height`, reference. Heights more than [`MAX_CHUNK_DISTANCE`] before `height` are left
+/// out, the headers referencing them fail to decode.
+pub fn referenced_heights<'a>(
+    height: u64,
+    blobs: impl IntoIterator<Item = &'a [u8]>,
+) -> BTreeSet<u64> {
+    blobs
+        .into_iter()
+        .filter_map(|blob| parse_header(blob.strip_prefix(&CHUNKED_HEADER_MAGIC)?).ok())
+        .flat_map(|header| header.chunks)
+        .map(|chunk| chunk.height)
+        .filter(|chunk_height| {
+            chunk_height_in_range(*chunk_height, height) && *chunk_height != height
+        })
+        .collect()
+}
+
+/// Reassembles the payloads in `blobs`, which were found at `height`, in the order of their
+/// blob. The chunks are looked up in `chunks`, which has to include the ones at `height` and
+/// at the [`referenced_heights`]. A header whose chunks are missing or don't add up to the
+/// payload it describes yields an error. Chunk blobs themselves are skipped, they are
+/// accounted for by the header posted after them.
+pub fn decode_chunked<'a>(
+    height: u64,
+    blobs: impl IntoIterator<Item = &'a [u8]>,
+    chunks: &ChunkIndex,
+) -> Vec<Result<Vec<u8>>> {
+    let mut payloads = Vec::new();
+    for blob in blobs {
+        if blob.starts_with(&CHUNK_MAGIC) {
+            continue;
+        }
+        match blob.strip_prefix(&CHUNKED_HEADER_MAGIC) {
+            Some(header) => payloads.push(reassemble(height, header, chunks)),
+            None => payloads.push(Ok(blob.to_vec())),
+        }
+    }
+    payloads
+}
+
+fn chunk_height_in_range(chunk_height: u64, height: u64) -> bool {
+    chunk_height <= height && height - chunk_height <= MAX_CHUNK_DISTANCE
+}
+
+fn parse_header(header: &[u8]) -> Result<ChunkedHeader> {
+    let [version, header @ ..] = header else {
+        bail!(GeneralError::DecodingError(
+            "truncated chunked header".to_string()
+        ));
+    };
+    if *version != CHUNKED_VERSION {
+        bail!(GeneralError::DecodingError(format!(
+            "unsupported chunked header version {}",
+            version
+        )));
+    }
+    let header =
+        bincode::deserialize(header).map_err(|e| GeneralError::DecodingError(e.to_string()))?;
+    Ok(header)
+}
+
+fn reassemble(height: u64, header: &[u8], chunks: &ChunkIndex) -> Result<Vec<u8>> {
+    let header = parse_header(header)?;
+
+    let mut parts = Vec::with_capacity(header.chunks.len());
+    for chunk_ref in &header.chunks {
+        if !chunk_height_in_range(chunk_ref.height, height) {
+            bail!(GeneralError::DecodingError(format!(
+                "chunk {} at height {} is out of range of its header at height {}",
+                chunk_ref.hash, chunk_ref.height, height
+            )));
+        }
+        let chunk = chunks.0.get(chunk_ref).ok_or_else(|| {
+            GeneralError::DecodingError(format!(
+                "chunk {} at height {} is missing",
+                chunk_ref.hash, chunk_ref.height
+            ))
+        })?;
+        parts.push(chunk.as_slice());
+    }
+
+    let length: usize = parts.iter().map(|chunk| chunk.len()).sum();
+    if length as u64 != header.length {
+        bail!(GeneralError::DecodingError(format!(
+            "chunks hold {} bytes instead of {}",
+            length, header.length
+        )));
+    }
+    let data = parts.concat();
+    if Digest::hash(&data) != header.hash {
+        bail!(GeneralError::DecodingError(
+            "reassembled chunks don't match the header's hash".to_string()
+        ));
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::{MAX_BLOB_SIZE, MAX_SUBMISSION_SIZE};
+
+    fn chunks(data: &[u8], max_blob_size: usize) -> Chunks {
+        match encode_chunked(data.to_vec(), max_blob_size).unwrap() {
+            Encoded::Chunks(chunks) => chunks,
+            Encoded::Blob(_) => panic!("payload was not chunked"),
+        }
+    }
+
+    /// Posts every chunk at its own height, starting at `first_height`, and the header at
+    /// the height after them.
+    fn post(chunks: &Chunks, first_height: u64) -> (u64, Vec<u8>, ChunkIndex) {
+        let heights: Vec<u64> = (first_height..).take(chunks.blobs().len()).collect();
+        let mut index = ChunkIndex::default();
+        for (blob, height) in chunks.blobs().iter().zip(&heights) {
+            index.insert(*height, [blob.as_slice()]);
+        }
+        let header_height = first_height + heights.len() as u64;
+        (header_height, chunks.header(&heights).unwrap(), index)
+    }
+
+    #[test]
+    fn test_small_payloads_are_not_chunked() {
+        let data = vec![7; 100];
+        assert_eq!(
+            encode_chunked(data.clone(), 100).unwrap(),
+            Encoded::Blob(data.clone())
+        );
+        let payloads = decode_chunked(1, [data.as_slice()], &ChunkIndex::default());
+        assert_eq!(payloads.into_iter().next().unwrap().unwrap(), data);
+    }
+
+    #[test]
+    fn test_chunked_roundtrip_across_heights() {
+        let data: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        let chunks = chunks(&data, 300);
+        assert_eq!(chunks.blobs().len(), 4);
+        assert!(chunks.blobs().iter().all(|blob| blob.len() <= 300));
+
+        let (height, header, index) = post(&chunks, 10);
+        assert_eq!(
+            referenced_heights(height, [header.as_slice()]),
+            BTreeSet::from([10, 11, 12, 13])
+        );
+
+        // chunks at the header's height are skipped, other payloads are kept
+        let other = vec![1; 10];
+        let blobs = [
+            chunks.blobs()[0].as_slice(),
+            header.as_slice(),
+            other.as_slice(),
+        ];
+        let payloads: Vec<Vec<u8>> = decode_chunked(height, blobs, &index)
+            .into_iter()
+            .map(|payload| payload.unwrap())
+            .collect();
+        assert_eq!(payloads, vec![data, other]);
+    }
+
+    #[test]
+    fn test_payload_larger_than_a_submission() {
+        let data: Vec<u8> = (0..3 * MAX_SUBMISSION_SIZE).map(|i| i as u8).collect();
+        let chunks = chunks(&data, MAX_BLOB_SIZE);
+
+        let submissions = chunks.submissions(MAX_SUBMISSION_SIZE).unwrap();
+        assert_eq!(submissions.len(), chunks.blobs().len());
+        assert!(submissions.iter().all(|submission| submission
+            .iter()
+            .map(Vec::len)
+            .sum::<usize>()
+            <= MAX_SUBMISSION_SIZE));
+
+        let (height, header, index) = post(&chunks, 1);
+        assert!(header.len() <= MAX_BLOB_SIZE);
+        let payloads = decode_chunked(height, [header.as_slice()], &index);
+        assert_eq!(payloads.into_iter().next().unwrap().unwrap(), data);
+    }
+
+    #[test]
+    fn test_small_chunks_share_a_submission() {
+        let chunks = chunks(&[0; 1000], 300);
+        let submissions = chunks.submissions(600).unwrap();
+        assert_eq!(
+            submissions.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![2, 2]
+        );
+        assert!(chunks.submissions(200).is_err());
+    }
+
+    #[test]
+    fn test_rejects_incomplete_or_tampered_chunks() {
+        let data: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        let chunks = chunks(&data, 300);
+        let (height, header, _) = post(&chunks, 10);
+        let decode = |index: &ChunkIndex| {
+            let payloads = decode_chunked(height, [header.as_slice()], index);
+            assert_eq!(payloads.len(), 1);
+            payloads.into_iter().next().unwrap()
+        };
+
+        // a missing chunk fails the payload it belongs to
+        let mut index = ChunkIndex::default();
+        for (i, blob) in chunks.blobs().iter().enumerate().skip(1) {
+            index.insert(10 + i as u64, [blob.as_slice()]);
+        }
+        assert!(decode(&index).is_err());
+
+        // a tampered chunk no longer matches its hash
+        let mut tampered = chunks.blobs()[0].clone();
+        tampered[10] ^= 1;
+        index.insert(10, [tampered.as_slice()]);
+        assert!(decode(&index).is_err());
+
+        // a chunk at another height than referenced
+        index.insert(9, [chunks.blobs()[0].as_slice()]);
+        assert!(decode(&index).is_err());
+
+        index.insert(10, [chunks.blobs()[0].as_slice()]);
+        assert_eq!(decode(&index).unwrap(), data);
+    }
+
+    #[test]
+    fn test_rejects_chunks_too_far_back() {
+        let chunks = chunks(&[0; 1000], 300);
+        let (height, header, index) = post(&chunks, 10);
+        let late = height + MAX_CHUNK_DISTANCE;
+
+        assert!(referenced_heights(late, [header.as_slice()]).len() < chunks.blobs().len());
+        assert!(decode_chunked(late, [header.as_slice()], &index)[0].is_err());
+    }
+
+    #[test]
+    fn test_rejects_too_small_blob_size() {
+        assert!(encode_chunked(vec![0; 100], CHUNK_MAGIC.len()).is_err());
+        // the header referencing 25 chunks doesn't fit into a blob
+        assert!(encode_chunked(vec![0; 100], 8).is_err());
+    }
+}
@@ -6,3 +6,13 @@ pub const DA_RETRY_COUNT: u64 = 5;
 pub const DA_RETRY_INTERVAL: Duration = Duration::from_secs(5);
 /// CHANNEL_BUFFER_SIZE determines the default channel size.
 pub const CHANNEL_BUFFER_SIZE: usize = 5;
+/// MAX_BLOB_SIZE is the largest blob prism submits to the DA layer, in bytes. It stays below
+/// celestia's limit for a single blob so that a blob always fits into one block.
+pub const MAX_BLOB_SIZE: usize = 1_500_000;
+/// MAX_SUBMISSION_SIZE is the largest total size of the blobs prism submits in one transaction,
+/// in bytes. It stays below celestia-app's limit of 2 MiB per transaction, larger epochs are
+/// split into chunks that are submitted in several transactions.
+pub const MAX_SUBMISSION_SIZE: usize = 1_900_000;
+/// MAX_CHUNK_DISTANCE is how many heights the header of a chunked epoch may be posted after its
+/// chunks. It bounds the heights fetched to reassemble an epoch.
+pub const MAX_CHUNK_DISTANCE: u64 = 100;
@@ -0,0 +1,291 @@
+//! Client for a local DA devnet, as served by the `prism-da-devnet` binary. The devnet runs an
+//! [`InMemoryDataAvailabilityLayer`](crate::memory::InMemoryDataAvailabilityLayer) in its own
+//! process, so provers, full nodes and light clients can share it without a Celestia network.
+//!
+//! The devnet serves JSON over HTTP:
+//!
+//! - `GET /height` returns the latest height
+//! - `GET /heights` upgrades to a websocket that sends every new height as text
+//! - `GET /epochs/:height` returns the hex encoded snark namespace blobs at a height
+//! - `POST /epochs` submits a [`FinalizedEpoch`]
+//! - `GET /checkpoints/:height` and `POST /checkpoints` for [`Checkpoint`]s
+//! - `GET /transactions/:height` and `POST /transactions` for lists of [`Transaction`]s
+//!
+//! Submissions answer with the height they were submitted at.
+
+use crate::{
+    fetch_epochs, publish_heights_up_to, Checkpoint, DaHealth, DataAvailabilityLayer,
+    FinalizedEpoch, SkippedEpochBlobCounter, SkippedEpochBlobs,
+};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use ed25519_consensus::VerificationKey as VerifyingKey;
+use futures_util::StreamExt;
+use log::{error, info, warn};
+use prism_common::transaction::Transaction;
+use prism_errors::DataAvailabilityError;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tokio::{sync::broadcast, task::spawn, time::sleep};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DevnetConfig {
+    /// HTTP URL of the devnet.
+    pub url: String,
+    /// Delay before reconnecting after the height stream dropped.
+    #[serde(default = "default_reconnect_interval_ms")]
+    pub reconnect_interval_ms: u64,
+}
+
+fn default_reconnect_interval_ms() -> u64 {
+    1_000
+}
+
+impl Default for DevnetConfig {
+    fn default() -> Self {
+        DevnetConfig {
+            url: "http://127.0.0.1:26660".to_string(),
+            reconnect_interval_ms: default_reconnect_interval_ms(),
+        }
+    }
+}
+
+/// Blobs posted to the snark namespace at one height, see [`FinalizedEpoch::encode`].
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct EpochBlobs {
+    /// Hex encoded blobs, in the order they were included.
+    pub blobs: Vec<String>,
+}
+
+pub struct DevnetConnection {
+    client: reqwest::Client,
+    url: String,
+    reconnect_interval: Duration,
+    height_update_tx: broadcast::Sender<u64>,
+    sync_target: Arc<AtomicU64>,
+    skipped_epoch_blobs: SkippedEpochBlobCounter,
+    health: Arc<Mutex<DaHealth>>,
+}
+
+impl DevnetConnection {
+    pub fn new(config: &DevnetConfig) -> Self {
+        let (height_update_tx, _) = broadcast::channel(100);
+        DevnetConnection {
+            client: reqwest::Client::new(),
+            url: config.url.trim_end_matches('/').to_string(),
+            reconnect_interval: Duration::from_millis(config.reconnect_interval_ms),
+            height_update_tx,
+            sync_target: Arc::new(AtomicU64::new(0)),
+            skipped_epoch_blobs: SkippedEpochBlobCounter::default(),
+            health: Arc::new(Mutex::new(DaHealth::default())),
+        }
+    }
+
+    async fn get<T: DeserializeOwned>(&self, height: u64, path: &str) -> Result<T> {
+        let response = async {
+            self.client
+                .get(format!("{}{}", self.url, path))
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await
+        }
+        .await
+        .map_err(|e| {
+            anyhow!(DataAvailabilityError::DataRetrievalError(
+                height,
+                format!("getting {} from devnet: {}", path, e)
+            ))
+        })?;
+        Ok(response)
+    }
+
+    async fn get_epoch_blobs(&self, height: u64) -> Result<Vec<Vec<u8>>> {
+        let EpochBlobs { blobs } = self.get(height, &format!("/epochs/{}", height)).await?;
+        blobs.iter().map(hex::decode).collect::<Result<Vec<_>, _>>().context(format!(
+            "Invalid epoch blobs from devnet at height {}",
+            height
+        ))
+    }
+
+    async fn submit<T: Serialize>(&self, path: &str, body: &T) -> Result<u64> {
+        let response = self
+            .client
+            .post(format!("{}{}", self.url, path))
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| anyhow!(DataAvailabilityError::SubmissionError(e.to_string())))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let reason = response.text().await.unwrap_or_default();
+            return Err(anyhow!(DataAvailabilityError::SubmissionError(format!(
+                "devnet rejected {} with {}: {}",
+                path, status, reason
+            ))));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| anyhow!(DataAvailabilityError::SubmissionError(e.to_string())))
+    }
+}
+
+/// Publishes the heights streamed by the devnet, and reconnects when the stream drops.
+struct HeightFollower {
+    client: reqwest::Client,
+    url: String,
+    reconnect_interval: Duration,
+    height_update_tx: broadcast::Sender<u64>,
+    sync_target: Arc<AtomicU64>,
+    health: Arc<Mutex<DaHealth>>,
+}
+
+impl HeightFollower {
+    async fn run(self) {
+        let mut connected_before = false;
+        loop {
+            if let Err(e) = self.follow_heights(&mut connected_before).await {
+                self.disconnected(format!("{:#}", e));
+            } else {
+                self.disconnected("height stream ended".to_string());
+            }
+            sleep(self.reconnect_interval).await;
+        }
+    }
+
+    async fn follow_heights(&self, connected_before: &mut bool) -> Result<()> {
+        let ws_url = format!("{}/heights", self.url.replacen("http", "ws", 1));
+        let (mut stream, _) =
+            connect_async(ws_url.as_str()).await.context("Failed to connect to height stream")?;
+        // catches up on heights produced while the stream was down
+        let head: u64 = self
+            .client
+            .get(format!("{}/height", self.url))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .context("Failed to get latest height from devnet")?;
+
+        {
+            let mut health = self.health.lock().unwrap();
+            health.connected = true;
+            health.last_error = None;
+            if *connected_before {
+                health.reconnects += 1;
+                info!("reconnected to devnet at {}", self.url);
+            }
+        }
+        *connected_before = true;
+        publish_heights_up_to(&self.sync_target, &self.height_update_tx, head);
+
+        while let Some(message) = stream.next().await {
+            match message.context("Failed to read from height stream")? {
+                Message::Text(text) => match text.parse() {
+                    Ok(height) => {
+                        publish_heights_up_to(&self.sync_target, &self.height_update_tx, height)
+                    }
+                    Err(e) => error!("Invalid height '{}' from devnet: {}", text, e),
+                },
+                Message::Close(_) => break,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn disconnected(&self, reason: String) {
+        warn!("lost connection to devnet: {}", reason);
+        let mut health = self.health.lock().unwrap();
+        health.connected = false;
+        health.last_error = Some(reason);
+    }
+}
+
+#[async_trait]
+impl DataAvailabilityLayer for DevnetConnection {
+    async fn get_latest_height(&self) -> Result<u64> {
+        Ok(self.sync_target.load(Ordering::Relaxed))
+    }
+
+    async fn initialize_sync_target(&self) -> Result<u64> {
+        let height = self.get(0, "/height").await?;
+        self.sync_target.store(height, Ordering::Relaxed);
+        Ok(height)
+    }
+
+    async fn get_finalized_epochs(
+        &self,
+        height: u64,
+        provers: &[VerifyingKey],
+    ) -> Result<Vec<FinalizedEpoch>> {
+        let blobs = self.get_epoch_blobs(height).await?;
+        let epochs = fetch_epochs(height, &blobs, |chunk_height| {
+            self.get_epoch_blobs(chunk_height)
+        })
+        .await?;
+        Ok(self.skipped_epoch_blobs.filter(height, epochs, provers))
+    }
+
+    fn skipped_epoch_blobs(&self) -> SkippedEpochBlobs {
+        self.skipped_epoch_blobs.get()
+    }
+
+    async fn submit_finalized_epoch(&self, epoch: FinalizedEpoch) -> Result<u64> {
+        self.submit("/epochs", &epoch).await
+    }
+
+    async fn get_checkpoints(
+        &self,
+        height: u64,
+        provers: &[VerifyingKey],
+    ) -> Result<Vec<Checkpoint>> {
+        let checkpoints: Vec<Checkpoint> =
+            self.get(height, &format!("/checkpoints/{}", height)).await?;
+        Ok(self.skipped_epoch_blobs.filter(height, checkpoints.into_iter().map(Ok), provers))
+    }
+
+    async fn submit_checkpoint(&self, checkpoint: Checkpoint) -> Result<u64> {
+        self.submit("/checkpoints", &checkpoint).await
+    }
+
+    async fn get_transactions(&self, height: u64) -> Result<Vec<Transaction>> {
+        self.get(height, &format!("/transactions/{}", height)).await
+    }
+
+    async fn submit_transactions(&self, transactions: Vec<Transaction>) -> Result<u64> {
+        self.submit("/transactions", &transactions).await
+    }
+
+    async fn start(&self) -> Result<()> {
+        let follower = HeightFollower {
+            client: self.client.clone(),
+            url: self.url.clone(),
+            reconnect_interval: self.reconnect_interval,
+            height_update_tx: self.height_update_tx.clone(),
+            sync_target: self.sync_target.clone(),
+            health: self.health.clone(),
+        };
+        spawn(follower.run());
+        Ok(())
+    }
+
+    fn subscribe_to_heights(&self) -> broadcast::Receiver<u64> {
+        self.height_update_tx.subscribe()
+    }
+
+    fn health(&self) -> DaHealth {
+        self.health.lock().unwrap().clone()
+    }
+}
@@ -1,13 +1,21 @@
-use anyhow::Result;
+use anyhow::{ensure, Context, Result};
 use async_trait::async_trait;
 use ed25519_consensus::{Signature, SigningKey, VerificationKey as VerifyingKey};
+use log::{debug, trace};
 use prism_common::{digest::Digest, transaction::Transaction};
 use serde::{Deserialize, Serialize};
 use sp1_sdk::SP1ProofWithPublicValues;
+use std::{
+    future::Future,
+    sync::atomic::{AtomicU64, Ordering},
+};
 use tokio::sync::broadcast;
 
+pub mod batch;
 pub mod celestia;
+pub mod chunks;
 pub mod consts;
+pub mod devnet;
 pub mod memory;
 
 // FinalizedEpoch is the data structure that represents the finalized epoch data, and is posted to the DA layer.
@@ -16,47 +24,474 @@ pub struct FinalizedEpoch {
     pub height: u64,
     pub prev_commitment: Digest,
     pub current_commitment: Digest,
+    /// Number of buffered transactions, in DA order, applied in this epoch. Transactions
+    /// beyond it carry over to the next epoch.
+    pub transaction_count: u64,
     pub proof: SP1ProofWithPublicValues,
     pub signature: Option<String>,
 }
 
 impl FinalizedEpoch {
+    /// Returns the part of the epoch covered by its signature.
+    pub fn header(&self) -> Result<EpochHeader> {
+        let proof = bincode::serialize(&self.proof)
+            .map_err(|e| anyhow::anyhow!("Failed to serialize epoch proof: {}", e))?;
+
+        Ok(EpochHeader {
+            height: self.height,
+            prev_commitment: self.prev_commitment,
+            current_commitment: self.current_commitment,
+            transaction_count: self.transaction_count,
+            proof_hash: Digest::hash(proof),
+        })
+    }
+
+    /// Returns the signed header, or `None` if the epoch is not signed.
+    pub fn signed_header(&self) -> Result<Option<SignedEpochHeader>> {
+        let Some(signature) = &self.signature else {
+            return Ok(None);
+        };
+
+        Ok(Some(SignedEpochHeader {
+            header: self.header()?,
+            signature: signature.clone(),
+        }))
+    }
+
     pub fn insert_signature(&mut self, key: &SigningKey) {
-        let plaintext = bincode::serialize(&self).unwrap();
+        let plaintext = bincode::serialize(&self.header().unwrap()).unwrap();
         let signature = key.sign(&plaintext);
         self.signature = Some(hex::encode(signature.to_bytes()));
     }
 
     pub fn verify_signature(&self, vk: VerifyingKey) -> Result<()> {
-        let epoch_without_signature = FinalizedEpoch {
-            height: self.height,
-            prev_commitment: self.prev_commitment,
-            current_commitment: self.current_commitment,
-            proof: self.proof.clone(),
+        let message = bincode::serialize(&self.header()?)
+            .map_err(|e| anyhow::anyhow!("Failed to serialize epoch header: {}", e))?;
+
+        verify_hex_signature(vk, self.signature.as_ref(), &message)
+    }
+
+    /// Encodes the epoch into a blob of at most `max_blob_size` bytes. Epochs that don't fit
+    /// into one blob are split into chunks, see [`chunks::encode_chunked`].
+    pub fn encode(&self, max_blob_size: usize) -> Result<chunks::Encoded> {
+        let data = bincode::serialize(self)
+            .map_err(|e| anyhow::anyhow!("Failed to serialize epoch {}: {}", self.height, e))?;
+        chunks::encode_chunked(data, max_blob_size)
+            .with_context(|| format!("Failed to split epoch {} into blobs", self.height))
+    }
+
+    /// Decodes the epochs in the snark namespace blobs found at `height`, reassembling the
+    /// chunked ones from `chunks`, see [`chunks::decode_chunked`]. Every blob that doesn't
+    /// belong to a valid epoch yields an error.
+    pub fn from_blobs<'a>(
+        height: u64,
+        blobs: impl IntoIterator<Item = &'a [u8]>,
+        chunks: &chunks::ChunkIndex,
+    ) -> Vec<Result<Self>> {
+        chunks::decode_chunked(height, blobs, chunks)
+            .into_iter()
+            .map(|data| {
+                bincode::deserialize(&data?).map_err(|e| {
+                    anyhow::anyhow!("Failed to decode blob into FinalizedEpoch: {}", e)
+                })
+            })
+            .collect()
+    }
+}
+
+/// Submits `epoch` with `submit`, which submits blobs in one transaction and returns the height
+/// they were included at. The chunks of an epoch larger than `max_blob_size` are submitted
+/// in transactions of at most `max_submission_size` bytes, followed by the header referencing
+/// them. Returns the height of the epoch's last blob.
+pub(crate) async fn submit_epoch_blobs<F, Fut>(
+    epoch: &FinalizedEpoch,
+    max_blob_size: usize,
+    max_submission_size: usize,
+    submit: F,
+) -> Result<u64>
+where
+    F: Fn(Vec<Vec<u8>>) -> Fut,
+    Fut: Future<Output = Result<u64>>,
+{
+    let chunks = match epoch.encode(max_blob_size)? {
+        chunks::Encoded::Blob(blob) => return submit(vec![blob]).await,
+        chunks::Encoded::Chunks(chunks) => chunks,
+    };
+
+    let mut heights = Vec::with_capacity(chunks.blobs().len());
+    for submission in chunks.submissions(max_submission_size)? {
+        let count = submission.len();
+        let height = submit(submission).await?;
+        heights.extend(std::iter::repeat(height).take(count));
+    }
+    debug!(
+        "posted epoch {} in {} chunks up to height {:?}, posting its header",
+        epoch.height,
+        heights.len(),
+        heights.last()
+    );
+    submit(vec![chunks.header(&heights)?]).await
+}
+
+/// Decodes the epochs in the snark namespace `blobs` found at `height`. The chunks of chunked
+/// epochs posted at earlier heights are fetched with `fetch`, which returns the snark
+/// namespace blobs at a height.
+pub(crate) async fn fetch_epochs<F, Fut>(
+    height: u64,
+    blobs: &[Vec<u8>],
+    fetch: F,
+) -> Result<Vec<Result<FinalizedEpoch>>>
+where
+    F: Fn(u64) -> Fut,
+    Fut: Future<Output = Result<Vec<Vec<u8>>>>,
+{
+    let mut index = chunks::ChunkIndex::default();
+    index.insert(height, blobs.iter().map(Vec::as_slice));
+    for chunk_height in chunks::referenced_heights(height, blobs.iter().map(Vec::as_slice)) {
+        let chunk_blobs = fetch(chunk_height)
+            .await
+            .with_context(|| format!("Failed to fetch chunks at height {}", chunk_height))?;
+        index.insert(chunk_height, chunk_blobs.iter().map(Vec::as_slice));
+    }
+    Ok(FinalizedEpoch::from_blobs(
+        height,
+        blobs.iter().map(Vec::as_slice),
+        &index,
+    ))
+}
+
+/// The fields of a [`FinalizedEpoch`] signed by the prover. The proof is only committed to by
+/// its hash, so signed headers can be passed around and checked without it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EpochHeader {
+    pub height: u64,
+    pub prev_commitment: Digest,
+    pub current_commitment: Digest,
+    pub transaction_count: u64,
+    pub proof_hash: Digest,
+}
+
+impl EpochHeader {
+    /// Whether both headers are for the same epoch but disagree on its state transition.
+    /// Proofs are not deterministic, so headers that only differ in the proof hash are the
+    /// same epoch proven twice, e.g. after the prover restarted before its epoch was included.
+    pub fn conflicts_with(&self, other: &EpochHeader) -> bool {
+        self.height == other.height
+            && (self.prev_commitment != other.prev_commitment
+                || self.current_commitment != other.current_commitment
+                || self.transaction_count != other.transaction_count)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SignedEpochHeader {
+    pub header: EpochHeader,
+    /// Hex encoded signature of the prover over the bincode encoded header.
+    pub signature: String,
+}
+
+impl SignedEpochHeader {
+    pub fn verify_signature(&self, vk: VerifyingKey) -> Result<()> {
+        let message = bincode::serialize(&self.header)
+            .map_err(|e| anyhow::anyhow!("Failed to serialize epoch header: {}", e))?;
+
+        verify_hex_signature(vk, Some(&self.signature), &message)
+    }
+}
+
+/// [`EquivocationEvidence`] shows that the prover signed two different epochs of the same
+/// height. It only needs the prover's verifying key to be checked, so it can be handed to
+/// other nodes as is.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EquivocationEvidence {
+    pub first: SignedEpochHeader,
+    pub second: SignedEpochHeader,
+}
+
+impl EquivocationEvidence {
+    /// Searches `epochs` for two conflicting epochs of the same height that are both signed by
+    /// `vk`, see [`EpochHeader::conflicts_with`]. Epochs without a valid signature are ignored.
+    pub fn find(epochs: &[FinalizedEpoch], vk: VerifyingKey) -> Result<Option<Self>> {
+        let mut signed: Vec<SignedEpochHeader> = Vec::new();
+        for epoch in epochs {
+            if epoch.verify_signature(vk).is_err() {
+                continue;
+            }
+            let Some(header) = epoch.signed_header()? else {
+                continue;
+            };
+
+            let conflicting =
+                signed.iter().find(|other| other.header.conflicts_with(&header.header));
+            if let Some(other) = conflicting {
+                return Ok(Some(EquivocationEvidence {
+                    first: other.clone(),
+                    second: header,
+                }));
+            }
+            signed.push(header);
+        }
+        Ok(None)
+    }
+
+    pub fn epoch_height(&self) -> u64 {
+        self.first.header.height
+    }
+
+    /// Checks that both headers are signed by `vk` and conflict with each other.
+    pub fn verify(&self, vk: VerifyingKey) -> Result<()> {
+        ensure!(
+            self.first.header.height == self.second.header.height,
+            "headers are for different epochs: {} and {}",
+            self.first.header.height,
+            self.second.header.height
+        );
+        ensure!(
+            self.first.header.conflicts_with(&self.second.header),
+            "headers do not conflict"
+        );
+        self.first.verify_signature(vk)?;
+        self.second.verify_signature(vk)
+    }
+}
+
+/// Prefixes the message signed for a [`Checkpoint`], so that the signature can't be passed off
+/// as a signature over other data the prover signs with the same key.
+pub const CHECKPOINT_CONTEXT: &[u8] = b"prism-checkpoint-v1";
+
+/// [`Checkpoint`] proves the whole chain of epochs up to `epoch_height` at once. It is posted
+/// to its own namespace so light clients can find it without fetching every epoch.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Checkpoint {
+    /// Height of the first epoch that is not covered by this checkpoint.
+    pub epoch_height: u64,
+    pub first_commitment: Digest,
+    pub last_commitment: Digest,
+    pub proof: SP1ProofWithPublicValues,
+    /// Hex encoded signature of the prover over [`CHECKPOINT_CONTEXT`], followed by the bincode
+    /// encoded checkpoint without its signature.
+    pub signature: Option<String>,
+}
+
+impl Checkpoint {
+    pub fn insert_signature(&mut self, key: &SigningKey) {
+        let plaintext = self.message().unwrap();
+        let signature = key.sign(&plaintext);
+        self.signature = Some(hex::encode(signature.to_bytes()));
+    }
+
+    pub fn verify_signature(&self, vk: VerifyingKey) -> Result<()> {
+        let message = self.message()?;
+        verify_hex_signature(vk, self.signature.as_ref(), &message)
+    }
+
+    fn message(&self) -> Result<Vec<u8>> {
+        let checkpoint_without_signature = Checkpoint {
             signature: None,
+            ..self.clone()
         };
+        let encoded = bincode::serialize(&checkpoint_without_signature)
+            .map_err(|e| anyhow::anyhow!("Failed to serialize checkpoint: {}", e))?;
+        Ok([CHECKPOINT_CONTEXT, encoded.as_slice()].concat())
+    }
+
+    /// Decodes a blob of the checkpoint namespace.
+    pub fn from_blob(data: &[u8]) -> Result<Self> {
+        bincode::deserialize(data)
+            .map_err(|e| anyhow::anyhow!("Failed to decode blob into Checkpoint: {}", e))
+    }
+}
+
+/// Prefixes the message signed for a [`SignedCommitment`], so that the signature can't be
+/// passed off as a signature over other data the node signs with the same key.
+pub const SIGNED_COMMITMENT_CONTEXT: &[u8] = b"prism-signed-commitment-v1";
+
+/// [`SignedCommitment`] is a node's statement that its state has `commitment` after `epoch`
+/// epochs. Light clients use it to cross-check a trusted checkpoint before syncing from it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SignedCommitment {
+    pub epoch: u64,
+    pub commitment: Digest,
+    /// Hex encoded signature of the node over [`SIGNED_COMMITMENT_CONTEXT`], followed by the
+    /// bincode encoded epoch and commitment.
+    pub signature: String,
+}
+
+impl SignedCommitment {
+    pub fn new(epoch: u64, commitment: Digest, key: &SigningKey) -> Result<Self> {
+        let plaintext = Self::message(epoch, commitment)?;
+        Ok(SignedCommitment {
+            epoch,
+            commitment,
+            signature: hex::encode(key.sign(&plaintext).to_bytes()),
+        })
+    }
+
+    pub fn verify_signature(&self, vk: VerifyingKey) -> Result<()> {
+        let message = Self::message(self.epoch, self.commitment)?;
+        verify_hex_signature(vk, Some(&self.signature), &message)
+    }
+
+    fn message(epoch: u64, commitment: Digest) -> Result<Vec<u8>> {
+        let encoded = bincode::serialize(&(epoch, commitment))
+            .map_err(|e| anyhow::anyhow!("Failed to serialize commitment: {}", e))?;
+        Ok([SIGNED_COMMITMENT_CONTEXT, encoded.as_slice()].concat())
+    }
+}
+
+fn verify_hex_signature(
+    vk: VerifyingKey,
+    signature: Option<&String>,
+    message: &[u8],
+) -> Result<()> {
+    let signature = signature.ok_or_else(|| anyhow::anyhow!("No signature present"))?;
+
+    let signature_bytes =
+        hex::decode(signature).map_err(|e| anyhow::anyhow!("Failed to decode signature: {}", e))?;
+
+    if signature_bytes.len() != 64 {
+        return Err(anyhow::anyhow!("Invalid signature length"));
+    }
 
-        let message = bincode::serialize(&epoch_without_signature)
-            .map_err(|e| anyhow::anyhow!("Failed to serialize epoch: {}", e))?;
+    let signature: Signature = signature_bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Invalid signature length"))?;
 
-        let signature =
-            self.signature.as_ref().ok_or_else(|| anyhow::anyhow!("No signature present"))?;
+    vk.verify(&signature, message)
+        .map_err(|e| anyhow::anyhow!("Signature verification failed: {}", e))?;
+    Ok(())
+}
+
+/// Numbers of blobs in the snark and checkpoint namespaces that were skipped because they are
+/// not epochs or checkpoints signed by a prover, since the DA layer was started.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedEpochBlobs {
+    pub undecodable: u64,
+    pub invalid_signature: u64,
+}
+
+/// Connection state of a DA layer, see [`DataAvailabilityLayer::health`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DaHealth {
+    /// Whether new heights are currently received from the DA layer.
+    pub connected: bool,
+    /// Number of times the connection was re-established since the DA layer was started.
+    pub reconnects: u64,
+    /// Why the connection was lost, while it is down.
+    pub last_error: Option<String>,
+}
+
+/// Blobs that anyone can post, but that are only used if signed by a prover.
+pub(crate) trait ProverSigned {
+    /// What the blob is called in logs.
+    const KIND: &'static str;
+
+    fn verify_signature(&self, vk: VerifyingKey) -> Result<()>;
+    fn epoch_height(&self) -> u64;
+}
 
-        let signature_bytes = hex::decode(signature)
-            .map_err(|e| anyhow::anyhow!("Failed to decode signature: {}", e))?;
+impl ProverSigned for FinalizedEpoch {
+    const KIND: &'static str = "epoch";
 
-        if signature_bytes.len() != 64 {
-            return Err(anyhow::anyhow!("Invalid signature length"));
+    fn verify_signature(&self, vk: VerifyingKey) -> Result<()> {
+        FinalizedEpoch::verify_signature(self, vk)
+    }
+
+    fn epoch_height(&self) -> u64 {
+        self.height
+    }
+}
+
+impl ProverSigned for Checkpoint {
+    const KIND: &'static str = "checkpoint";
+
+    fn verify_signature(&self, vk: VerifyingKey) -> Result<()> {
+        Checkpoint::verify_signature(self, vk)
+    }
+
+    fn epoch_height(&self) -> u64 {
+        self.epoch_height
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct SkippedEpochBlobCounter {
+    undecodable: AtomicU64,
+    invalid_signature: AtomicU64,
+}
+
+impl SkippedEpochBlobCounter {
+    pub(crate) fn get(&self) -> SkippedEpochBlobs {
+        SkippedEpochBlobs {
+            undecodable: self.undecodable.load(Ordering::Relaxed),
+            invalid_signature: self.invalid_signature.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Keeps the decodable candidates at `height` that are signed by one of `provers`, or all
+    /// of them if no prover is given, and counts the others.
+    pub(crate) fn filter<T: ProverSigned>(
+        &self,
+        height: u64,
+        candidates: impl IntoIterator<Item = Result<T>>,
+        provers: &[VerifyingKey],
+    ) -> Vec<T> {
+        let mut blobs = Vec::new();
+        for candidate in candidates {
+            let blob = match candidate {
+                Ok(blob) => blob,
+                Err(e) => {
+                    self.undecodable.fetch_add(1, Ordering::Relaxed);
+                    debug!(
+                        "skipping undecodable {} blob at height {}: {:?}",
+                        T::KIND,
+                        height,
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            if !provers.is_empty() && !provers.iter().any(|vk| blob.verify_signature(*vk).is_ok()) {
+                self.invalid_signature.fetch_add(1, Ordering::Relaxed);
+                debug!(
+                    "skipping {} for epoch {} at height {} without a valid prover signature",
+                    T::KIND,
+                    blob.epoch_height(),
+                    height
+                );
+                continue;
+            }
+            blobs.push(blob);
         }
+        blobs
+    }
+}
 
-        let signature: Signature = signature_bytes
-            .as_slice()
-            .try_into()
-            .map_err(|_| anyhow::anyhow!("Invalid signature length"))?;
+/// Publishes the heights after `sync_target` up to `height` in order, so subscribers see every
+/// height even if the DA layer skipped some, e.g. while reconnecting. A long backfill can
+/// exceed the channel capacity, see [`DataAvailabilityLayer::subscribe_to_heights`].
+pub(crate) fn publish_heights_up_to(
+    sync_target: &AtomicU64,
+    height_update_tx: &broadcast::Sender<u64>,
+    height: u64,
+) {
+    let last = sync_target.load(Ordering::Relaxed);
+    if height <= last {
+        return;
+    }
+    // without a sync target, there is nothing to backfill
+    let first = if last == 0 { height } else { last + 1 };
+    if first < height {
+        debug!("backfilling heights {} to {}", first, height - 1);
+    }
 
-        vk.verify(&signature, &message)
-            .map_err(|e| anyhow::anyhow!("Signature verification failed: {}", e))?;
-        Ok(())
+    for height in first..=height {
+        sync_target.store(height, Ordering::Relaxed);
+        // sending only fails without subscribers, new ones start from the sync target
+        let _ = height_update_tx.send(height);
+        trace!("updated sync target for height {}", height);
     }
 }
 
@@ -64,10 +499,34 @@ impl FinalizedEpoch {
 pub trait DataAvailabilityLayer: Send + Sync {
     async fn get_latest_height(&self) -> Result<u64>;
     async fn initialize_sync_target(&self) -> Result<u64>;
-    async fn get_finalized_epoch(&self, height: u64) -> Result<Option<FinalizedEpoch>>;
+    /// Returns the epochs posted at `height` that are signed by one of `provers`, in the order
+    /// they were included. Anyone can post to the snark namespace, so other blobs are skipped
+    /// and counted, see [`DataAvailabilityLayer::skipped_epoch_blobs`]. Without `provers`,
+    /// every decodable epoch is returned.
+    async fn get_finalized_epochs(
+        &self,
+        height: u64,
+        provers: &[VerifyingKey],
+    ) -> Result<Vec<FinalizedEpoch>>;
+    fn skipped_epoch_blobs(&self) -> SkippedEpochBlobs;
     async fn submit_finalized_epoch(&self, epoch: FinalizedEpoch) -> Result<u64>;
+    /// Returns the checkpoints posted at `height` that are signed by one of `provers`. Like
+    /// epochs, other blobs in the checkpoint namespace are skipped and counted.
+    async fn get_checkpoints(
+        &self,
+        height: u64,
+        provers: &[VerifyingKey],
+    ) -> Result<Vec<Checkpoint>>;
+    async fn submit_checkpoint(&self, checkpoint: Checkpoint) -> Result<u64>;
     async fn get_transactions(&self, height: u64) -> Result<Vec<Transaction>>;
     async fn submit_transactions(&self, transactions: Vec<Transaction>) -> Result<u64>;
     async fn start(&self) -> Result<()>;
+    /// Subscribes to new heights, which are published in order and without gaps. Subscribers
+    /// that fall behind by more than the channel capacity, e.g. during a backfill after a
+    /// reconnect, receive [`broadcast::error::RecvError::Lagged`] and have to catch up to
+    /// [`DataAvailabilityLayer::get_latest_height`] themselves.
     fn subscribe_to_heights(&self) -> broadcast::Receiver<u64>;
+    /// Reports whether the DA layer currently delivers new heights. A started DA layer
+    /// reconnects on its own, so this is informational.
+    fn health(&self) -> DaHealth;
 }
@@ -1,30 +1,45 @@
-use crate::{DataAvailabilityLayer, FinalizedEpoch};
-use anyhow::Result;
+use crate::{
+    consts::{MAX_BLOB_SIZE, MAX_SUBMISSION_SIZE},
+    fetch_epochs, submit_epoch_blobs, Checkpoint, DaHealth, DataAvailabilityLayer, FinalizedEpoch,
+    SkippedEpochBlobCounter, SkippedEpochBlobs,
+};
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use ed25519_consensus::VerificationKey as VerifyingKey;
 use log::debug;
 use prism_common::transaction::Transaction;
-use std::{collections::VecDeque, sync::Arc};
+use prism_errors::DataAvailabilityError;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc};
 use tokio::{
     sync::{broadcast, RwLock},
     time::{interval, Duration},
 };
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Block {
     pub height: u64,
     pub transactions: Vec<Transaction>,
-    pub epoch: Option<FinalizedEpoch>,
+    /// Blobs posted to the snark namespace, see [`FinalizedEpoch::encode`].
+    pub epoch_blobs: Vec<Vec<u8>>,
+    pub checkpoints: Vec<Checkpoint>,
 }
 
 #[derive(Clone)]
 pub struct InMemoryDataAvailabilityLayer {
     blocks: Arc<RwLock<Vec<Block>>>,
     pending_transactions: Arc<RwLock<Vec<Transaction>>>,
-    pending_epochs: Arc<RwLock<VecDeque<FinalizedEpoch>>>,
+    /// Epoch blobs by the height they are included at, see
+    /// [`InMemoryDataAvailabilityLayer::submit_epoch_blobs`].
+    pending_epoch_blobs: Arc<RwLock<BTreeMap<u64, Vec<Vec<u8>>>>>,
+    pending_checkpoints: Arc<RwLock<Vec<Checkpoint>>>,
     latest_height: Arc<RwLock<u64>>,
     height_update_tx: broadcast::Sender<u64>,
     block_update_tx: broadcast::Sender<Block>,
     block_time: u64,
+    max_blob_size: usize,
+    max_submission_size: usize,
+    skipped_epoch_blobs: Arc<SkippedEpochBlobCounter>,
 }
 
 impl InMemoryDataAvailabilityLayer {
@@ -35,31 +50,106 @@ impl InMemoryDataAvailabilityLayer {
             Self {
                 blocks: Arc::new(RwLock::new(Vec::new())),
                 pending_transactions: Arc::new(RwLock::new(Vec::new())),
-                pending_epochs: Arc::new(RwLock::new(VecDeque::new())),
+                pending_epoch_blobs: Arc::new(RwLock::new(BTreeMap::new())),
+                pending_checkpoints: Arc::new(RwLock::new(Vec::new())),
                 latest_height: Arc::new(RwLock::new(0)),
                 height_update_tx: height_tx,
                 block_update_tx: block_tx,
                 block_time,
+                max_blob_size: MAX_BLOB_SIZE,
+                max_submission_size: MAX_SUBMISSION_SIZE,
+                skipped_epoch_blobs: Arc::new(SkippedEpochBlobCounter::default()),
             },
             height_rx,
             block_rx,
         )
     }
 
+    /// Limits the size of blobs like Celestia does, [`MAX_BLOB_SIZE`] by default. Epochs are
+    /// split into blobs of this size, checkpoints that don't fit are rejected.
+    pub fn with_max_blob_size(mut self, max_blob_size: usize) -> Self {
+        self.max_blob_size = max_blob_size;
+        self
+    }
+
+    /// Limits the total size of the blobs submitted at once like Celestia limits the size of a
+    /// transaction, [`MAX_SUBMISSION_SIZE`] by default. The epoch blobs of a block stay within
+    /// it too, so the chunks of large epochs are spread across several blocks.
+    pub fn with_max_submission_size(mut self, max_submission_size: usize) -> Self {
+        self.max_submission_size = max_submission_size;
+        self
+    }
+
+    /// Continues the chain after `blocks`, e.g. as persisted by a previous run. They have to
+    /// be ordered by height.
+    pub fn with_blocks(mut self, blocks: Vec<Block>) -> Self {
+        let latest_height = blocks.last().map_or(0, |block| block.height);
+        self.blocks = Arc::new(RwLock::new(blocks));
+        self.latest_height = Arc::new(RwLock::new(latest_height));
+        self
+    }
+
+    pub async fn get_block(&self, height: u64) -> Option<Block> {
+        let blocks = self.blocks.read().await;
+        blocks.iter().find(|block| block.height == height).cloned()
+    }
+
+    async fn get_epoch_blobs(&self, height: u64) -> Result<Vec<Vec<u8>>> {
+        Ok(self.get_block(height).await.map(|block| block.epoch_blobs).unwrap_or_default())
+    }
+
+    /// Schedules `blobs` like one Celestia transaction and returns the height they will be
+    /// included at. They go into the block of the last scheduled epoch blobs, or the next
+    /// one, if the block's epoch blobs stay within the submission size.
+    async fn schedule_epoch_blobs(&self, blobs: Vec<Vec<u8>>) -> Result<u64> {
+        let size: usize = blobs.iter().map(Vec::len).sum();
+        if let Some(blob) = blobs.iter().find(|blob| blob.len() > self.max_blob_size) {
+            return Err(anyhow!(DataAvailabilityError::SubmissionError(format!(
+                "blob of {} bytes exceeds the blob limit of {}",
+                blob.len(),
+                self.max_blob_size
+            ))));
+        }
+        if size > self.max_submission_size {
+            return Err(anyhow!(DataAvailabilityError::SubmissionError(format!(
+                "{} blobs take {} bytes, more than the submission limit of {}",
+                blobs.len(),
+                size,
+                self.max_submission_size
+            ))));
+        }
+
+        let mut pending_epoch_blobs = self.pending_epoch_blobs.write().await;
+        let next_height = *self.latest_height.read().await + 1;
+        let mut height = pending_epoch_blobs
+            .keys()
+            .next_back()
+            .map_or(next_height, |last| (*last).max(next_height));
+        let scheduled: usize =
+            pending_epoch_blobs.get(&height).map_or(0, |blobs| blobs.iter().map(Vec::len).sum());
+        if scheduled + size > self.max_submission_size {
+            height += 1;
+        }
+        pending_epoch_blobs.entry(height).or_default().extend(blobs);
+        Ok(height)
+    }
+
     async fn produce_blocks(self: Arc<Self>) {
         let mut interval = interval(Duration::from_secs(self.block_time));
         loop {
             interval.tick().await;
             let mut blocks = self.blocks.write().await;
             let mut pending_transactions = self.pending_transactions.write().await;
-            let mut pending_epochs = self.pending_epochs.write().await;
+            let mut pending_epoch_blobs = self.pending_epoch_blobs.write().await;
+            let mut pending_checkpoints = self.pending_checkpoints.write().await;
             let mut latest_height = self.latest_height.write().await;
 
             *latest_height += 1;
             let new_block = Block {
                 height: *latest_height,
                 transactions: std::mem::take(&mut *pending_transactions),
-                epoch: pending_epochs.pop_front(),
+                epoch_blobs: pending_epoch_blobs.remove(&*latest_height).unwrap_or_default(),
+                checkpoints: std::mem::take(&mut *pending_checkpoints),
             };
             debug!(
                 "new block produced at height {} with {} transactions",
@@ -85,6 +175,13 @@ impl DataAvailabilityLayer for InMemoryDataAvailabilityLayer {
         self.height_update_tx.subscribe()
     }
 
+    fn health(&self) -> DaHealth {
+        DaHealth {
+            connected: true,
+            ..DaHealth::default()
+        }
+    }
+
     async fn get_latest_height(&self) -> Result<u64> {
         Ok(*self.latest_height.read().await)
     }
@@ -93,18 +190,57 @@ impl DataAvailabilityLayer for InMemoryDataAvailabilityLayer {
         self.get_latest_height().await
     }
 
-    async fn get_finalized_epoch(&self, height: u64) -> Result<Option<FinalizedEpoch>> {
-        let blocks = self.blocks.read().await;
-        Ok(blocks
-            .iter()
-            .find(|block| block.height == height)
-            .map(|block| block.epoch.clone())
-            .unwrap_or_default())
+    async fn get_finalized_epochs(
+        &self,
+        height: u64,
+        provers: &[VerifyingKey],
+    ) -> Result<Vec<FinalizedEpoch>> {
+        let blobs = self.get_epoch_blobs(height).await?;
+        let epochs = fetch_epochs(height, &blobs, |chunk_height| {
+            self.get_epoch_blobs(chunk_height)
+        })
+        .await?;
+        Ok(self.skipped_epoch_blobs.filter(height, epochs, provers))
+    }
+
+    fn skipped_epoch_blobs(&self) -> SkippedEpochBlobs {
+        self.skipped_epoch_blobs.get()
     }
 
     async fn submit_finalized_epoch(&self, epoch: FinalizedEpoch) -> Result<u64> {
-        let mut pending_epochs = self.pending_epochs.write().await;
-        pending_epochs.push_back(epoch);
+        submit_epoch_blobs(
+            &epoch,
+            self.max_blob_size,
+            self.max_submission_size,
+            |blobs| self.schedule_epoch_blobs(blobs),
+        )
+        .await
+        .map_err(|e| anyhow!(DataAvailabilityError::SubmissionError(format!("{:#}", e))))
+    }
+
+    async fn get_checkpoints(
+        &self,
+        height: u64,
+        provers: &[VerifyingKey],
+    ) -> Result<Vec<Checkpoint>> {
+        let blocks = self.blocks.read().await;
+        let Some(block) = blocks.iter().find(|block| block.height == height) else {
+            return Ok(Vec::new());
+        };
+        let checkpoints = block.checkpoints.iter().cloned().map(Ok);
+        Ok(self.skipped_epoch_blobs.filter(height, checkpoints, provers))
+    }
+
+    async fn submit_checkpoint(&self, checkpoint: Checkpoint) -> Result<u64> {
+        let size = bincode::serialized_size(&checkpoint)? as usize;
+        if size > self.max_blob_size {
+            return Err(anyhow!(DataAvailabilityError::SubmissionError(format!(
+                "checkpoint up to epoch {} takes {} bytes, more than the blob limit of {}",
+                checkpoint.epoch_height, size, self.max_blob_size
+            ))));
+        }
+        let mut pending_checkpoints = self.pending_checkpoints.write().await;
+        pending_checkpoints.push(checkpoint);
         self.get_latest_height().await
     }
 
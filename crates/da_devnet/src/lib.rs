@@ -0,0 +1,378 @@
+//! Serves an [`InMemoryDataAvailabilityLayer`] to other processes, so a prover, full nodes and
+//! light clients can run against the same local DA layer. See [`prism_da::devnet`] for the API
+//! and its client.
+
+use anyhow::{bail, ensure, Context, Result};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, State,
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use log::{info, warn};
+use prism_common::transaction::Transaction;
+use prism_da::{
+    devnet::EpochBlobs,
+    memory::{Block, InMemoryDataAvailabilityLayer},
+    Checkpoint, DataAvailabilityLayer, FinalizedEpoch,
+};
+use serde::Serialize;
+use std::{
+    ffi::OsStr,
+    fs,
+    net::TcpListener,
+    path::{Path as FsPath, PathBuf},
+    sync::Arc,
+};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+const BLOCK_EXTENSION: &str = "block";
+
+pub struct DevnetServerConfig {
+    /// Seconds between blocks.
+    pub block_time: u64,
+    pub max_blob_size: usize,
+    /// Largest total size of the blobs submitted at once, like the size of a Celestia
+    /// transaction.
+    pub max_submission_size: usize,
+    /// Directory blocks are persisted to. Without it, the chain starts over on every run.
+    pub data_dir: Option<PathBuf>,
+}
+
+/// Persists blocks as one bincode file per height, so a restarted devnet continues its chain.
+pub struct BlockStore {
+    dir: PathBuf,
+}
+
+impl BlockStore {
+    pub fn open(dir: impl AsRef<FsPath>) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create data directory {}", dir.display()))?;
+        Ok(BlockStore { dir })
+    }
+
+    /// Returns the persisted blocks ordered by height, which have to start at 1 without gaps.
+    pub fn load(&self) -> Result<Vec<Block>> {
+        let mut blocks = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension() != Some(OsStr::new(BLOCK_EXTENSION)) {
+                continue;
+            }
+            let data = fs::read(&path)?;
+            let block: Block = bincode::deserialize(&data)
+                .with_context(|| format!("Failed to decode block from {}", path.display()))?;
+            blocks.push(block);
+        }
+
+        blocks.sort_by_key(|block| block.height);
+        for (expected, block) in (1..).zip(&blocks) {
+            ensure!(
+                block.height == expected,
+                "block {} is missing from {}",
+                expected,
+                self.dir.display()
+            );
+        }
+        Ok(blocks)
+    }
+
+    pub fn write(&self, block: &Block) -> Result<()> {
+        let data = bincode::serialize(block)?;
+        let path = self.dir.join(format!("{:012}.{}", block.height, BLOCK_EXTENSION));
+        // a block is either persisted completely or not at all
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, data)?;
+        fs::rename(&tmp, &path).with_context(|| format!("Failed to persist block {}", block.height))
+    }
+}
+
+pub struct DevnetServer {
+    da: Arc<InMemoryDataAvailabilityLayer>,
+    store: Option<BlockStore>,
+}
+
+impl DevnetServer {
+    pub fn new(config: &DevnetServerConfig) -> Result<Self> {
+        let store = config.data_dir.as_ref().map(BlockStore::open).transpose()?;
+        let blocks = match &store {
+            Some(store) => store.load()?,
+            None => Vec::new(),
+        };
+        if let Some(last) = blocks.last() {
+            info!("continuing devnet after height {}", last.height);
+        }
+
+        let (da, _height_rx, _block_rx) = InMemoryDataAvailabilityLayer::new(config.block_time);
+        let da = da
+            .with_blocks(blocks)
+            .with_max_blob_size(config.max_blob_size)
+            .with_max_submission_size(config.max_submission_size);
+        Ok(DevnetServer {
+            da: Arc::new(da),
+            store,
+        })
+    }
+
+    /// Produces blocks and serves them on `listener`, until the returned future is dropped or
+    /// a block can't be persisted.
+    pub async fn serve(self, listener: TcpListener) -> Result<()> {
+        let blocks = self.da.subscribe_blocks();
+        self.da.start().await?;
+
+        let server = axum::Server::from_tcp(listener)?.serve(router(self.da).into_make_service());
+        info!("serving devnet on {}", server.local_addr());
+        tokio::select! {
+            result = server => result.context("Devnet server failed"),
+            result = persist_blocks(blocks, self.store) => result,
+        }
+    }
+}
+
+async fn persist_blocks(
+    mut blocks: broadcast::Receiver<Block>,
+    store: Option<BlockStore>,
+) -> Result<()> {
+    loop {
+        match blocks.recv().await {
+            Ok(block) => {
+                if let Some(store) = &store {
+                    store.write(&block)?;
+                }
+            }
+            Err(RecvError::Lagged(skipped)) => {
+                bail!(
+                    "fell behind persisting blocks, {} were not persisted",
+                    skipped
+                )
+            }
+            Err(RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+fn router(da: Arc<InMemoryDataAvailabilityLayer>) -> Router {
+    Router::new()
+        .route("/height", get(get_height))
+        .route("/heights", get(stream_heights))
+        .route("/epochs", post(submit_epoch))
+        .route("/epochs/:height", get(get_epochs))
+        .route("/checkpoints", post(submit_checkpoint))
+        .route("/checkpoints/:height", get(get_checkpoints))
+        .route("/transactions", post(submit_transactions))
+        .route("/transactions/:height", get(get_transactions))
+        .with_state(da)
+}
+
+fn respond<T: Serialize>(result: Result<T>, error_status: StatusCode) -> Response {
+    match result {
+        Ok(value) => Json(value).into_response(),
+        Err(e) => (error_status, format!("{:#}", e)).into_response(),
+    }
+}
+
+async fn get_height(State(da): State<Arc<InMemoryDataAvailabilityLayer>>) -> Response {
+    respond(
+        da.get_latest_height().await,
+        StatusCode::INTERNAL_SERVER_ERROR,
+    )
+}
+
+async fn stream_heights(
+    ws: WebSocketUpgrade,
+    State(da): State<Arc<InMemoryDataAvailabilityLayer>>,
+) -> Response {
+    let heights = da.subscribe_to_heights();
+    ws.on_upgrade(move |socket| send_heights(socket, heights))
+}
+
+async fn send_heights(mut socket: WebSocket, mut heights: broadcast::Receiver<u64>) {
+    loop {
+        match heights.recv().await {
+            Ok(height) => {
+                if socket.send(Message::Text(height.to_string())).await.is_err() {
+                    return;
+                }
+            }
+            // clients backfill skipped heights once they receive the next one
+            Err(RecvError::Lagged(skipped)) => warn!("height stream skipped {} heights", skipped),
+            Err(RecvError::Closed) => return,
+        }
+    }
+}
+
+async fn get_epochs(
+    Path(height): Path<u64>,
+    State(da): State<Arc<InMemoryDataAvailabilityLayer>>,
+) -> Response {
+    let blobs = da.get_block(height).await.map(|block| block.epoch_blobs).unwrap_or_default();
+    Json(EpochBlobs {
+        blobs: blobs.iter().map(hex::encode).collect(),
+    })
+    .into_response()
+}
+
+async fn submit_epoch(
+    State(da): State<Arc<InMemoryDataAvailabilityLayer>>,
+    Json(epoch): Json<FinalizedEpoch>,
+) -> Response {
+    respond(
+        da.submit_finalized_epoch(epoch).await,
+        StatusCode::BAD_REQUEST,
+    )
+}
+
+async fn get_checkpoints(
+    Path(height): Path<u64>,
+    State(da): State<Arc<InMemoryDataAvailabilityLayer>>,
+) -> Response {
+    // clients filter by their own provers
+    respond(
+        da.get_checkpoints(height, &[]).await,
+        StatusCode::INTERNAL_SERVER_ERROR,
+    )
+}
+
+async fn submit_checkpoint(
+    State(da): State<Arc<InMemoryDataAvailabilityLayer>>,
+    Json(checkpoint): Json<Checkpoint>,
+) -> Response {
+    respond(
+        da.submit_checkpoint(checkpoint).await,
+        StatusCode::BAD_REQUEST,
+    )
+}
+
+async fn get_transactions(
+    Path(height): Path<u64>,
+    State(da): State<Arc<InMemoryDataAvailabilityLayer>>,
+) -> Response {
+    respond(
+        da.get_transactions(height).await,
+        StatusCode::INTERNAL_SERVER_ERROR,
+    )
+}
+
+async fn submit_transactions(
+    State(da): State<Arc<InMemoryDataAvailabilityLayer>>,
+    Json(transactions): Json<Vec<Transaction>>,
+) -> Response {
+    respond(
+        da.submit_transactions(transactions).await,
+        StatusCode::BAD_REQUEST,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_common::transaction_builder::TransactionBuilder;
+    use prism_da::{
+        consts::{MAX_BLOB_SIZE, MAX_SUBMISSION_SIZE},
+        devnet::{DevnetConfig, DevnetConnection},
+    };
+    use std::time::Duration;
+    use tokio::{task::JoinHandle, time::timeout};
+
+    fn data_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("prism-devnet-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn spawn_devnet(data_dir: &FsPath) -> (DevnetConnection, JoinHandle<Result<()>>) {
+        let server = DevnetServer::new(&DevnetServerConfig {
+            block_time: 1,
+            max_blob_size: MAX_BLOB_SIZE,
+            max_submission_size: MAX_SUBMISSION_SIZE,
+            data_dir: Some(data_dir.to_path_buf()),
+        })
+        .unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let client = DevnetConnection::new(&DevnetConfig {
+            url,
+            reconnect_interval_ms: 10,
+        });
+        (client, tokio::spawn(server.serve(listener)))
+    }
+
+    #[tokio::test]
+    async fn test_serves_and_persists_blocks() {
+        let dir = data_dir("serve");
+        let (client, server) = spawn_devnet(&dir);
+        let mut heights = client.subscribe_to_heights();
+        client.start().await.unwrap();
+
+        let transaction =
+            TransactionBuilder::new().register_service_with_random_keys("test_service").commit();
+        client.submit_transactions(vec![transaction.clone()]).await.unwrap();
+        let height = timeout(Duration::from_secs(10), async {
+            loop {
+                let height = heights.recv().await.unwrap();
+                if !client.get_transactions(height).await.unwrap().is_empty() {
+                    break height;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            client.get_transactions(height).await.unwrap(),
+            vec![transaction.clone()]
+        );
+        assert!(client.get_checkpoints(height, &[]).await.unwrap().is_empty());
+        assert!(client.get_finalized_epochs(height, &[]).await.unwrap().is_empty());
+        assert!(client.health().connected);
+
+        // a restarted devnet continues the persisted chain
+        let persisted = dir.join(format!("{:012}.{}", height, BLOCK_EXTENSION));
+        timeout(Duration::from_secs(10), async {
+            while !persisted.exists() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        server.abort();
+        let _ = server.await;
+        let (client, server) = spawn_devnet(&dir);
+        assert!(client.initialize_sync_target().await.unwrap() >= height);
+        assert_eq!(
+            client.get_transactions(height).await.unwrap(),
+            vec![transaction]
+        );
+
+        server.abort();
+        let _ = server.await;
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_block_store_rejects_gaps() {
+        let dir = data_dir("gaps");
+        let store = BlockStore::open(&dir).unwrap();
+        let block = |height| Block {
+            height,
+            transactions: Vec::new(),
+            epoch_blobs: Vec::new(),
+            checkpoints: Vec::new(),
+        };
+
+        store.write(&block(1)).unwrap();
+        store.write(&block(2)).unwrap();
+        let heights: Vec<u64> = store.load().unwrap().iter().map(|block| block.height).collect();
+        assert_eq!(heights, vec![1, 2]);
+
+        store.write(&block(4)).unwrap();
+        assert!(store.load().is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,63 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use log::info;
+use prism_da::consts::{MAX_BLOB_SIZE, MAX_SUBMISSION_SIZE};
+use prism_da_devnet::{DevnetServer, DevnetServerConfig};
+use std::{net::TcpListener, path::PathBuf};
+
+/// Serves an in-memory DA layer to prism nodes running in other processes.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Log level
+    #[arg(short, long, default_value = "INFO")]
+    log_level: String,
+
+    /// IP address to listen on
+    #[arg(long, default_value = "127.0.0.1")]
+    host: String,
+
+    /// Port to listen on
+    #[arg(short, long, default_value_t = 26660)]
+    port: u16,
+
+    /// Seconds between blocks
+    #[arg(long, default_value_t = 1)]
+    block_time: u64,
+
+    /// Largest blob accepted, in bytes
+    #[arg(long, default_value_t = MAX_BLOB_SIZE)]
+    max_blob_size: usize,
+
+    /// Largest total size of the blobs submitted at once, in bytes
+    #[arg(long, default_value_t = MAX_SUBMISSION_SIZE)]
+    max_submission_size: usize,
+
+    /// Directory to persist blocks to. Without it, blocks are lost on exit.
+    #[arg(long)]
+    data_dir: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    std::env::set_var("RUST_LOG", &args.log_level);
+    pretty_env_logger::init();
+
+    let server = DevnetServer::new(&DevnetServerConfig {
+        block_time: args.block_time,
+        max_blob_size: args.max_blob_size,
+        max_submission_size: args.max_submission_size,
+        data_dir: args.data_dir,
+    })?;
+    let listener = TcpListener::bind((args.host.as_str(), args.port))
+        .with_context(|| format!("Failed to listen on {}:{}", args.host, args.port))?;
+
+    tokio::select! {
+        result = server.serve(listener) => result,
+        _ = tokio::signal::ctrl_c() => {
+            info!("stopping devnet");
+            Ok(())
+        }
+    }
+}
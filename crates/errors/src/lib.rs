@@ -11,6 +11,10 @@ pub enum PrismError {
     DataAvailability(#[from] DataAvailabilityError),
     #[error(transparent)]
     Proof(#[from] ProofError),
+    #[error(transparent)]
+    Transaction(#[from] TransactionError),
+    #[error(transparent)]
+    EpochVerification(#[from] EpochVerificationError),
     #[error("config error: {0}")]
     ConfigError(String),
     #[error(transparent)]
@@ -72,7 +76,7 @@ pub enum DataAvailabilityError {
     NetworkError(String),
     #[error("retrieving data at height {0}: {1}")]
     DataRetrievalError(u64, String),
-    #[error("submitting epoch to da layer: {0}")]
+    #[error("submitting to da layer: {0}")]
     SubmissionError(String),
     #[error("setting new sync target: {0}")]
     SyncTargetError(String),
@@ -95,3 +99,53 @@ pub enum ProofError {
     #[error("invalid proof format")]
     InvalidFormatError,
 }
+
+// reasons a finalized epoch read from the DA layer is rejected
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum EpochVerificationError {
+    /// Anyone can post to the DA layer, so epochs without a valid signature of the prover are
+    /// skipped. All other errors are attributable to the prover.
+    #[error("invalid signature: {0}")]
+    InvalidSignature(String),
+    #[error("epoch height mismatch: expected {0}, got {1}")]
+    HeightMismatch(u64, u64),
+    #[error("previous commitment mismatch at epoch {0}")]
+    PreviousCommitmentMismatch(u64),
+    #[error("new commitment mismatch at epoch {0}")]
+    NewCommitmentMismatch(u64),
+    #[error("commitments of epoch {0} do not match the public values of its proof")]
+    PublicValuesMismatch(u64),
+    #[error("epoch {0} includes {1} transactions, but only {2} are buffered")]
+    MissingTransactions(u64, u64, u64),
+    #[error("invalid proof for epoch {0}: {1}")]
+    InvalidProof(u64, String),
+    #[error("prover signed conflicting epochs at height {0}")]
+    Equivocation(u64),
+}
+
+impl EpochVerificationError {
+    /// Whether the rejection is a fault of the prover. This only holds if the epoch was
+    /// checked against a prover key, otherwise anyone could have posted it.
+    pub fn is_fault(&self) -> bool {
+        !matches!(self, EpochVerificationError::InvalidSignature(_))
+    }
+}
+
+// errors of transactions that can not be applied to the state
+#[derive(Error, Debug)]
+pub enum TransactionError {
+    #[error("invalid operation: {0}")]
+    InvalidOperation(String),
+    #[error("{0} already exists")]
+    AlreadyExists(String),
+    #[error("{0} not found")]
+    NotFound(String),
+    #[error("previous hash for new entry must be the last hash - prev: {0}, last: {1}")]
+    InvalidPreviousHash(String, String),
+    #[error("hashchain entry has incorrect hash")]
+    InvalidHash,
+    #[error("invalid signature: {0}")]
+    InvalidSignature(String),
+    #[error("invalid key: {0}")]
+    InvalidKey(String),
+}
@@ -0,0 +1,114 @@
+use crate::{
+    lightclient::{DEFAULT_CATCHUP_WINDOW, DEFAULT_CHECKPOINT_SEARCH_DEPTH},
+    trusted_checkpoint::{CommitmentAttestor, TrustedCheckpoint},
+    webserver::WebServerConfig,
+    LightClient,
+};
+use anyhow::{ensure, Result};
+use ed25519_consensus::VerificationKey as VerifyingKey;
+use prism_da::DataAvailabilityLayer;
+use prism_proving::EpochVerifier;
+use prism_storage::Database;
+use std::sync::Arc;
+
+/// Configures a [`LightClient`], see [`LightClient::builder`]. Without further configuration
+/// the client verifies every epoch from DA height 0, accepts epochs signed by anyone and
+/// doesn't serve its webserver.
+pub struct LightClientBuilder {
+    pub(crate) da: Arc<dyn DataAvailabilityLayer>,
+    pub(crate) db: Arc<Box<dyn Database>>,
+    pub(crate) verifier: Arc<dyn EpochVerifier>,
+    pub(crate) prover_pubkey: Option<VerifyingKey>,
+    pub(crate) start_height: u64,
+    pub(crate) webserver: WebServerConfig,
+    pub(crate) catchup_window: usize,
+    pub(crate) checkpoint_search_depth: u64,
+    pub(crate) trusted_checkpoint: Option<TrustedCheckpoint>,
+    pub(crate) attestors: Vec<Arc<dyn CommitmentAttestor>>,
+}
+
+impl LightClientBuilder {
+    pub(crate) fn new(
+        da: Arc<dyn DataAvailabilityLayer>,
+        db: Arc<Box<dyn Database>>,
+        verifier: Arc<dyn EpochVerifier>,
+    ) -> Self {
+        LightClientBuilder {
+            da,
+            db,
+            verifier,
+            prover_pubkey: None,
+            start_height: 0,
+            webserver: WebServerConfig {
+                enabled: false,
+                ..WebServerConfig::default()
+            },
+            catchup_window: DEFAULT_CATCHUP_WINDOW,
+            checkpoint_search_depth: DEFAULT_CHECKPOINT_SEARCH_DEPTH,
+            trusted_checkpoint: None,
+            attestors: Vec::new(),
+        }
+    }
+
+    /// Only accepts epochs and checkpoints signed by the prover's key.
+    pub fn prover_pubkey(mut self, prover_pubkey: VerifyingKey) -> Self {
+        self.prover_pubkey = Some(prover_pubkey);
+        self
+    }
+
+    /// DA height a fresh client starts searching for epochs after.
+    pub fn start_height(mut self, start_height: u64) -> Self {
+        self.start_height = start_height;
+        self
+    }
+
+    pub fn webserver(mut self, webserver: WebServerConfig) -> Self {
+        self.webserver = webserver;
+        self
+    }
+
+    /// Number of DA heights fetched and verified in parallel while catching up.
+    pub fn catchup_window(mut self, catchup_window: usize) -> Self {
+        self.catchup_window = catchup_window;
+        self
+    }
+
+    /// Number of DA heights searched back from the latest height for a checkpoint signed by the
+    /// prover, once the client fell behind by more than its catch-up window. Checkpoints are
+    /// only used with a [`LightClientBuilder::prover_pubkey`], 0 disables the search.
+    pub fn checkpoint_search_depth(mut self, checkpoint_search_depth: u64) -> Self {
+        self.checkpoint_search_depth = checkpoint_search_depth;
+        self
+    }
+
+    /// Starts a fresh client from `checkpoint` instead of the start height, once one of the
+    /// `attestors` confirmed it.
+    pub fn trusted_checkpoint(
+        mut self,
+        checkpoint: TrustedCheckpoint,
+        attestors: Vec<Arc<dyn CommitmentAttestor>>,
+    ) -> Self {
+        self.trusted_checkpoint = Some(checkpoint);
+        self.attestors = attestors;
+        self
+    }
+
+    pub fn build(self) -> Result<LightClient> {
+        ensure!(
+            self.catchup_window > 0,
+            "catch-up window must cover at least one DA height"
+        );
+        if let Some(checkpoint) = &self.trusted_checkpoint {
+            ensure!(
+                checkpoint.epoch > 0,
+                "trusted checkpoint must come after at least one epoch"
+            );
+            ensure!(
+                !self.attestors.is_empty(),
+                "trusted checkpoint needs at least one full node to cross-check it against"
+            );
+        }
+
+        Ok(LightClient::from_builder(self))
+    }
+}
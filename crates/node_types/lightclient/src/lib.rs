@@ -1,6 +1,11 @@
+pub mod builder;
 pub mod lightclient;
+pub mod trusted_checkpoint;
+pub mod webserver;
 
-pub use lightclient::LightClient;
+pub use builder::LightClientBuilder;
+pub use lightclient::{LightClient, LightClientState, VerificationSource, VerifiedEpoch};
+pub use trusted_checkpoint::{CommitmentAttestor, FullNodeClient, TrustedCheckpoint};
 
 #[macro_use]
 extern crate log;
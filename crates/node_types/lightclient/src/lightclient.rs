@@ -1,46 +1,266 @@
-use anyhow::{Context, Result};
+use crate::{
+    builder::LightClientBuilder,
+    trusted_checkpoint::{CommitmentAttestor, TrustedCheckpoint},
+    webserver::{WebServer, WebServerConfig},
+};
+use anyhow::{anyhow, bail, ensure, Context, Result};
 use ed25519_consensus::VerificationKey as VerifyingKey;
-use prism_common::digest::Digest;
-use prism_da::{celestia::CelestiaConfig, DataAvailabilityLayer};
-use prism_errors::{DataAvailabilityError, GeneralError};
-use sp1_sdk::{ProverClient, SP1VerifyingKey};
-use std::{self, sync::Arc};
-use tokio::{sync::broadcast, task::spawn};
+use jmt::{proof::SparseMerkleProof, KeyHash};
+use prism_common::{
+    digest::Digest,
+    hashchain::Hashchain,
+    hasher::Hasher,
+    tree::{KeyDirectoryTree, MembershipProof, NonMembershipProof},
+};
+use prism_da::{
+    Checkpoint, DataAvailabilityLayer, EquivocationEvidence, FinalizedEpoch, SignedEpochHeader,
+};
+use prism_errors::{DataAvailabilityError, DatabaseError, EpochVerificationError, GeneralError};
+use prism_proving::EpochVerifier;
+use prism_storage::{inmemory::InMemoryDatabase, Database, EpochFault, WriteBatch};
+use std::{
+    self,
+    cmp::Reverse,
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+use tokio::{
+    sync::{broadcast, watch},
+    task::{spawn, JoinHandle, JoinSet},
+};
 
-pub const PRISM_ELF: &[u8] = include_bytes!("../../../../elf/riscv32im-succinct-zkvm-elf");
+pub use prism_proving::PRISM_ELF;
 
-#[allow(dead_code)]
+/// Default number of DA heights fetched and verified in parallel while catching up.
+pub const DEFAULT_CATCHUP_WINDOW: usize = 16;
+
+/// Default number of DA heights searched back from the latest height for a checkpoint, once
+/// the client fell behind by more than its catch-up window.
+pub const DEFAULT_CHECKPOINT_SEARCH_DEPTH: u64 = 1_000;
+
+/// Number of [`VerifiedEpoch`] events kept for subscribers that fall behind.
+pub const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// The latest state verified by a [`LightClient`], which it resumes from after a restart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LightClientState {
+    /// Height of the next epoch to verify.
+    pub epoch: u64,
+    /// DA height the latest verified epoch or checkpoint was found at.
+    pub da_height: u64,
+    /// Commitment after the latest verified epoch.
+    pub commitment: Digest,
+}
+
+impl LightClientState {
+    /// Returns the stored state, or `None` if no epoch was verified yet.
+    pub fn load(db: &dyn Database) -> Result<Option<Self>> {
+        let epoch = match db.get_epoch() {
+            Ok(epoch) => epoch,
+            // a fresh database has no epoch yet
+            Err(e)
+                if matches!(
+                    e.downcast_ref::<DatabaseError>(),
+                    Some(DatabaseError::NotFoundError(_))
+                ) =>
+            {
+                0
+            }
+            Err(e) => return Err(e.context("Failed to load the verified epoch")),
+        };
+        if epoch == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(LightClientState {
+            epoch,
+            da_height: db.get_last_synced_height()?,
+            commitment: db.get_commitment(&epoch)?,
+        }))
+    }
+
+    pub fn store(&self, db: &dyn Database) -> Result<()> {
+        let mut batch = WriteBatch {
+            epoch: Some(self.epoch),
+            sync_height: Some(self.da_height),
+            ..WriteBatch::default()
+        };
+        batch.commitments.insert(self.epoch, self.commitment);
+        db.commit_batch(&batch)
+    }
+}
+
+/// How the state in a [`VerifiedEpoch`] was verified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationSource {
+    /// The epoch's proof was verified.
+    Epoch,
+    /// A checkpoint on the DA layer covering all epochs so far was verified.
+    Checkpoint,
+    /// The configured [`TrustedCheckpoint`] was confirmed by a full node.
+    TrustedCheckpoint,
+}
+
+/// Sent to [`LightClient::subscribe`]rs whenever the verified state advances.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifiedEpoch {
+    /// Number of epochs the commitment was reached after.
+    pub epoch: u64,
+    /// DA height the epoch or checkpoint was found at.
+    pub da_height: u64,
+    pub commitment: Digest,
+    pub source: VerificationSource,
+}
+
+/// Checks a proof from a full node's `/get-hashchain` response against `commitment`. With a
+/// `hashchain` the proof must show that it is stored for `id`, otherwise that `id` is absent.
+pub fn verify_hashchain_proof(
+    id: &str,
+    hashchain: Option<Hashchain>,
+    proof: SparseMerkleProof<Hasher>,
+    commitment: Digest,
+) -> Result<()> {
+    let key = KeyHash::with::<Hasher>(Digest::hash(id));
+    match hashchain {
+        Some(value) => MembershipProof {
+            root: commitment,
+            proof,
+            key,
+            value,
+        }
+        .verify(),
+        None => NonMembershipProof {
+            root: commitment,
+            proof,
+            key,
+        }
+        .verify(),
+    }
+}
+
+/// Returns the commitment of the empty tree, which the prover's first epoch starts from.
+fn genesis_commitment() -> Result<Digest> {
+    KeyDirectoryTree::new(Arc::new(InMemoryDatabase::new())).get_commitment()
+}
+
+/// Epochs found at one DA height, with the results of the checks that don't depend on the
+/// verified chain tip.
+enum FetchedEpochs {
+    Verified(Vec<(FinalizedEpoch, Result<(), EpochVerificationError>)>),
+    /// The prover signed conflicting epochs at the height, none of them is applied.
+    Equivocation(EquivocationEvidence),
+}
+
+/// How a [`FinalizedEpoch`] relates to the verified chain tip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EpochPosition {
+    /// The epoch extends the tip.
+    Next,
+    /// The epoch was verified before.
+    Duplicate,
+}
+
+/// Fetch tasks of a catch-up, which are aborted when the catch-up ends or is cancelled.
+struct PendingFetches(VecDeque<JoinHandle<Result<FetchedEpochs>>>);
+
+impl Drop for PendingFetches {
+    fn drop(&mut self) {
+        for task in &self.0 {
+            task.abort();
+        }
+    }
+}
+
+/// Verifies the epochs posted to the DA layer and keeps the latest verified commitment, which
+/// proofs from full nodes are checked against. Created with [`LightClient::builder`] and driven
+/// by [`LightClient::run`] until [`LightClient::stop`] is called.
 pub struct LightClient {
-    pub da: Arc<dyn DataAvailabilityLayer>,
-    pub prover_pubkey: Option<VerifyingKey>,
-    pub client: ProverClient,
-    pub verifying_key: SP1VerifyingKey,
-    pub start_height: u64,
+    da: Arc<dyn DataAvailabilityLayer>,
+    db: Arc<Box<dyn Database>>,
+    prover_pubkey: Option<VerifyingKey>,
+    verifier: Arc<dyn EpochVerifier>,
+    start_height: u64,
+    webserver: WebServerConfig,
+    /// Number of DA heights fetched and verified in parallel while catching up. The verified
+    /// chain tip still advances one height at a time.
+    catchup_window: usize,
+    /// Number of DA heights searched for a checkpoint when the client fell behind, see
+    /// [`LightClient::find_latest_checkpoint`].
+    checkpoint_search_depth: u64,
+    /// State a fresh client starts verifying from instead of `start_height`, see
+    /// [`TrustedCheckpoint`].
+    trusted_checkpoint: Option<TrustedCheckpoint>,
+    /// Full nodes the trusted checkpoint is cross-checked against.
+    attestors: Vec<Arc<dyn CommitmentAttestor>>,
+    events: broadcast::Sender<VerifiedEpoch>,
+    stopped: watch::Sender<bool>,
+    /// Set once an epoch signed by the prover failed verification or the prover signed
+    /// conflicting epochs, see [`LightClient::epoch_faults`] and
+    /// [`LightClient::equivocation_evidence`].
+    halted: AtomicBool,
 }
 
-#[allow(dead_code)]
 impl LightClient {
-    pub fn new(
+    pub fn builder(
         da: Arc<dyn DataAvailabilityLayer>,
-        cfg: CelestiaConfig,
-        prover_pubkey: Option<VerifyingKey>,
-    ) -> LightClient {
-        #[cfg(feature = "mock_prover")]
-        let client = ProverClient::mock();
-        #[cfg(not(feature = "mock_prover"))]
-        let client = ProverClient::local();
-        let (_, verifying_key) = client.setup(PRISM_ELF);
+        db: Arc<Box<dyn Database>>,
+        verifier: Arc<dyn EpochVerifier>,
+    ) -> LightClientBuilder {
+        LightClientBuilder::new(da, db, verifier)
+    }
 
+    pub(crate) fn from_builder(builder: LightClientBuilder) -> LightClient {
         LightClient {
-            da,
-            verifying_key,
-            client,
-            prover_pubkey,
-            start_height: cfg.start_height,
+            da: builder.da,
+            db: builder.db,
+            verifier: builder.verifier,
+            prover_pubkey: builder.prover_pubkey,
+            start_height: builder.start_height,
+            webserver: builder.webserver,
+            catchup_window: builder.catchup_window,
+            checkpoint_search_depth: builder.checkpoint_search_depth,
+            trusted_checkpoint: builder.trusted_checkpoint,
+            attestors: builder.attestors,
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            stopped: watch::channel(false).0,
+            halted: AtomicBool::new(false),
         }
     }
 
+    /// Returns a stream of the states verified from now on. A receiver that falls more than
+    /// [`EVENT_CHANNEL_CAPACITY`] events behind skips the oldest ones, the latest state is
+    /// always available through [`LightClient::state`].
+    pub fn subscribe(&self) -> broadcast::Receiver<VerifiedEpoch> {
+        self.events.subscribe()
+    }
+
+    /// Stops syncing and the webserver, which makes [`LightClient::run`] return. A stopped
+    /// client can't be restarted, but its verified state stays available.
+    pub fn stop(&self) {
+        self.stopped.send_replace(true);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        *self.stopped.borrow()
+    }
+
+    /// Syncs with the DA layer and serves the webserver until [`LightClient::stop`] is called
+    /// or syncing fails.
     pub async fn run(self: Arc<Self>) -> Result<()> {
+        let mut stopped = self.stopped.subscribe();
+        tokio::select! {
+            result = self.clone().run_until_failure() => result,
+            _ = stopped.wait_for(|stopped| *stopped) => {
+                info!("light client: stopped");
+                Ok(())
+            }
+        }
+    }
+
+    async fn run_until_failure(self: Arc<Self>) -> Result<()> {
         // start listening for new headers to update sync target
         self.da
             .start()
@@ -48,80 +268,1290 @@ impl LightClient {
             .map_err(|e| DataAvailabilityError::InitializationError(e.to_string()))
             .context("Failed to start DataAvailabilityLayer")?;
 
-        self.sync_loop()
-            .await
-            .map_err(|e| GeneralError::InitializationError(e.to_string()))
-            .context("Sync loop failed")
+        let restored =
+            match self.restore_state().await.context("Failed to restore light client state")? {
+                Some(state) => Some(state),
+                None => self
+                    .start_from_trusted_checkpoint()
+                    .await
+                    .context("Failed to start from trusted checkpoint")?,
+            };
+        if !self.db.get_equivocation_evidence()?.is_empty() {
+            self.halted.store(true, Ordering::SeqCst);
+            error!(
+                target: "prism::fault",
+                "light client: not syncing: the prover signed conflicting epochs"
+            );
+        }
+
+        let mut futures = JoinSet::new();
+        let sync_loop = self.clone().sync_loop(restored);
+        let this = self.clone();
+        futures.spawn(async move {
+            let result = sync_loop.await;
+            if this.is_halted() {
+                // keep serving the last verified state after a faulty epoch
+                return std::future::pending().await;
+            }
+            result
+                .map_err(|e| GeneralError::InitializationError(e.to_string()))
+                .context("Sync loop failed")
+        });
+
+        if self.webserver.enabled {
+            let ws = WebServer::new(self.webserver.clone(), self.clone());
+            futures.spawn(async move { ws.start().await });
+        }
+
+        match futures.join_next().await {
+            Some(result) => result?,
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the latest verified state, see [`LightClientState`].
+    pub fn state(&self) -> Result<Option<LightClientState>> {
+        LightClientState::load(&**self.db)
+    }
+
+    /// Whether syncing stopped at a faulty epoch.
+    pub fn is_halted(&self) -> bool {
+        self.halted.load(Ordering::SeqCst)
+    }
+
+    /// Returns the evidence of all epochs that were signed by the prover but failed
+    /// verification, ordered by DA height.
+    pub fn epoch_faults(&self) -> Result<Vec<EpochFault>> {
+        self.db.get_epoch_faults()
+    }
+
+    /// Returns the evidence of the prover signing conflicting epochs, ordered by epoch height.
+    pub fn equivocation_evidence(&self) -> Result<Vec<EquivocationEvidence>> {
+        self.db
+            .get_equivocation_evidence()?
+            .iter()
+            .map(|evidence| Ok(bincode::deserialize(evidence)?))
+            .collect()
+    }
+
+    /// Accepts equivocation evidence found by another node. Valid evidence halts syncing,
+    /// just like evidence found on the DA layer.
+    pub fn submit_equivocation_evidence(&self, evidence: &EquivocationEvidence) -> Result<()> {
+        let pubkey = self
+            .prover_pubkey
+            .ok_or_else(|| anyhow!("No prover key configured to check the evidence against"))?;
+        evidence.verify(pubkey)?;
+        self.record_equivocation(evidence)
+    }
+
+    fn record_equivocation(&self, evidence: &EquivocationEvidence) -> Result<()> {
+        self.db
+            .put_equivocation_evidence(&evidence.epoch_height(), &bincode::serialize(evidence)?)?;
+        self.halted.store(true, Ordering::SeqCst);
+
+        error!(
+            target: "prism::fault",
+            "light client: halting sync: prover signed conflicting epochs at height {}",
+            evidence.epoch_height()
+        );
+        Ok(())
+    }
+
+    /// Returns the verified commitment after `epoch` epochs, or the latest one if `epoch` is
+    /// `None`, together with the number of epochs it was reached after.
+    pub fn verified_commitment(&self, epoch: Option<u64>) -> Result<(u64, Digest)> {
+        let state = self.state()?.ok_or_else(|| anyhow!("No epoch was verified yet"))?;
+        let epoch = epoch.unwrap_or(state.epoch);
+        ensure!(
+            epoch <= state.epoch,
+            "Epoch {} was not verified yet, latest verified epoch is {}",
+            epoch,
+            state.epoch
+        );
+
+        let commitment = self
+            .db
+            .get_commitment(&epoch)
+            .with_context(|| format!("No verified commitment for epoch {}", epoch))?;
+        Ok((epoch, commitment))
+    }
+
+    /// Checks a proof from a full node's `/get-hashchain` response against the verified
+    /// commitment selected by `epoch`, see [`LightClient::verified_commitment`].
+    pub fn verify_hashchain_proof(
+        &self,
+        id: &str,
+        hashchain: Option<Hashchain>,
+        proof: SparseMerkleProof<Hasher>,
+        epoch: Option<u64>,
+    ) -> Result<(u64, Digest)> {
+        let (epoch, commitment) = self.verified_commitment(epoch)?;
+        verify_hashchain_proof(id, hashchain, proof, commitment)?;
+        Ok((epoch, commitment))
+    }
+
+    /// Loads the stored state and checks it against the DA layer. A state that the DA layer
+    /// does not confirm means the client is connected to a different network than before.
+    async fn restore_state(&self) -> Result<Option<LightClientState>> {
+        let Some(state) = self.state()? else {
+            return Ok(None);
+        };
+        self.confirm_on_da(&state).await?;
+
+        if let Some(checkpoint) = self.trusted_checkpoint {
+            // the stored state may come from a checkpoint on the DA layer, which doesn't keep
+            // the commitments of the epochs it covers
+            if let Ok(commitment) = self.db.get_commitment(&checkpoint.epoch) {
+                ensure!(
+                    commitment == checkpoint.commitment,
+                    "stored state conflicts with the trusted checkpoint at epoch {}",
+                    checkpoint.epoch
+                );
+            }
+        }
+
+        info!(
+            "light client: resuming from epoch {} at DA height {}",
+            state.epoch, state.da_height
+        );
+        Ok(Some(state))
+    }
+
+    /// Stores [`LightClient::trusted_checkpoint`] as the verified state of a fresh client,
+    /// once it was confirmed by a full node and the DA layer.
+    async fn start_from_trusted_checkpoint(&self) -> Result<Option<LightClientState>> {
+        let Some(checkpoint) = self.trusted_checkpoint else {
+            return Ok(None);
+        };
+        ensure!(
+            checkpoint.epoch > 0,
+            "trusted checkpoint must come after at least one epoch"
+        );
+
+        let confirmations = checkpoint.cross_check(&self.attestors).await?;
+        let state = LightClientState {
+            epoch: checkpoint.epoch,
+            da_height: checkpoint.da_height,
+            commitment: checkpoint.commitment,
+        };
+        self.confirm_on_da(&state).await?;
+        state.store(&**self.db)?;
+        self.publish(state, VerificationSource::TrustedCheckpoint);
+
+        info!(
+            "light client: starting from trusted checkpoint at epoch {} and DA height {}, confirmed by {} full node(s)",
+            state.epoch, state.da_height, confirmations
+        );
+        Ok(Some(state))
     }
 
-    async fn sync_loop(self: Arc<Self>) -> Result<(), tokio::task::JoinError> {
+    /// Checks that the epoch or checkpoint `state` was reached with is posted at its DA height.
+    async fn confirm_on_da(&self, state: &LightClientState) -> Result<()> {
+        let epochs =
+            self.da.get_finalized_epochs(state.da_height, self.prover_pubkey.as_slice()).await?;
+        let checkpoints =
+            self.da.get_checkpoints(state.da_height, self.prover_pubkey.as_slice()).await?;
+        if epochs.is_empty() && checkpoints.is_empty() {
+            bail!(
+                "no epoch or checkpoint found at DA height {}, where epoch {} was verified",
+                state.da_height,
+                state.epoch - 1
+            );
+        }
+
+        let matches_epoch = epochs.iter().any(|epoch| {
+            epoch.height + 1 == state.epoch && epoch.current_commitment == state.commitment
+        });
+        let matches_checkpoint = checkpoints.iter().any(|checkpoint| {
+            checkpoint.epoch_height == state.epoch && checkpoint.last_commitment == state.commitment
+        });
+        ensure!(
+            matches_epoch || matches_checkpoint,
+            "state at epoch {} conflicts with the DA layer at height {}",
+            state.epoch,
+            state.da_height
+        );
+        Ok(())
+    }
+
+    /// Stores `state` as the verified chain tip and publishes it once it is persisted.
+    fn store_state(&self, state: LightClientState, source: VerificationSource) -> Result<()> {
+        state.store(&**self.db).context("Failed to store light client state")?;
+        self.publish(state, source);
+        Ok(())
+    }
+
+    fn publish(&self, state: LightClientState, source: VerificationSource) {
+        // sending only fails without subscribers
+        let _ = self.events.send(VerifiedEpoch {
+            epoch: state.epoch,
+            da_height: state.da_height,
+            commitment: state.commitment,
+            source,
+        });
+    }
+
+    async fn sync_loop(self: Arc<Self>, restored: Option<LightClientState>) -> Result<()> {
         info!("starting SNARK sync loop");
-        let start_height = self.start_height;
-        spawn(async move {
-            let mut current_position = start_height;
-            let mut height_rx = self.da.subscribe_to_heights();
-
-            loop {
-                match height_rx.recv().await {
-                    Ok(target) => {
-                        for i in current_position..target {
-                            trace!("processing height: {}", i);
-                            match self.da.get_finalized_epoch(i + 1).await {
-                                Ok(Some(finalized_epoch)) => {
-                                    debug!("light client: got epochs at height {}", i + 1);
-
-                                    // TODO: Issue #144
-                                    if let Some(pubkey) = &self.prover_pubkey {
-                                        match finalized_epoch.verify_signature(*pubkey) {
-                                            Ok(_) => trace!("valid signature for epoch {}", finalized_epoch.height),
-                                            Err(e) => panic!("invalid signature in epoch {}: {:?}", i, e),
-                                        }
-                                    }
-
-                                    // Commitment verification
-                                    let prev_commitment = &finalized_epoch.prev_commitment;
-                                    let current_commitment = &finalized_epoch.current_commitment;
-                                    let mut public_values = finalized_epoch.proof.public_values.clone();
-                                    let proof_prev_commitment: Digest = public_values.read();
-                                    let proof_current_commitment: Digest = public_values.read();
-
-                                    if prev_commitment != &proof_prev_commitment
-                                        || current_commitment != &proof_current_commitment
-                                    {
-                                        error!(
-                                            "Commitment mismatch:
-                                            prev_commitment: {:?}, proof_prev_commitment: {:?},
-                                            current_commitment: {:?}, proof_current_commitment: {:?}",
-                                            prev_commitment, proof_prev_commitment,
-                                            current_commitment, proof_current_commitment
-                                        );
-                                        panic!("Commitment mismatch in epoch {}", finalized_epoch.height);
-                                    }
-
-                                    // SNARK verification
-                                    match self.client.verify(&finalized_epoch.proof, &self.verifying_key) {
-                                        Ok(_) => info!("zkSNARK for epoch {} was validated successfully", finalized_epoch.height),
-                                        Err(err) => panic!("failed to validate epoch at height {}: {:?}", finalized_epoch.height, err),
-                                    }
-                                },
-                                Ok(None) => {
-                                    debug!("no finalized epoch found at height: {}", i + 1);
-                                },
-                                Err(e) => debug!("light client: getting epoch: {}", e),
-                            };
-                        }
-                        current_position = target;
-                    },
-                    Err(broadcast::error::RecvError::Closed) => {
-                        error!("Height channel closed unexpectedly");
-                        break;
-                    },
-                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
-                        warn!("Lagged behind by {} messages", skipped);
-                    },
+        let mut current_position = restored.map_or(self.start_height, |state| state.da_height);
+        let mut height_rx = self.da.subscribe_to_heights();
+
+        loop {
+            let target = match height_rx.recv().await {
+                Ok(target) => target,
+                Err(broadcast::error::RecvError::Closed) => {
+                    error!("Height channel closed unexpectedly");
+                    break;
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    // the skipped heights are caught up on right away instead of with the
+                    // next notification
+                    warn!(
+                        "Lagged behind by {} messages, catching up to the latest height",
+                        skipped
+                    );
+                    self.da.get_latest_height().await?
+                }
+            };
+
+            if self.is_halted() {
+                bail!("sync halted");
+            }
+            if let Some(state) = restored {
+                if target < state.da_height {
+                    return Err(anyhow!(
+                        "DA layer at height {} is behind the stored state at height {}",
+                        target,
+                        state.da_height
+                    ));
+                }
+            }
+
+            // epochs covered by the newest checkpoint don't need to be verified one by one once
+            // the client fell behind, e.g. after a restart
+            if target.saturating_sub(current_position) > self.catchup_window as u64 {
+                let from = (current_position + 1)
+                    .max((target + 1).saturating_sub(self.checkpoint_search_depth));
+                if let Some(checkpoint_height) = self.find_latest_checkpoint(from, target).await? {
+                    current_position = current_position.max(checkpoint_height.saturating_sub(1));
                 }
             }
+
+            current_position = self.catch_up(current_position, target).await?;
+        }
+        Ok(())
+    }
+
+    /// Checks that `epoch` extends the verified chain tip. Without a tip, the first epoch found
+    /// starts the chain. An epoch that forks or skips ahead of the tip is rejected with an
+    /// [`EpochVerificationError`].
+    fn check_chain_tip(&self, epoch: &FinalizedEpoch) -> Result<EpochPosition> {
+        let Some(tip) = self.state()? else {
+            return Ok(EpochPosition::Next);
+        };
+
+        if epoch.height < tip.epoch {
+            // the commitments of verified epochs are kept, a different one means a fork
+            return match self.db.get_commitment(&(epoch.height + 1)) {
+                Ok(commitment) if commitment != epoch.current_commitment => {
+                    bail!(EpochVerificationError::NewCommitmentMismatch(epoch.height))
+                }
+                _ => Ok(EpochPosition::Duplicate),
+            };
+        }
+
+        if epoch.height != tip.epoch {
+            bail!(EpochVerificationError::HeightMismatch(
+                tip.epoch,
+                epoch.height
+            ));
+        }
+        if epoch.prev_commitment != tip.commitment {
+            bail!(EpochVerificationError::PreviousCommitmentMismatch(
+                epoch.height
+            ));
+        }
+        Ok(EpochPosition::Next)
+    }
+
+    /// Compares the header of a prover-signed `epoch` with the one first seen for its height,
+    /// which may have been posted at an earlier DA height, and halts if the two conflict.
+    /// Otherwise the header is stored for epochs posted later.
+    fn check_signed_header(&self, epoch: &FinalizedEpoch) -> Result<()> {
+        let Some(header) = epoch.signed_header()? else {
+            return Ok(());
+        };
+
+        let Some(known) = self.db.get_signed_epoch_header(&epoch.height)? else {
+            return self.db.put_signed_epoch_header(&epoch.height, &bincode::serialize(&header)?);
+        };
+        let known: SignedEpochHeader = bincode::deserialize(&known)?;
+        if known.header.conflicts_with(&header.header) {
+            let evidence = EquivocationEvidence {
+                first: known,
+                second: header,
+            };
+            self.record_equivocation(&evidence)?;
+            bail!(EpochVerificationError::Equivocation(
+                evidence.epoch_height()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Fetches and verifies the epochs after DA height `position` up to `target`, up to
+    /// [`LightClient::catchup_window`] heights at a time, and applies them strictly in order.
+    /// Returns the last applied height, from which the next call continues.
+    async fn catch_up(self: &Arc<Self>, position: u64, target: u64) -> Result<u64> {
+        let mut pending = PendingFetches(VecDeque::new());
+        let mut next_height = position + 1;
+        let mut applied = position;
+
+        while applied < target {
+            while next_height <= target && pending.0.len() < self.catchup_window.max(1) {
+                let this = self.clone();
+                let height = next_height;
+                pending.0.push_back(spawn(async move { this.fetch_epochs(height).await }));
+                next_height += 1;
+            }
+
+            // equivocation evidence can also be submitted through the webserver
+            if self.is_halted() {
+                bail!("sync halted before DA height {}", applied + 1);
+            }
+
+            // the task stays pending until it finished, so that it is aborted on cancellation
+            let Some(task) = pending.0.front_mut() else {
+                break;
+            };
+            let fetched = task.await?;
+            pending.0.pop_front();
+            match fetched {
+                Ok(fetched) => self.apply_epochs(applied + 1, fetched)?,
+                Err(e) => {
+                    warn!(
+                        "light client: getting epochs at height {}, retrying with the next height update: {:?}",
+                        applied + 1,
+                        e
+                    );
+                    break;
+                }
+            }
+            applied += 1;
+        }
+
+        Ok(applied)
+    }
+
+    /// Fetches the epochs at `da_height` and runs the checks that don't depend on the verified
+    /// chain tip, so that they can run for many heights in parallel.
+    async fn fetch_epochs(&self, da_height: u64) -> Result<FetchedEpochs> {
+        trace!("processing height: {}", da_height);
+        let epochs = self.da.get_finalized_epochs(da_height, self.prover_pubkey.as_slice()).await?;
+        if epochs.is_empty() {
+            debug!("no finalized epoch found at height: {}", da_height);
+        } else {
+            debug!("light client: got epochs at height {}", da_height);
+        }
+
+        if let Some(pubkey) = self.prover_pubkey {
+            if let Some(evidence) = EquivocationEvidence::find(&epochs, pubkey)? {
+                return Ok(FetchedEpochs::Equivocation(evidence));
+            }
+        }
+
+        let mut verified = Vec::with_capacity(epochs.len());
+        for epoch in epochs {
+            let verification = self.verify_finalized_epoch(&epoch).await;
+            verified.push((epoch, verification));
+        }
+        Ok(FetchedEpochs::Verified(verified))
+    }
+
+    fn apply_epochs(&self, da_height: u64, fetched: FetchedEpochs) -> Result<()> {
+        match fetched {
+            FetchedEpochs::Equivocation(evidence) => {
+                self.record_equivocation(&evidence)?;
+                bail!(EpochVerificationError::Equivocation(
+                    evidence.epoch_height()
+                ));
+            }
+            FetchedEpochs::Verified(epochs) => {
+                for (epoch, verification) in epochs {
+                    self.apply_epoch(da_height, epoch, verification)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Advances the verified chain tip to `finalized_epoch` if it extends it and passed
+    /// `verification`. A prover-signed epoch that conflicts with an earlier one or doesn't fit
+    /// the verified chain halts syncing.
+    fn apply_epoch(
+        &self,
+        da_height: u64,
+        finalized_epoch: FinalizedEpoch,
+        verification: Result<(), EpochVerificationError>,
+    ) -> Result<()> {
+        let signed_by_prover = self.signed_by_prover(&finalized_epoch);
+        if signed_by_prover {
+            self.check_signed_header(&finalized_epoch)?;
+        }
+
+        match self.check_chain_tip(&finalized_epoch) {
+            Ok(EpochPosition::Next) => {}
+            Ok(EpochPosition::Duplicate) => {
+                debug!(
+                    "light client: epoch {} was already verified",
+                    finalized_epoch.height
+                );
+                return Ok(());
+            }
+            Err(e) => match e.downcast::<EpochVerificationError>() {
+                Ok(reason) if signed_by_prover => {
+                    return Err(self.record_fault(da_height, &finalized_epoch, reason));
+                }
+                Ok(reason) => {
+                    warn!(
+                        "light client: rejecting epoch found at height {}: {}",
+                        da_height, reason
+                    );
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            },
+        }
+
+        match verification {
+            Ok(()) => {}
+            // without a configured prover key, anyone could have posted the epoch
+            Err(e) if !e.is_fault() || !signed_by_prover => {
+                warn!(
+                    "light client: ignoring epoch found at height {}: {}",
+                    da_height, e
+                );
+                return Ok(());
+            }
+            Err(e) => return Err(self.record_fault(da_height, &finalized_epoch, e)),
+        }
+
+        self.store_state(
+            LightClientState {
+                epoch: finalized_epoch.height + 1,
+                da_height,
+                commitment: finalized_epoch.current_commitment,
+            },
+            VerificationSource::Epoch,
+        )
+    }
+
+    /// Whether `epoch` is signed by the configured prover key, which makes it attributable to
+    /// the prover.
+    fn signed_by_prover(&self, epoch: &FinalizedEpoch) -> bool {
+        self.prover_pubkey.is_some_and(|pubkey| epoch.verify_signature(pubkey).is_ok())
+    }
+
+    /// Checks the signature, commitments and proof of `epoch`.
+    async fn verify_finalized_epoch(
+        &self,
+        epoch: &FinalizedEpoch,
+    ) -> Result<(), EpochVerificationError> {
+        if let Some(pubkey) = &self.prover_pubkey {
+            epoch
+                .verify_signature(*pubkey)
+                .map_err(|e| EpochVerificationError::InvalidSignature(e.to_string()))?;
+            trace!("valid signature for epoch {}", epoch.height);
+        }
+
+        let mut public_values = epoch.proof.public_values.clone();
+        let proof_prev_commitment: Digest = public_values.read();
+        let proof_current_commitment: Digest = public_values.read();
+        if epoch.prev_commitment != proof_prev_commitment
+            || epoch.current_commitment != proof_current_commitment
+        {
+            return Err(EpochVerificationError::PublicValuesMismatch(epoch.height));
+        }
+
+        self.verifier
+            .verify(&epoch.proof)
+            .await
+            .map_err(|e| EpochVerificationError::InvalidProof(epoch.height, e.to_string()))?;
+        info!(
+            "zkSNARK for epoch {} was validated successfully",
+            epoch.height
+        );
+        Ok(())
+    }
+
+    /// Halts syncing because `epoch` failed verification and persists it as evidence. The
+    /// returned error stops the sync loop.
+    fn record_fault(
+        &self,
+        da_height: u64,
+        epoch: &FinalizedEpoch,
+        reason: EpochVerificationError,
+    ) -> anyhow::Error {
+        self.halted.store(true, Ordering::SeqCst);
+        error!(
+            target: "prism::fault",
+            "light client: halting sync: epoch {} at DA height {} failed verification: {}",
+            epoch.height, da_height, reason
+        );
+
+        let fault = bincode::serialize(epoch).map_err(anyhow::Error::from).and_then(|blob| {
+            self.db.put_epoch_fault(&EpochFault {
+                da_height,
+                epoch_height: epoch.height,
+                reason: reason.to_string(),
+                blob,
+            })
+        });
+        if let Err(e) = fault {
+            error!("light client: failed to record epoch fault: {:?}", e);
+        }
+        anyhow!(reason)
+    }
+
+    /// Searches the DA layer backwards from `target` to `from` for the newest valid checkpoint
+    /// beyond the stored state and returns the DA height it was posted at. Invalid checkpoints
+    /// are skipped, errors of the DA layer or the database end the search.
+    async fn find_latest_checkpoint(&self, from: u64, target: u64) -> Result<Option<u64>> {
+        // a valid proof alone doesn't show that the checkpoint covers the prover's chain
+        let Some(pubkey) = self.prover_pubkey else {
+            debug!("light client: not searching for checkpoints without a prover key");
+            return Ok(None);
+        };
+        let verified_epoch = self.state()?.map_or(0, |state| state.epoch);
+
+        for height in (from..=target).rev() {
+            let mut checkpoints = self
+                .da
+                .get_checkpoints(height, &[pubkey])
+                .await
+                .with_context(|| format!("Failed to get checkpoints at height {}", height))?;
+            if checkpoints.is_empty() {
+                trace!("no checkpoint found at height: {}", height);
+                continue;
+            }
+
+            // newest first, in case several checkpoints were posted at the same height
+            checkpoints.sort_by_key(|checkpoint| Reverse(checkpoint.epoch_height));
+            for checkpoint in checkpoints {
+                if checkpoint.epoch_height <= verified_epoch {
+                    debug!(
+                        "light client: checkpoint at height {} is not newer than the stored state",
+                        height
+                    );
+                    return Ok(None);
+                }
+                match self.verify_checkpoint(&checkpoint, pubkey).await {
+                    Ok(()) => {
+                        info!(
+                            "light client: syncing from checkpoint at height {}, covering epochs up to {} with commitment {:?}",
+                            height, checkpoint.epoch_height, checkpoint.last_commitment
+                        );
+                        self.store_state(
+                            LightClientState {
+                                epoch: checkpoint.epoch_height,
+                                da_height: height,
+                                commitment: checkpoint.last_commitment,
+                            },
+                            VerificationSource::Checkpoint,
+                        )?;
+                        return Ok(Some(height));
+                    }
+                    Err(e) => warn!(
+                        "light client: skipping invalid checkpoint at height {}: {:?}",
+                        height, e
+                    ),
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Checks the signature and proof of `checkpoint`, and that it covers the chain from the
+    /// genesis commitment.
+    async fn verify_checkpoint(&self, checkpoint: &Checkpoint, pubkey: VerifyingKey) -> Result<()> {
+        checkpoint.verify_signature(pubkey)?;
+        ensure!(
+            checkpoint.first_commitment == genesis_commitment()?,
+            "checkpoint does not start from the genesis commitment"
+        );
+
+        let output = self.verifier.verify_checkpoint(&checkpoint.proof).await?;
+        ensure!(
+            output.epoch_count == checkpoint.epoch_height
+                && output.first_commitment == checkpoint.first_commitment
+                && output.last_commitment == checkpoint.last_commitment,
+            "checkpoint does not match the public values of its proof"
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trusted_checkpoint::tests::attestor;
+    use ed25519_consensus::SigningKey;
+    use prism_common::{
+        checkpoint::CheckpointOutput,
+        test_utils::TestTreeState,
+        tree::{Batch, HashchainResponse::*, SnarkableTree},
+    };
+    use prism_da::memory::{Block, InMemoryDataAvailabilityLayer};
+    use prism_proving::ProvingBackend;
+    use sp1_sdk::SP1ProofWithPublicValues;
+
+    fn light_client(db: Arc<Box<dyn Database>>) -> LightClient {
+        let (da, _, _) = InMemoryDataAvailabilityLayer::new(1);
+        LightClient::builder(
+            Arc::new(da),
+            db,
+            Arc::new(prism_proving::Sp1Backend::mock()),
+        )
+        .build()
+        .unwrap()
+    }
+
+    // Returns the height of the next block with epoch blobs.
+    async fn next_epoch_block(blocks: &mut broadcast::Receiver<Block>) -> u64 {
+        loop {
+            let block = blocks.recv().await.unwrap();
+            if !block.epoch_blobs.is_empty() {
+                return block.height;
+            }
+        }
+    }
+
+    // Returns the height of the next block with checkpoints.
+    async fn next_checkpoint_block(blocks: &mut broadcast::Receiver<Block>) -> u64 {
+        loop {
+            let block = blocks.recv().await.unwrap();
+            if !block.checkpoints.is_empty() {
+                return block.height;
+            }
+        }
+    }
+
+    #[test]
+    fn test_state_roundtrip() {
+        let db: Arc<Box<dyn Database>> = Arc::new(Box::new(InMemoryDatabase::new()));
+        assert_eq!(LightClientState::load(&**db).unwrap(), None);
+
+        let state = LightClientState {
+            epoch: 3,
+            da_height: 42,
+            commitment: Digest([7; 32]),
+        };
+        state.store(&**db).unwrap();
+        assert_eq!(LightClientState::load(&**db).unwrap(), Some(state));
+    }
+
+    #[tokio::test]
+    async fn test_fresh_client_has_nothing_to_restore() {
+        let db: Arc<Box<dyn Database>> = Arc::new(Box::new(InMemoryDatabase::new()));
+        assert_eq!(light_client(db).restore_state().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_refuses_state_unknown_to_da_layer() {
+        let db: Arc<Box<dyn Database>> = Arc::new(Box::new(InMemoryDatabase::new()));
+        LightClientState {
+            epoch: 1,
+            da_height: 5,
+            commitment: Digest([1; 32]),
+        }
+        .store(&**db)
+        .unwrap();
+
+        assert!(light_client(db).restore_state().await.is_err());
+    }
+
+    #[test]
+    fn test_verify_hashchain_proof_against_verified_commitment() {
+        let mut tree_state = TestTreeState::new();
+        let service = tree_state.register_service("service_1".to_string());
+        tree_state.insert_account(service.registration.clone()).unwrap();
+        let commitment = tree_state.tree.get_commitment().unwrap();
+
+        let db: Arc<Box<dyn Database>> = Arc::new(Box::new(InMemoryDatabase::new()));
+        let client = light_client(db.clone());
+        assert!(client.verified_commitment(None).is_err());
+
+        LightClientState {
+            epoch: 1,
+            da_height: 5,
+            commitment,
+        }
+        .store(&**db)
+        .unwrap();
+        assert_eq!(client.verified_commitment(None).unwrap(), (1, commitment));
+        assert!(client.verified_commitment(Some(2)).is_err());
+
+        let Found(hashchain, membership_proof) =
+            tree_state.tree.get(service.registration.key_hash).unwrap()
+        else {
+            panic!("service was not inserted");
+        };
+        client
+            .verify_hashchain_proof(
+                "service_1",
+                Some(hashchain.clone()),
+                membership_proof.proof.clone(),
+                None,
+            )
+            .unwrap();
+
+        // the proof doesn't show the hashchain for another id
+        assert!(client
+            .verify_hashchain_proof("service_2", Some(hashchain), membership_proof.proof, None)
+            .is_err());
+
+        let missing_key = KeyHash::with::<Hasher>(Digest::hash("missing"));
+        let NotFound(non_membership_proof) = tree_state.tree.get(missing_key).unwrap() else {
+            panic!("missing id was found");
+        };
+        client.verify_hashchain_proof("missing", None, non_membership_proof.proof, None).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_epochs_must_extend_chain_tip() {
+        let db: Arc<Box<dyn Database>> = Arc::new(Box::new(InMemoryDatabase::new()));
+        let client = light_client(db.clone());
+
+        let backend = prism_proving::Sp1Backend::mock();
+        let proof = backend
+            .prove(&Batch {
+                prev_root: Digest::zero(),
+                new_root: Digest::zero(),
+                proofs: Vec::new(),
+            })
+            .await
+            .unwrap();
+        let epoch = |height, prev: u8, current: u8| FinalizedEpoch {
+            height,
+            prev_commitment: Digest([prev; 32]),
+            current_commitment: Digest([current; 32]),
+            transaction_count: 0,
+            proof: proof.clone(),
+            signature: None,
+        };
+
+        // without a tip, the first epoch starts the chain
+        assert_eq!(
+            client.check_chain_tip(&epoch(3, 0, 1)).unwrap(),
+            EpochPosition::Next
+        );
+
+        LightClientState {
+            epoch: 1,
+            da_height: 2,
+            commitment: Digest([1; 32]),
+        }
+        .store(&**db)
+        .unwrap();
+
+        assert_eq!(
+            client.check_chain_tip(&epoch(1, 1, 2)).unwrap(),
+            EpochPosition::Next
+        );
+        assert_eq!(
+            client.check_chain_tip(&epoch(0, 0, 1)).unwrap(),
+            EpochPosition::Duplicate
+        );
+        // forks the verified epoch 0
+        assert!(client.check_chain_tip(&epoch(0, 0, 9)).is_err());
+        // doesn't build on the verified commitment
+        assert!(client.check_chain_tip(&epoch(1, 9, 2)).is_err());
+        // skips epoch 1
+        assert!(client.check_chain_tip(&epoch(2, 2, 3)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_faulty_epoch_halts_sync() {
+        let signing_key = SigningKey::new(rand::thread_rng());
+        let db: Arc<Box<dyn Database>> = Arc::new(Box::new(InMemoryDatabase::new()));
+        let (da, _, _) = InMemoryDataAvailabilityLayer::new(1);
+        let client = LightClient::builder(
+            Arc::new(da),
+            db.clone(),
+            Arc::new(prism_proving::Sp1Backend::mock()),
+        )
+        .prover_pubkey(signing_key.verification_key())
+        .build()
+        .unwrap();
+
+        let proof = prism_proving::Sp1Backend::mock()
+            .prove(&Batch {
+                prev_root: Digest::zero(),
+                new_root: Digest::zero(),
+                proofs: Vec::new(),
+            })
+            .await
+            .unwrap();
+        // the commitments differ from the ones the proof was generated for
+        let mut epoch = FinalizedEpoch {
+            height: 0,
+            prev_commitment: Digest([1; 32]),
+            current_commitment: Digest([2; 32]),
+            transaction_count: 0,
+            proof,
+            signature: None,
+        };
+
+        // without the prover's signature, the epoch is not attributable to the prover
+        let verification = client.verify_finalized_epoch(&epoch).await;
+        client.apply_epoch(7, epoch.clone(), verification).unwrap();
+        assert!(!client.is_halted());
+
+        epoch.insert_signature(&signing_key);
+        let verification = client.verify_finalized_epoch(&epoch).await;
+        assert!(client.apply_epoch(7, epoch.clone(), verification).is_err());
+        assert!(client.is_halted());
+        assert_eq!(
+            client.epoch_faults().unwrap(),
+            vec![EpochFault {
+                da_height: 7,
+                epoch_height: 0,
+                reason: EpochVerificationError::PublicValuesMismatch(0).to_string(),
+                blob: bincode::serialize(&epoch).unwrap(),
+            }]
+        );
+        assert_eq!(client.state().unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_faulty_epoch_without_prover_key_is_skipped() {
+        let db: Arc<Box<dyn Database>> = Arc::new(Box::new(InMemoryDatabase::new()));
+        let client = light_client(db.clone());
+
+        let proof = prism_proving::Sp1Backend::mock()
+            .prove(&Batch {
+                prev_root: Digest::zero(),
+                new_root: Digest::zero(),
+                proofs: Vec::new(),
+            })
+            .await
+            .unwrap();
+        let mut epoch = FinalizedEpoch {
+            height: 0,
+            prev_commitment: Digest([1; 32]),
+            current_commitment: Digest([2; 32]),
+            transaction_count: 0,
+            proof,
+            signature: None,
+        };
+        // a signature can't be checked without a configured key
+        epoch.insert_signature(&SigningKey::new(rand::thread_rng()));
+
+        let verification = client.verify_finalized_epoch(&epoch).await;
+        client.apply_epoch(7, epoch, verification).unwrap();
+        assert!(!client.is_halted());
+        assert!(client.epoch_faults().unwrap().is_empty());
+        assert_eq!(client.state().unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_conflicting_epochs_at_different_da_heights_halt_sync() {
+        let (da, _, mut blocks) = InMemoryDataAvailabilityLayer::new(1);
+        let da = Arc::new(da);
+        da.start().await.unwrap();
+        let signing_key = SigningKey::new(rand::thread_rng());
+        let client = |db: Arc<Box<dyn Database>>| {
+            Arc::new(
+                LightClient::builder(da.clone(), db, Arc::new(prism_proving::Sp1Backend::mock()))
+                    .prover_pubkey(signing_key.verification_key())
+                    .build()
+                    .unwrap(),
+            )
+        };
+
+        let commitment = Digest([1; 32]);
+        let proof = prism_proving::Sp1Backend::mock()
+            .prove(&Batch {
+                prev_root: commitment,
+                new_root: commitment,
+                proofs: Vec::new(),
+            })
+            .await
+            .unwrap();
+        let signed_epoch = |height, prev_commitment, current_commitment| {
+            let mut epoch = FinalizedEpoch {
+                height,
+                prev_commitment,
+                current_commitment,
+                transaction_count: 0,
+                proof: proof.clone(),
+                signature: None,
+            };
+            epoch.insert_signature(&signing_key);
+            epoch
+        };
+        let post = |epoch: FinalizedEpoch| {
+            let da = da.clone();
+            async move { da.submit_finalized_epoch(epoch).await.unwrap() }
+        };
+
+        // epoch 0 is verified before its fork is posted at a later DA height
+        let valid = signed_epoch(0, commitment, commitment);
+        post(valid.clone()).await;
+        let first_height = next_epoch_block(&mut blocks).await;
+        let fork = signed_epoch(0, commitment, Digest([9; 32]));
+        post(fork.clone()).await;
+        let second_height = next_epoch_block(&mut blocks).await;
+        assert!(second_height > first_height);
+
+        let db: Arc<Box<dyn Database>> = Arc::new(Box::new(InMemoryDatabase::new()));
+        let equivocating = client(db);
+        let result = equivocating.catch_up(0, second_height).await;
+        assert_eq!(
+            result.unwrap_err().downcast_ref::<EpochVerificationError>(),
+            Some(&EpochVerificationError::Equivocation(0))
+        );
+        assert!(equivocating.is_halted());
+        assert_eq!(
+            equivocating.equivocation_evidence().unwrap(),
+            vec![EquivocationEvidence {
+                first: valid.signed_header().unwrap().unwrap(),
+                second: fork.signed_header().unwrap().unwrap(),
+            }]
+        );
+        assert_eq!(
+            equivocating.state().unwrap().unwrap().da_height,
+            first_height
+        );
+
+        // an epoch skipping ahead of the verified tip is a fault as well
+        let db: Arc<Box<dyn Database>> = Arc::new(Box::new(InMemoryDatabase::new()));
+        let skipping = client(db);
+        skipping.catch_up(0, first_height).await.unwrap();
+        let skip = signed_epoch(2, commitment, commitment);
+        post(skip.clone()).await;
+        let skip_height = next_epoch_block(&mut blocks).await;
+        assert!(skipping.catch_up(second_height, skip_height).await.is_err());
+        assert!(skipping.is_halted());
+        assert_eq!(
+            skipping.epoch_faults().unwrap(),
+            vec![EpochFault {
+                da_height: skip_height,
+                epoch_height: 2,
+                reason: EpochVerificationError::HeightMismatch(1, 2).to_string(),
+                blob: bincode::serialize(&skip).unwrap(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_starts_from_trusted_checkpoint() {
+        let (da, _, mut blocks) = InMemoryDataAvailabilityLayer::new(1);
+        let da = Arc::new(da);
+        da.start().await.unwrap();
+
+        let commitment = Digest([1; 32]);
+        let proof = prism_proving::Sp1Backend::mock()
+            .prove(&Batch {
+                prev_root: commitment,
+                new_root: commitment,
+                proofs: Vec::new(),
+            })
+            .await
+            .unwrap();
+        da.submit_finalized_epoch(FinalizedEpoch {
+            height: 4,
+            prev_commitment: commitment,
+            current_commitment: commitment,
+            transaction_count: 0,
+            proof,
+            signature: None,
         })
         .await
+        .unwrap();
+        let da_height = loop {
+            let block = blocks.recv().await.unwrap();
+            if !block.epoch_blobs.is_empty() {
+                break block.height;
+            }
+        };
+
+        let client = |checkpoint: TrustedCheckpoint, attested: u8| {
+            LightClient::builder(
+                da.clone(),
+                Arc::new(Box::new(InMemoryDatabase::new())),
+                Arc::new(prism_proving::Sp1Backend::mock()),
+            )
+            .trusted_checkpoint(checkpoint, vec![attestor(Some(attested), false)])
+            .build()
+            .unwrap()
+        };
+        let checkpoint = TrustedCheckpoint {
+            epoch: 5,
+            commitment,
+            da_height,
+        };
+
+        let trusting = client(checkpoint, 1);
+        let mut events = trusting.subscribe();
+        let state = trusting.start_from_trusted_checkpoint().await.unwrap().unwrap();
+        assert_eq!(trusting.state().unwrap(), Some(state));
+        assert_eq!(
+            state,
+            LightClientState {
+                epoch: 5,
+                da_height,
+                commitment,
+            }
+        );
+        assert_eq!(
+            events.try_recv().unwrap(),
+            VerifiedEpoch {
+                epoch: 5,
+                da_height,
+                commitment,
+                source: VerificationSource::TrustedCheckpoint,
+            }
+        );
+        // once stored, the state is restored and checked against the checkpoint
+        assert_eq!(trusting.restore_state().await.unwrap(), Some(state));
+
+        // the epoch isn't posted at a different DA height
+        let wrong_height = client(
+            TrustedCheckpoint {
+                da_height: da_height + 1,
+                ..checkpoint
+            },
+            1,
+        );
+        assert!(wrong_height.start_from_trusted_checkpoint().await.is_err());
+        assert_eq!(wrong_height.state().unwrap(), None);
+
+        // the full node attests a different commitment
+        let conflicting = client(checkpoint, 2);
+        assert!(conflicting.start_from_trusted_checkpoint().await.is_err());
+        assert_eq!(conflicting.state().unwrap(), None);
+    }
+
+    /// Accepts every proof and returns fixed public values for checkpoints.
+    struct StubVerifier(CheckpointOutput);
+
+    #[async_trait::async_trait]
+    impl EpochVerifier for StubVerifier {
+        async fn verify(&self, _proof: &SP1ProofWithPublicValues) -> Result<()> {
+            Ok(())
+        }
+
+        async fn verify_checkpoint(
+            &self,
+            _proof: &SP1ProofWithPublicValues,
+        ) -> Result<CheckpointOutput> {
+            Ok(self.0)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_checkpoints_must_be_signed_and_start_from_genesis() {
+        let (da, _, mut blocks) = InMemoryDataAvailabilityLayer::new(1);
+        let da = Arc::new(da);
+        da.start().await.unwrap();
+        let signing_key = SigningKey::new(rand::thread_rng());
+
+        let proof = prism_proving::Sp1Backend::mock()
+            .prove(&Batch {
+                prev_root: Digest::zero(),
+                new_root: Digest::zero(),
+                proofs: Vec::new(),
+            })
+            .await
+            .unwrap();
+        let checkpoint = |first_commitment| {
+            let mut checkpoint = Checkpoint {
+                epoch_height: 3,
+                first_commitment,
+                last_commitment: Digest([3; 32]),
+                proof: proof.clone(),
+                signature: None,
+            };
+            checkpoint.insert_signature(&signing_key);
+            checkpoint
+        };
+        let client = |checkpoint: &Checkpoint, prover_pubkey: Option<VerifyingKey>| {
+            let verifier = StubVerifier(CheckpointOutput {
+                epoch_vkey: [0; 8],
+                checkpoint_vkey: [0; 8],
+                first_commitment: checkpoint.first_commitment,
+                last_commitment: checkpoint.last_commitment,
+                epoch_count: checkpoint.epoch_height,
+            });
+            let mut builder = LightClient::builder(
+                da.clone(),
+                Arc::new(Box::new(InMemoryDatabase::new())),
+                Arc::new(verifier),
+            );
+            if let Some(prover_pubkey) = prover_pubkey {
+                builder = builder.prover_pubkey(prover_pubkey);
+            }
+            builder.build().unwrap()
+        };
+
+        // the proof is valid, but doesn't link the checkpoint to the prover's chain
+        let unlinked = checkpoint(Digest([9; 32]));
+        da.submit_checkpoint(unlinked.clone()).await.unwrap();
+        let height = next_checkpoint_block(&mut blocks).await;
+        let light_client = client(&unlinked, Some(signing_key.verification_key()));
+        assert_eq!(
+            light_client.find_latest_checkpoint(0, height).await.unwrap(),
+            None
+        );
+        assert_eq!(light_client.state().unwrap(), None);
+
+        let valid = checkpoint(genesis_commitment().unwrap());
+        da.submit_checkpoint(valid.clone()).await.unwrap();
+        let height = next_checkpoint_block(&mut blocks).await;
+
+        // without the prover's key, anyone could have posted the checkpoint
+        let unauthenticated = client(&valid, None);
+        assert_eq!(
+            unauthenticated.find_latest_checkpoint(0, height).await.unwrap(),
+            None
+        );
+        assert_eq!(unauthenticated.state().unwrap(), None);
+
+        let light_client = client(&valid, Some(signing_key.verification_key()));
+        assert_eq!(
+            light_client.find_latest_checkpoint(0, height).await.unwrap(),
+            Some(height)
+        );
+        assert_eq!(
+            light_client.state().unwrap(),
+            Some(LightClientState {
+                epoch: 3,
+                da_height: height,
+                commitment: valid.last_commitment,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_applies_epochs_in_order() {
+        let (da, _, mut blocks) = InMemoryDataAvailabilityLayer::new(1);
+        let da = Arc::new(da);
+        da.start().await.unwrap();
+
+        let db: Arc<Box<dyn Database>> = Arc::new(Box::new(InMemoryDatabase::new()));
+        let client = Arc::new(
+            LightClient::builder(da.clone(), db, Arc::new(prism_proving::Sp1Backend::mock()))
+                .catchup_window(2)
+                .build()
+                .unwrap(),
+        );
+        let mut events = client.subscribe();
+
+        let commitment = Digest([1; 32]);
+        let proof = prism_proving::Sp1Backend::mock()
+            .prove(&Batch {
+                prev_root: commitment,
+                new_root: commitment,
+                proofs: Vec::new(),
+            })
+            .await
+            .unwrap();
+
+        // one epoch per block, with an empty block in between
+        let mut last_epoch_block = 0;
+        for height in 0..3 {
+            if height == 2 {
+                blocks.recv().await.unwrap();
+            }
+            da.submit_finalized_epoch(FinalizedEpoch {
+                height,
+                prev_commitment: commitment,
+                current_commitment: commitment,
+                transaction_count: 0,
+                proof: proof.clone(),
+                signature: None,
+            })
+            .await
+            .unwrap();
+            last_epoch_block = loop {
+                let block = blocks.recv().await.unwrap();
+                if !block.epoch_blobs.is_empty() {
+                    break block.height;
+                }
+            };
+        }
+
+        let target = da.get_latest_height().await.unwrap();
+        assert_eq!(client.catch_up(0, target).await.unwrap(), target);
+
+        let state = client.state().unwrap().unwrap();
+        assert_eq!(state.epoch, 3);
+        assert_eq!(state.da_height, last_epoch_block);
+        assert_eq!(state.commitment, commitment);
+
+        // every applied epoch was published, in order
+        for epoch in 1..=3 {
+            let event = events.try_recv().unwrap();
+            assert_eq!(event.epoch, epoch);
+            assert_eq!(event.source, VerificationSource::Epoch);
+        }
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stop_ends_run() {
+        let (da, _, _) = InMemoryDataAvailabilityLayer::new(1);
+        let client = Arc::new(
+            LightClient::builder(
+                Arc::new(da),
+                Arc::new(Box::new(InMemoryDatabase::new())),
+                Arc::new(prism_proving::Sp1Backend::mock()),
+            )
+            .build()
+            .unwrap(),
+        );
+
+        let running = spawn(client.clone().run());
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert!(!running.is_finished());
+
+        client.stop();
+        assert!(client.is_stopped());
+        tokio::time::timeout(std::time::Duration::from_secs(5), running)
+            .await
+            .expect("run did not return after stop")
+            .unwrap()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_config() {
+        let builder = || {
+            let (da, _, _) = InMemoryDataAvailabilityLayer::new(1);
+            LightClient::builder(
+                Arc::new(da),
+                Arc::new(Box::new(InMemoryDatabase::new())),
+                Arc::new(prism_proving::Sp1Backend::mock()),
+            )
+        };
+        let checkpoint = TrustedCheckpoint {
+            epoch: 1,
+            commitment: Digest::zero(),
+            da_height: 1,
+        };
+
+        assert!(builder().catchup_window(0).build().is_err());
+        assert!(builder().trusted_checkpoint(checkpoint, Vec::new()).build().is_err());
+        assert!(builder()
+            .trusted_checkpoint(
+                TrustedCheckpoint {
+                    epoch: 0,
+                    ..checkpoint
+                },
+                vec![attestor(Some(0), false)],
+            )
+            .build()
+            .is_err());
     }
 }
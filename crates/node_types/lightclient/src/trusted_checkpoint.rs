@@ -0,0 +1,218 @@
+use anyhow::{bail, ensure, Context, Result};
+use async_trait::async_trait;
+use ed25519_consensus::VerificationKey as VerifyingKey;
+use prism_common::digest::Digest;
+use prism_da::SignedCommitment;
+use std::sync::Arc;
+
+/// A verified state a new light client starts from instead of `start_height`, trusting all
+/// epochs before it. It is cross-checked against at least one full node and the DA layer
+/// before it is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrustedCheckpoint {
+    /// Number of epochs before the checkpoint, which is the height of the next epoch to verify.
+    pub epoch: u64,
+    /// Commitment after those epochs.
+    pub commitment: Digest,
+    /// DA height the last epoch before the checkpoint, or a checkpoint covering it, was
+    /// posted at. Syncing continues after it.
+    pub da_height: u64,
+}
+
+/// A node that states the commitment it has after a number of epochs, see
+/// [`SignedCommitment`].
+#[async_trait]
+pub trait CommitmentAttestor: Send + Sync {
+    /// Identifies the node in logs.
+    fn name(&self) -> String;
+
+    /// The key the node signs its responses with.
+    fn verifying_key(&self) -> VerifyingKey;
+
+    async fn get_signed_commitment(&self, epoch: u64) -> Result<SignedCommitment>;
+}
+
+/// Requests signed commitments from a full node's `/commitment/{epoch}` endpoint.
+pub struct FullNodeClient {
+    pub url: String,
+    pub verifying_key: VerifyingKey,
+    client: reqwest::Client,
+}
+
+impl FullNodeClient {
+    pub fn new(url: impl Into<String>, verifying_key: VerifyingKey) -> Self {
+        FullNodeClient {
+            url: url.into(),
+            verifying_key,
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl CommitmentAttestor for FullNodeClient {
+    fn name(&self) -> String {
+        self.url.clone()
+    }
+
+    fn verifying_key(&self) -> VerifyingKey {
+        self.verifying_key
+    }
+
+    async fn get_signed_commitment(&self, epoch: u64) -> Result<SignedCommitment> {
+        let url = format!("{}/commitment/{}", self.url.trim_end_matches('/'), epoch);
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .with_context(|| format!("Failed to request {}", url))?
+            .error_for_status()?;
+        response.json().await.context("Failed to decode signed commitment")
+    }
+}
+
+impl TrustedCheckpoint {
+    /// Asks every attestor for its commitment at the checkpoint's epoch and returns how many
+    /// of them confirmed it. Fails if an attestor signed a different commitment or if none of
+    /// them confirmed it. Unreachable attestors and unsigned responses are skipped.
+    pub async fn cross_check(&self, attestors: &[Arc<dyn CommitmentAttestor>]) -> Result<usize> {
+        ensure!(
+            !attestors.is_empty(),
+            "no full node configured to cross-check the trusted checkpoint against"
+        );
+
+        let mut confirmations = 0;
+        for attestor in attestors {
+            let response = match attestor.get_signed_commitment(self.epoch).await {
+                Ok(response) => response,
+                Err(e) => {
+                    warn!(
+                        "light client: {} did not attest epoch {}: {:?}",
+                        attestor.name(),
+                        self.epoch,
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            if let Err(e) = response.verify_signature(attestor.verifying_key()) {
+                warn!(
+                    "light client: ignoring commitment from {}: {:?}",
+                    attestor.name(),
+                    e
+                );
+                continue;
+            }
+            if response.epoch != self.epoch {
+                warn!(
+                    "light client: {} attested epoch {} instead of {}",
+                    attestor.name(),
+                    response.epoch,
+                    self.epoch
+                );
+                continue;
+            }
+            if response.commitment != self.commitment {
+                bail!(
+                    "{} signed commitment {:?} for epoch {}, conflicting with the trusted checkpoint",
+                    attestor.name(),
+                    response.commitment,
+                    self.epoch
+                );
+            }
+            confirmations += 1;
+        }
+
+        ensure!(
+            confirmations > 0,
+            "no full node confirmed the trusted checkpoint at epoch {}",
+            self.epoch
+        );
+        Ok(confirmations)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use ed25519_consensus::SigningKey;
+
+    struct StaticAttestor {
+        key: SigningKey,
+        /// Signs with `key`, but is expected to sign with another key.
+        impostor: bool,
+        commitment: Option<Digest>,
+    }
+
+    #[async_trait]
+    impl CommitmentAttestor for StaticAttestor {
+        fn name(&self) -> String {
+            "static".to_string()
+        }
+
+        fn verifying_key(&self) -> VerifyingKey {
+            if self.impostor {
+                SigningKey::new(rand::thread_rng()).verification_key()
+            } else {
+                self.key.verification_key()
+            }
+        }
+
+        async fn get_signed_commitment(&self, epoch: u64) -> Result<SignedCommitment> {
+            let commitment = self.commitment.ok_or_else(|| anyhow!("unreachable"))?;
+            SignedCommitment::new(epoch, commitment, &self.key)
+        }
+    }
+
+    pub(crate) fn attestor(commitment: Option<u8>, impostor: bool) -> Arc<dyn CommitmentAttestor> {
+        Arc::new(StaticAttestor {
+            key: SigningKey::new(rand::thread_rng()),
+            impostor,
+            commitment: commitment.map(|byte| Digest([byte; 32])),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_cross_check() {
+        let checkpoint = TrustedCheckpoint {
+            epoch: 4,
+            commitment: Digest([1; 32]),
+            da_height: 10,
+        };
+
+        assert!(checkpoint.cross_check(&[]).await.is_err());
+        assert_eq!(
+            checkpoint
+                .cross_check(&[
+                    attestor(Some(1), false),
+                    attestor(None, false),
+                    attestor(Some(1), false)
+                ])
+                .await
+                .unwrap(),
+            2
+        );
+
+        // unreachable nodes and forged responses don't confirm the checkpoint
+        assert!(checkpoint
+            .cross_check(&[attestor(None, false), attestor(Some(1), true)])
+            .await
+            .is_err());
+        // a forged conflicting response is ignored
+        assert_eq!(
+            checkpoint
+                .cross_check(&[attestor(Some(2), true), attestor(Some(1), false)])
+                .await
+                .unwrap(),
+            1
+        );
+        // a full node that signed a different commitment rejects the checkpoint
+        assert!(checkpoint
+            .cross_check(&[attestor(Some(1), false), attestor(Some(2), false)])
+            .await
+            .is_err());
+    }
+}
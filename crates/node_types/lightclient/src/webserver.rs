@@ -0,0 +1,298 @@
+use crate::{lightclient::verify_hashchain_proof, LightClient};
+use anyhow::{bail, Context, Result};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use jmt::proof::SparseMerkleProof;
+use prism_common::{digest::Digest, hashchain::Hashchain, hasher::Hasher};
+use prism_da::EquivocationEvidence;
+use prism_storage::EpochFault;
+use serde::{Deserialize, Serialize};
+use std::{self, net::SocketAddr, sync::Arc};
+use tower_http::cors::CorsLayer;
+use utoipa::{OpenApi, ToSchema};
+use utoipa_swagger_ui::SwaggerUi;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebServerConfig {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+}
+
+impl Default for WebServerConfig {
+    fn default() -> Self {
+        WebServerConfig {
+            enabled: true,
+            host: "127.0.0.1".to_string(),
+            port: 0,
+        }
+    }
+}
+
+pub struct WebServer {
+    pub cfg: WebServerConfig,
+    pub session: Arc<LightClient>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct VerifiedCommitmentResponse {
+    /// Number of epochs applied to reach the commitment.
+    pub epoch: u64,
+    pub commitment: Digest,
+}
+
+/// A proof as returned by a full node's `/get-hashchain` endpoint, together with the id it
+/// was requested for.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct VerifyProofRequest {
+    pub id: String,
+    /// Verified epoch whose commitment the proof is checked against. Defaults to the latest.
+    pub epoch: Option<u64>,
+    /// The hashchain proven to be included, or `None` for a proof of non-membership.
+    pub hashchain: Option<Hashchain>,
+    pub proof: SparseMerkleProof<Hasher>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct VerifyProofResponse {
+    pub valid: bool,
+    pub epoch: u64,
+    pub commitment: Digest,
+    /// Why the proof is invalid, if it is.
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct EpochFaultResponse {
+    pub da_height: u64,
+    pub epoch_height: u64,
+    pub reason: String,
+    /// Hex encoded epoch, as it was posted to the DA layer.
+    pub blob: String,
+}
+
+impl From<EpochFault> for EpochFaultResponse {
+    fn from(fault: EpochFault) -> Self {
+        EpochFaultResponse {
+            da_height: fault.da_height,
+            epoch_height: fault.epoch_height,
+            reason: fault.reason,
+            blob: hex::encode(fault.blob),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct FaultsResponse {
+    /// Whether syncing stopped at a faulty epoch.
+    pub halted: bool,
+    pub faults: Vec<EpochFaultResponse>,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        get_latest_commitment,
+        get_commitment,
+        verify_proof,
+        get_faults,
+        get_equivocation_evidence,
+        post_equivocation_evidence
+    ),
+    components(schemas(
+        VerifiedCommitmentResponse,
+        VerifyProofRequest,
+        VerifyProofResponse,
+        EpochFaultResponse,
+        FaultsResponse
+    ))
+)]
+struct ApiDoc;
+
+impl WebServer {
+    pub fn new(cfg: WebServerConfig, session: Arc<LightClient>) -> Self {
+        Self { cfg, session }
+    }
+
+    pub async fn start(&self) -> Result<()> {
+        if !self.cfg.enabled {
+            bail!("Webserver is disabled")
+        }
+
+        info!("starting webserver on {}:{}", self.cfg.host, self.cfg.port);
+        let app = Router::new()
+            .route("/verified-commitment", get(get_latest_commitment))
+            .route("/verified-commitment/:epoch", get(get_commitment))
+            .route("/verify-proof", post(verify_proof))
+            .route("/faults", get(get_faults))
+            .route(
+                "/equivocation-evidence",
+                get(get_equivocation_evidence).post(post_equivocation_evidence),
+            )
+            .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
+            .layer(CorsLayer::permissive())
+            .with_state(self.session.clone());
+
+        let addr = format!("{}:{}", self.cfg.host, self.cfg.port);
+        let addr: SocketAddr =
+            addr.parse().with_context(|| format!("Invalid webserver address {}", addr))?;
+        axum::Server::bind(&addr).serve(app.into_make_service()).await.context("Server error")?;
+
+        Ok(())
+    }
+}
+
+/// Returns the latest commitment whose epoch proof was verified by the light client.
+///
+#[utoipa::path(
+    get,
+    path = "/verified-commitment",
+    responses(
+        (status = 200, description = "Successfully retrieved verified commitment", body = VerifiedCommitmentResponse),
+        (status = 404, description = "No epoch was verified yet"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_latest_commitment(State(session): State<Arc<LightClient>>) -> impl IntoResponse {
+    match session.state() {
+        Ok(Some(state)) => (
+            StatusCode::OK,
+            Json(VerifiedCommitmentResponse {
+                epoch: state.epoch,
+                commitment: state.commitment,
+            }),
+        )
+            .into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "No epoch was verified yet").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Returns the verified commitment after the given number of epochs.
+///
+#[utoipa::path(
+    get,
+    path = "/verified-commitment/{epoch}",
+    params(("epoch" = u64, Path, description = "Number of applied epochs")),
+    responses(
+        (status = 200, description = "Successfully retrieved verified commitment", body = VerifiedCommitmentResponse),
+        (status = 404, description = "Commitment was not verified by this light client")
+    )
+)]
+async fn get_commitment(
+    State(session): State<Arc<LightClient>>,
+    Path(epoch): Path<u64>,
+) -> impl IntoResponse {
+    match session.verified_commitment(Some(epoch)) {
+        Ok((epoch, commitment)) => (
+            StatusCode::OK,
+            Json(VerifiedCommitmentResponse { epoch, commitment }),
+        )
+            .into_response(),
+        Err(e) => (StatusCode::NOT_FOUND, e.to_string()).into_response(),
+    }
+}
+
+/// Checks a membership or non-membership proof from a full node's `/get-hashchain` response
+/// against a verified commitment.
+///
+#[utoipa::path(
+    post,
+    path = "/verify-proof",
+    request_body = VerifyProofRequest,
+    responses(
+        (status = 200, description = "Proof was checked, see `valid`", body = VerifyProofResponse),
+        (status = 400, description = "Requested commitment was not verified by this light client")
+    )
+)]
+async fn verify_proof(
+    State(session): State<Arc<LightClient>>,
+    Json(request): Json<VerifyProofRequest>,
+) -> impl IntoResponse {
+    let (epoch, commitment) = match session.verified_commitment(request.epoch) {
+        Ok(verified) => verified,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+
+    let result = verify_hashchain_proof(&request.id, request.hashchain, request.proof, commitment);
+    (
+        StatusCode::OK,
+        Json(VerifyProofResponse {
+            valid: result.is_ok(),
+            epoch,
+            commitment,
+            error: result.err().map(|e| e.to_string()),
+        }),
+    )
+        .into_response()
+}
+
+/// Returns the epochs that were signed by the prover but failed verification, and whether
+/// syncing halted because of them.
+///
+#[utoipa::path(
+    get,
+    path = "/faults",
+    responses(
+        (status = 200, description = "Successfully retrieved faults", body = FaultsResponse),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_faults(State(session): State<Arc<LightClient>>) -> impl IntoResponse {
+    match session.epoch_faults() {
+        Ok(faults) => (
+            StatusCode::OK,
+            Json(FaultsResponse {
+                halted: session.is_halted(),
+                faults: faults.into_iter().map(EpochFaultResponse::from).collect(),
+            }),
+        )
+            .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Returns the evidence of the prover signing conflicting epochs, found on the DA layer or
+/// submitted by other nodes.
+///
+#[utoipa::path(
+    get,
+    path = "/equivocation-evidence",
+    responses(
+        (status = 200, description = "Successfully retrieved evidence", body = Vec<EquivocationEvidence>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_equivocation_evidence(State(session): State<Arc<LightClient>>) -> impl IntoResponse {
+    match session.equivocation_evidence() {
+        Ok(evidence) => (StatusCode::OK, Json(evidence)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Submits evidence of the prover signing conflicting epochs. Valid evidence is stored and
+/// halts syncing.
+///
+#[utoipa::path(
+    post,
+    path = "/equivocation-evidence",
+    request_body = EquivocationEvidence,
+    responses(
+        (status = 200, description = "Evidence was verified and stored"),
+        (status = 400, description = "Evidence is invalid")
+    )
+)]
+async fn post_equivocation_evidence(
+    State(session): State<Arc<LightClient>>,
+    Json(evidence): Json<EquivocationEvidence>,
+) -> impl IntoResponse {
+    match session.submit_equivocation_evidence(&evidence) {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, format!("Invalid evidence: {}", e)).into_response(),
+    }
+}
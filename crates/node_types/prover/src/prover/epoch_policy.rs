@@ -0,0 +1,166 @@
+use prism_common::{operation::Operation, transaction::Transaction};
+use serde::{Deserialize, Serialize};
+
+/// Rough estimate of the zkVM cycles spent per epoch, independent of its transactions.
+pub const EPOCH_BASE_CYCLES: u64 = 1_000_000;
+/// Rough estimate of the zkVM cycles spent verifying the insert proof of a new account.
+pub const INSERT_CYCLES: u64 = 3_000_000;
+/// Rough estimate of the zkVM cycles spent verifying the update proof of an existing account.
+pub const UPDATE_CYCLES: u64 = 2_000_000;
+
+/// [`EpochPolicy`] decides when the prover finalizes a new epoch and which of the buffered
+/// transactions it includes. Transactions that do not fit into an epoch stay buffered and
+/// carry over to the next one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EpochPolicy {
+    /// Maximum number of transactions included in a single epoch.
+    pub max_transactions: usize,
+
+    /// Minimum number of DA heights between two epochs.
+    pub min_da_heights: u64,
+
+    /// Number of DA heights after the last epoch at which buffered transactions are
+    /// finalized, even if the epoch is not full yet.
+    pub max_da_heights: u64,
+
+    /// Estimated zkVM cycle budget of a single epoch, see [`EpochPolicy::estimate_cycles`].
+    pub max_cycles: u64,
+
+    /// If set, an empty epoch is finalized after this many DA heights without an epoch,
+    /// so that light clients can tell a live prover from a stalled one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heartbeat_interval: Option<u64>,
+}
+
+impl Default for EpochPolicy {
+    fn default() -> Self {
+        EpochPolicy {
+            max_transactions: 1_000,
+            min_da_heights: 1,
+            max_da_heights: 1,
+            max_cycles: 2_000_000_000,
+            heartbeat_interval: None,
+        }
+    }
+}
+
+impl EpochPolicy {
+    /// Returns whether an epoch should be finalized `heights_since_epoch` DA heights after
+    /// the last one, given the currently buffered transactions.
+    pub fn should_finalize(&self, heights_since_epoch: u64, buffered: &[Transaction]) -> bool {
+        if heights_since_epoch < self.min_da_heights {
+            return false;
+        }
+
+        if buffered.is_empty() {
+            return self.heartbeat_interval.is_some_and(|interval| heights_since_epoch >= interval);
+        }
+
+        heights_since_epoch >= self.max_da_heights
+            || buffered.len() >= self.max_transactions
+            || self.estimate_cycles(buffered) >= self.max_cycles
+    }
+
+    /// Returns how many of the buffered transactions, in order, fit into the next epoch.
+    /// At least one transaction is included, so that a single transaction exceeding the
+    /// cycle budget cannot stall the prover.
+    pub fn epoch_size(&self, buffered: &[Transaction]) -> usize {
+        let mut cycles = EPOCH_BASE_CYCLES;
+        let mut size = 0;
+        for transaction in buffered.iter().take(self.max_transactions) {
+            cycles += Self::transaction_cycles(transaction);
+            if size > 0 && cycles > self.max_cycles {
+                break;
+            }
+            size += 1;
+        }
+        size
+    }
+
+    /// Estimates the zkVM cycles needed to prove an epoch containing `transactions`.
+    pub fn estimate_cycles(&self, transactions: &[Transaction]) -> u64 {
+        EPOCH_BASE_CYCLES + transactions.iter().map(Self::transaction_cycles).sum::<u64>()
+    }
+
+    fn transaction_cycles(transaction: &Transaction) -> u64 {
+        match transaction.entry.operation {
+            Operation::RegisterService { .. } | Operation::CreateAccount { .. } => INSERT_CYCLES,
+            Operation::AddKey { .. } | Operation::RevokeKey { .. } | Operation::AddData { .. } => {
+                UPDATE_CYCLES
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_common::transaction_builder::TransactionBuilder;
+
+    fn create_transactions(count: usize) -> Vec<Transaction> {
+        let mut tx_builder = TransactionBuilder::new();
+        (0..count)
+            .map(|i| {
+                tx_builder.register_service_with_random_keys(&format!("service_{}", i)).commit()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_waits_for_min_and_max_da_heights() {
+        let policy = EpochPolicy {
+            min_da_heights: 2,
+            max_da_heights: 5,
+            ..EpochPolicy::default()
+        };
+        let transactions = create_transactions(1);
+
+        assert!(!policy.should_finalize(1, &transactions));
+        assert!(!policy.should_finalize(4, &transactions));
+        assert!(policy.should_finalize(5, &transactions));
+        assert!(!policy.should_finalize(10, &[]));
+    }
+
+    #[test]
+    fn test_full_epoch_is_finalized_early() {
+        let policy = EpochPolicy {
+            max_transactions: 3,
+            max_da_heights: 10,
+            ..EpochPolicy::default()
+        };
+        let transactions = create_transactions(4);
+
+        assert!(!policy.should_finalize(1, &transactions[..2]));
+        assert!(policy.should_finalize(1, &transactions));
+        assert_eq!(policy.epoch_size(&transactions), 3);
+    }
+
+    #[test]
+    fn test_cycle_budget_limits_epoch_size() {
+        let policy = EpochPolicy {
+            max_cycles: EPOCH_BASE_CYCLES + 2 * INSERT_CYCLES,
+            ..EpochPolicy::default()
+        };
+        let transactions = create_transactions(3);
+        assert_eq!(policy.epoch_size(&transactions), 2);
+
+        // a single transaction over the budget is still included
+        let policy = EpochPolicy {
+            max_cycles: 0,
+            ..EpochPolicy::default()
+        };
+        assert_eq!(policy.epoch_size(&transactions), 1);
+    }
+
+    #[test]
+    fn test_heartbeat_finalizes_empty_epochs() {
+        let policy = EpochPolicy {
+            heartbeat_interval: Some(3),
+            ..EpochPolicy::default()
+        };
+
+        assert!(!policy.should_finalize(2, &[]));
+        assert!(policy.should_finalize(3, &[]));
+    }
+}
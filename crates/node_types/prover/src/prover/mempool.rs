@@ -0,0 +1,210 @@
+use anyhow::{bail, Result};
+use prism_common::{
+    digest::Digest,
+    hashchain::{Hashchain, HashchainEntry},
+    operation::Operation,
+    transaction::Transaction,
+};
+use prism_errors::TransactionError;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MempoolConfig {
+    /// Maximum number of transactions waiting to be applied in an epoch.
+    pub max_transactions: usize,
+
+    /// Maximum number of pending transactions for a single account.
+    pub max_transactions_per_account: usize,
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        MempoolConfig {
+            max_transactions: 10_000,
+            max_transactions_per_account: 100,
+        }
+    }
+}
+
+/// [`Mempool`] tracks transactions from the time they are accepted until they are
+/// applied in an epoch. New transactions are validated against the committed hashchain
+/// of their account extended by all of its pending entries, so that conflicting
+/// transactions are rejected before they are posted to the DA layer.
+pub struct Mempool {
+    cfg: MempoolConfig,
+    hashes: HashSet<Digest>,
+    /// Pending entries per account id, in the order they were accepted.
+    pending: HashMap<String, Vec<(Digest, HashchainEntry)>>,
+}
+
+impl Mempool {
+    pub fn new(cfg: MempoolConfig) -> Self {
+        Mempool {
+            cfg,
+            hashes: HashSet::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    pub fn contains(&self, hash: &Digest) -> bool {
+        self.hashes.contains(hash)
+    }
+
+    /// Validates `transaction` on top of the pending state of its account and adds it
+    /// to the mempool. `committed` is the account's hashchain in the current state.
+    pub fn insert(
+        &mut self,
+        transaction: &Transaction,
+        committed: Option<Hashchain>,
+    ) -> Result<()> {
+        if self.len() >= self.cfg.max_transactions {
+            bail!(
+                "mempool is full: {} pending transactions",
+                self.cfg.max_transactions
+            );
+        }
+
+        let pending = self.pending.get(&transaction.id).map(Vec::as_slice).unwrap_or_default();
+        if pending.len() >= self.cfg.max_transactions_per_account {
+            bail!(
+                "too many pending transactions for {}: limit is {}",
+                transaction.id,
+                self.cfg.max_transactions_per_account
+            );
+        }
+
+        let is_creation = matches!(
+            transaction.entry.operation,
+            Operation::RegisterService { .. } | Operation::CreateAccount { .. }
+        );
+        let mut hashchain = match committed {
+            Some(_) if is_creation => bail!(TransactionError::AlreadyExists(format!(
+                "Account for ID {}",
+                transaction.id
+            ))),
+            Some(hashchain) => hashchain,
+            None if is_creation || !pending.is_empty() => Hashchain::empty(),
+            None => bail!(TransactionError::NotFound(format!(
+                "Hashchain for ID {}",
+                transaction.id
+            ))),
+        };
+        for (_, entry) in pending {
+            hashchain.add_entry(entry.clone())?;
+        }
+        hashchain.add_entry(transaction.entry.clone())?;
+
+        self.track(transaction)
+    }
+
+    /// Adds `transaction` without validating it, e.g. when restoring the mempool after a restart.
+    pub fn track(&mut self, transaction: &Transaction) -> Result<()> {
+        let hash = transaction.hash()?;
+        if self.hashes.insert(hash) {
+            self.pending
+                .entry(transaction.id.clone())
+                .or_default()
+                .push((hash, transaction.entry.clone()));
+        }
+        Ok(())
+    }
+
+    /// Removes a transaction once it was applied to the state or rejected.
+    pub fn remove(&mut self, transaction: &Transaction) -> Result<()> {
+        let hash = transaction.hash()?;
+        if !self.hashes.remove(&hash) {
+            return Ok(());
+        }
+
+        if let Some(entries) = self.pending.get_mut(&transaction.id) {
+            entries.retain(|(entry_hash, _)| *entry_hash != hash);
+            if entries.is_empty() {
+                self.pending.remove(&transaction.id);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_common::transaction_builder::TransactionBuilder;
+
+    #[test]
+    fn test_pending_entries_extend_committed_hashchain() {
+        let mut mempool = Mempool::new(MempoolConfig::default());
+        let mut tx_builder = TransactionBuilder::new();
+
+        let service = tx_builder.register_service_with_random_keys("service_id").commit();
+        let account =
+            tx_builder.create_account_with_random_key("account_id", "service_id").commit();
+        // both updates build on the account creation, so only the first one can be valid
+        let first_update = tx_builder.add_random_key_verified_with_root("account_id").build();
+        let conflicting_update = tx_builder.add_random_key_verified_with_root("account_id").build();
+
+        mempool.insert(&service, None).unwrap();
+        mempool.insert(&account, None).unwrap();
+        mempool.insert(&first_update, None).unwrap();
+
+        let err = mempool.insert(&conflicting_update, None).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<TransactionError>(),
+            Some(TransactionError::InvalidPreviousHash(..))
+        ));
+        assert_eq!(mempool.len(), 3);
+    }
+
+    #[test]
+    fn test_duplicates_and_limits() {
+        let mut mempool = Mempool::new(MempoolConfig {
+            max_transactions: 2,
+            max_transactions_per_account: 1,
+        });
+        let mut tx_builder = TransactionBuilder::new();
+
+        let service = tx_builder.register_service_with_random_keys("service_id").commit();
+        let other_service = tx_builder.register_service_with_random_keys("other_service").commit();
+        let account =
+            tx_builder.create_account_with_random_key("account_id", "service_id").commit();
+        let update = tx_builder.add_random_key_verified_with_root("account_id").commit();
+
+        mempool.insert(&account, None).unwrap();
+        assert!(mempool.contains(&account.hash().unwrap()));
+        // a second pending transaction for the same account exceeds the per-account limit
+        assert!(mempool.insert(&update, None).is_err());
+
+        mempool.insert(&service, None).unwrap();
+        assert!(mempool.insert(&other_service, None).is_err());
+
+        mempool.remove(&service).unwrap();
+        mempool.insert(&other_service, None).unwrap();
+        assert_eq!(mempool.len(), 2);
+    }
+
+    #[test]
+    fn test_create_account_rejected_if_committed() {
+        let mut mempool = Mempool::new(MempoolConfig::default());
+        let mut tx_builder = TransactionBuilder::new();
+
+        let service = tx_builder.register_service_with_random_keys("service_id").commit();
+        let committed = Hashchain::from_entry(service.entry.clone()).unwrap();
+
+        let err = mempool.insert(&service, Some(committed)).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<TransactionError>(),
+            Some(TransactionError::AlreadyExists(_))
+        ));
+        assert!(mempool.is_empty());
+    }
+}
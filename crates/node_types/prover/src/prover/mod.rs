@@ -4,29 +4,53 @@ use jmt::KeyHash;
 use keystore_rs::create_signing_key;
 use prism_common::{
     digest::Digest,
-    hashchain::Hashchain,
     hasher::Hasher,
-    transaction::Transaction,
+    transaction::{RejectionKind, RejectionReceipt, Transaction, TransactionStatus},
     tree::{
         Batch,
         HashchainResponse::{self, *},
         KeyDirectoryTree, Proof, SnarkableTree,
     },
 };
-use prism_errors::DataAvailabilityError;
-use std::{self, collections::VecDeque, sync::Arc};
+use prism_errors::{
+    DataAvailabilityError, DatabaseError, EpochVerificationError, TransactionError,
+};
+use std::{
+    self,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 use tokio::{
-    sync::{broadcast, RwLock},
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc, Mutex, RwLock,
+    },
     task::JoinSet,
 };
 
 use crate::webserver::{WebServer, WebServerConfig};
-use prism_common::operation::Operation;
-use prism_da::{DataAvailabilityLayer, FinalizedEpoch};
-use prism_storage::Database;
-use sp1_sdk::{ProverClient, SP1ProvingKey, SP1Stdin, SP1VerifyingKey};
+use epoch_policy::EpochPolicy;
+use mempool::{Mempool, MempoolConfig};
+use outbox::{EpochJob, Outbox, OutboxItem};
+use prism_da::{
+    consts::{DA_RETRY_COUNT, DA_RETRY_INTERVAL},
+    Checkpoint, DataAvailabilityLayer, EquivocationEvidence, FinalizedEpoch, SignedCommitment,
+    SignedEpochHeader,
+};
+use prism_proving::{ProvingBackend, ProvingBackendConfig};
+use prism_storage::{Database, EpochFault, StagedDatabase};
+
+pub mod epoch_policy;
+pub mod mempool;
+pub mod outbox;
 
-pub const PRISM_ELF: &[u8] = include_bytes!("../../../../../elf/riscv32im-succinct-zkvm-elf");
+pub use prism_proving::PRISM_ELF;
+
+pub const PROVING_QUEUE_SIZE: usize = 8;
 
 #[derive(Clone)]
 pub struct Config {
@@ -42,6 +66,12 @@ pub struct Config {
     /// Configuration for the webserver.
     pub webserver: WebServerConfig,
 
+    /// Limits of the pool of transactions waiting to be applied in an epoch.
+    pub mempool: MempoolConfig,
+
+    /// Decides when new epochs are finalized and how many transactions they include.
+    pub epoch_policy: EpochPolicy,
+
     /// Key used to sign new [`FinalizedEpochs`].
     pub signing_key: SigningKey,
 
@@ -51,6 +81,23 @@ pub struct Config {
 
     /// DA layer height the prover should start syncing transactions from.
     pub start_height: u64,
+
+    /// Backend used to prove new epochs and verify the proofs of incoming ones.
+    pub proving_backend: ProvingBackendConfig,
+
+    /// Maximum number of executed epochs waiting to be proven. Processing of further DA heights
+    /// pauses while the queue is full.
+    pub proving_queue_size: usize,
+
+    /// Number of epochs aggregated into each [`Checkpoint`], or `None` to not create checkpoints.
+    /// Aggregation requires the proving backend to produce compressed epoch proofs.
+    pub checkpoint_interval: Option<u64>,
+
+    /// How many times a submission to the DA layer is attempted before giving up.
+    pub da_retry_count: u64,
+
+    /// Delay before the first retry of a failed DA submission, doubled on every further retry.
+    pub da_retry_interval: Duration,
 }
 
 impl Default for Config {
@@ -61,30 +108,52 @@ impl Default for Config {
             prover: true,
             batcher: true,
             webserver: WebServerConfig::default(),
+            mempool: MempoolConfig::default(),
+            epoch_policy: EpochPolicy::default(),
             signing_key: signing_key.clone(),
             verifying_key: signing_key.verification_key(),
             start_height: 1,
+            proving_backend: ProvingBackendConfig::default(),
+            proving_queue_size: PROVING_QUEUE_SIZE,
+            checkpoint_interval: None,
+            da_retry_count: DA_RETRY_COUNT,
+            da_retry_interval: DA_RETRY_INTERVAL,
         }
     }
 }
 
 #[allow(dead_code)]
 pub struct Prover {
-    pub db: Arc<dyn Database>,
+    /// All writes to [`db`] are staged in memory and persisted atomically once a DA height
+    /// has been fully processed, see [`Prover::commit_height`].
+    pub db: Arc<StagedDatabase>,
     pub da: Arc<dyn DataAvailabilityLayer>,
 
     pub cfg: Config,
 
-    /// [`pending_transactions`] is a buffer for transactions that have not yet been
-    /// posted to the DA layer.
-    pub pending_transactions: Arc<RwLock<Vec<Transaction>>>,
+    /// [`outbox`] holds transactions and epochs that have not yet been posted to the DA layer.
+    pub outbox: Arc<Outbox>,
+
+    /// [`mempool`] validates incoming transactions against the pending state of their account.
+    mempool: Arc<Mutex<Mempool>>,
+
+    /// Outbox ids of executed epochs, in order, waiting for the proving worker.
+    proving_queue: mpsc::Sender<u64>,
+    proving_queue_rx: Mutex<Option<mpsc::Receiver<u64>>>,
+
+    /// Outbox ids of epochs finalized at the DA height being processed. They are only queued
+    /// for proving once [`Prover::commit_height`] persisted their state transition.
+    finalized_epochs: Mutex<Vec<u64>>,
 
     /// [`tree`] is the representation of the JMT, prism's state tree. It is accessed via the [`db`].
-    tree: Arc<RwLock<KeyDirectoryTree<Box<dyn Database>>>>,
+    tree: Arc<RwLock<KeyDirectoryTree<StagedDatabase>>>,
+
+    proving_backend: Arc<dyn ProvingBackend>,
 
-    prover_client: Arc<RwLock<ProverClient>>,
-    proving_key: SP1ProvingKey,
-    verifying_key: SP1VerifyingKey,
+    /// Set once an epoch signed by the prover failed verification, or the prover signed
+    /// conflicting epochs. Syncing stops at the faulty DA height while the webserver keeps
+    /// serving the last verified state.
+    halted: AtomicBool,
 }
 
 #[allow(dead_code)]
@@ -94,6 +163,37 @@ impl Prover {
         da: Arc<dyn DataAvailabilityLayer>,
         cfg: &Config,
     ) -> Result<Prover> {
+        Self::with_proving_backend(db, da, cfg, cfg.proving_backend.backend())
+    }
+
+    /// Creates a prover using `proving_backend` instead of the one selected in
+    /// [`Config::proving_backend`].
+    pub fn with_proving_backend(
+        db: Arc<Box<dyn Database>>,
+        da: Arc<dyn DataAvailabilityLayer>,
+        cfg: &Config,
+        proving_backend: Arc<dyn ProvingBackend>,
+    ) -> Result<Prover> {
+        let outbox = Arc::new(Outbox::new(db.clone())?);
+
+        // transactions that were accepted before a restart, but not yet applied
+        let mut mempool = Mempool::new(cfg.mempool.clone());
+        for (_, transaction) in outbox.transactions()? {
+            mempool.track(&transaction)?;
+        }
+        for (entry, transaction) in outbox.posted_transactions()? {
+            match db.get_transaction_status(&transaction.hash()?)? {
+                Some(TransactionStatus::Included { .. } | TransactionStatus::Rejected(_)) => {
+                    outbox.remove(&[entry.id])?
+                }
+                _ => mempool.track(&transaction)?,
+            }
+        }
+        for transaction in db.get_buffered_transactions()?.values().flatten() {
+            mempool.track(transaction)?;
+        }
+
+        let db = Arc::new(StagedDatabase::new(db));
         let saved_epoch = match db.get_epoch() {
             Ok(epoch) => epoch,
             Err(_) => {
@@ -105,22 +205,20 @@ impl Prover {
 
         let tree = Arc::new(RwLock::new(KeyDirectoryTree::load(db.clone(), saved_epoch)));
 
-        #[cfg(feature = "mock_prover")]
-        let prover_client = ProverClient::mock();
-        #[cfg(not(feature = "mock_prover"))]
-        let prover_client = ProverClient::local();
-
-        let (pk, vk) = prover_client.setup(PRISM_ELF);
+        let (proving_queue, proving_queue_rx) = mpsc::channel(cfg.proving_queue_size);
 
         Ok(Prover {
             db: db.clone(),
             da,
             cfg: cfg.clone(),
-            proving_key: pk,
-            verifying_key: vk,
-            prover_client: Arc::new(RwLock::new(prover_client)),
+            proving_backend,
             tree,
-            pending_transactions: Arc::new(RwLock::new(Vec::new())),
+            outbox,
+            mempool: Arc::new(Mutex::new(mempool)),
+            proving_queue,
+            proving_queue_rx: Mutex::new(Some(proving_queue_rx)),
+            finalized_epochs: Mutex::new(Vec::new()),
+            halted: AtomicBool::new(false),
         })
     }
 
@@ -136,6 +234,11 @@ impl Prover {
         let mut futures = JoinSet::new();
         futures.spawn(main_loop);
 
+        if self.cfg.prover {
+            let proving_worker = self.clone().proving_loop();
+            futures.spawn(proving_worker);
+        }
+
         if self.cfg.batcher {
             let batch_poster = self.clone().post_batch_loop();
             futures.spawn(batch_poster);
@@ -155,6 +258,15 @@ impl Prover {
     }
 
     async fn main_loop(self: Arc<Self>) -> Result<()> {
+        if !self.db.get_equivocation_evidence()?.is_empty() {
+            self.halted.store(true, Ordering::SeqCst);
+            error!(
+                target: "prism::fault",
+                "not syncing: the prover signed conflicting epochs, see /equivocation-evidence"
+            );
+            return std::future::pending().await;
+        }
+
         let mut height_rx = self.da.subscribe_to_heights();
         let historical_sync_height = height_rx.recv().await?;
 
@@ -167,7 +279,81 @@ impl Prover {
             }
         };
 
-        self.sync_loop(start_height, historical_sync_height, height_rx).await
+        let result = self.sync_loop(start_height, historical_sync_height, height_rx).await;
+        if self.is_halted() {
+            // a faulty epoch must not take down the webserver, which still serves the
+            // last verified state
+            return std::future::pending().await;
+        }
+        result
+    }
+
+    /// Whether syncing stopped because of a faulty epoch, see [`Prover::get_epoch_faults`].
+    pub fn is_halted(&self) -> bool {
+        self.halted.load(Ordering::SeqCst)
+    }
+
+    /// Returns the evidence of all epochs that were signed by the prover but failed
+    /// verification, ordered by DA height.
+    pub fn get_epoch_faults(&self) -> Result<Vec<EpochFault>> {
+        self.db.get_epoch_faults()
+    }
+
+    /// Returns the evidence of the prover signing conflicting epochs, ordered by epoch height.
+    pub fn get_equivocation_evidence(&self) -> Result<Vec<EquivocationEvidence>> {
+        self.db
+            .get_equivocation_evidence()?
+            .iter()
+            .map(|evidence| Ok(bincode::deserialize(evidence)?))
+            .collect()
+    }
+
+    /// Accepts equivocation evidence found by another node. Valid evidence halts syncing,
+    /// just like evidence found on the DA layer.
+    pub fn submit_equivocation_evidence(&self, evidence: &EquivocationEvidence) -> Result<()> {
+        evidence.verify(self.cfg.verifying_key)?;
+        self.record_equivocation(evidence)
+    }
+
+    fn record_equivocation(&self, evidence: &EquivocationEvidence) -> Result<()> {
+        self.db
+            .put_equivocation_evidence(&evidence.epoch_height(), &bincode::serialize(evidence)?)?;
+        self.halted.store(true, Ordering::SeqCst);
+
+        error!(
+            target: "prism::fault",
+            "halting sync: prover signed conflicting epochs at height {}",
+            evidence.epoch_height()
+        );
+        Ok(())
+    }
+
+    /// Compares the header of `epoch` with the prover-signed header first seen for its height,
+    /// which may have been posted at an earlier DA height, and halts if the two conflict.
+    /// Otherwise the header is stored for epochs posted later.
+    fn check_signed_header(&self, epoch: &FinalizedEpoch) -> Result<()> {
+        if epoch.verify_signature(self.cfg.verifying_key).is_err() {
+            return Ok(());
+        }
+        let Some(header) = epoch.signed_header()? else {
+            return Ok(());
+        };
+
+        let Some(known) = self.db.get_signed_epoch_header(&epoch.height)? else {
+            return self.db.put_signed_epoch_header(&epoch.height, &bincode::serialize(&header)?);
+        };
+        let known: SignedEpochHeader = bincode::deserialize(&known)?;
+        if known.header.conflicts_with(&header.header) {
+            let evidence = EquivocationEvidence {
+                first: known,
+                second: header,
+            };
+            self.record_equivocation(&evidence)?;
+            bail!(EpochVerificationError::Equivocation(
+                evidence.epoch_height()
+            ));
+        }
+        Ok(())
     }
 
     async fn sync_loop(
@@ -183,15 +369,21 @@ impl Prover {
             self.db.set_commitment(&0, &initial_commitment)?;
         }
 
-        // TODO: Should be persisted in database for crash recovery
-        let mut buffered_transactions: VecDeque<Transaction> = VecDeque::new();
+        // transactions buffered before a restart are picked up from the database
+        let buffered = self.db.get_buffered_transactions()?;
+        if !buffered.is_empty() {
+            info!(
+                "restored buffered transactions from {} DA heights",
+                buffered.len()
+            );
+        }
+
         let mut current_height = start_height;
 
         while current_height <= end_height {
-            self.process_da_height(current_height, &mut buffered_transactions, false).await?;
-            // TODO: Race between set_epoch and set_last_synced_height
-            self.db.set_last_synced_height(&current_height)?;
+            self.process_da_height(current_height, false).await?;
             current_height += 1;
+            self.commit_height(current_height).await?;
         }
 
         info!(
@@ -200,31 +392,78 @@ impl Prover {
         );
 
         loop {
-            let height = incoming_heights.recv().await?;
-            if height != current_height {
-                return Err(anyhow!(
-                    "heights are not sequential: expected {}, got {}",
-                    current_height,
-                    height
-                ));
+            let target = match incoming_heights.recv().await {
+                Ok(height) if height > current_height => {
+                    return Err(anyhow!(
+                        "heights are not sequential: expected {}, got {}",
+                        current_height,
+                        height
+                    ));
+                }
+                Ok(height) => height,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!(
+                        "lagged behind by {} heights, catching up to the latest height",
+                        skipped
+                    );
+                    self.da.get_latest_height().await?
+                }
+                Err(e) => return Err(e.into()),
+            };
+            if target < current_height {
+                // already processed while catching up
+                trace!("skipping height {}", target);
+                continue;
+            }
+
+            while current_height <= target {
+                self.process_da_height(current_height, true).await?;
+                current_height += 1;
+                self.commit_height(current_height).await?;
             }
-            self.process_da_height(height, &mut buffered_transactions, true).await?;
-            current_height += 1;
-            // TODO: Race between set_epoch and set_last_synced_height - updating these should be a single atomic transaction
-            self.db.set_last_synced_height(&current_height)?;
         }
     }
 
-    async fn process_da_height(
-        &self,
-        height: u64,
-        buffered_transactions: &mut VecDeque<Transaction>,
-        is_real_time: bool,
-    ) -> Result<()> {
+    /// Persists the tree nodes, epoch counter and commitments produced while processing a
+    /// DA height together with the next height to sync from, in one atomic step.
+    /// A crash before this point leaves the database at the previously committed height.
+    /// Epochs finalized at the height are queued for proving afterwards, so that a state
+    /// transition that was never committed is not proven.
+    async fn commit_height(&self, next_height: u64) -> Result<()> {
+        let finalized = std::mem::take(&mut *self.finalized_epochs.lock().await);
+        self.db.set_last_synced_height(&next_height)?;
+        self.db.commit().context("Failed to commit state for DA height")?;
+        self.remove_executed_transactions()?;
+
+        for id in finalized {
+            // waits while the proving worker is [`Config::proving_queue_size`] epochs behind
+            self.proving_queue.send(id).await.context("Proving worker has stopped")?;
+        }
+        Ok(())
+    }
+
+    /// Removes posted transactions from the outbox once the epoch executing them is committed.
+    fn remove_executed_transactions(&self) -> Result<()> {
+        for (entry, transaction) in self.outbox.posted_transactions()? {
+            if let Some(TransactionStatus::Included { .. } | TransactionStatus::Rejected(_)) =
+                self.db.get_transaction_status(&transaction.hash()?)?
+            {
+                self.outbox.remove(&[entry.id])?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn process_da_height(&self, height: u64, is_real_time: bool) -> Result<()> {
+        // equivocation evidence can also be submitted through the webserver
+        if self.is_halted() {
+            bail!("sync halted before DA height {}", height);
+        }
+
         let current_epoch = self.db.get_epoch()?;
 
         let transactions = self.da.get_transactions(height).await?;
-        let epoch_result = self.da.get_finalized_epoch(height).await?;
+        let epochs = self.da.get_finalized_epochs(height, &[self.cfg.verifying_key]).await?;
 
         debug!(
             "processing {} height {}, current_epoch: {}",
@@ -233,32 +472,119 @@ impl Prover {
             current_epoch
         );
 
-        if let Some(epoch) = epoch_result {
-            // run all buffered transactions from the last celestia blocks and increment current_epoch
-            self.process_epoch(epoch, buffered_transactions).await?;
-        } else {
+        if let Some(evidence) = EquivocationEvidence::find(&epochs, self.cfg.verifying_key)? {
+            self.record_equivocation(&evidence)?;
+            bail!(EpochVerificationError::Equivocation(
+                evidence.epoch_height()
+            ));
+        }
+
+        // epochs posted at earlier DA heights are only known by their signed headers
+        for epoch in &epochs {
+            self.check_signed_header(epoch)?;
+        }
+
+        if epochs.is_empty() {
             debug!("No transactions to process at height {}", height);
         }
+        for epoch in epochs {
+            // run the buffered transactions included in the epoch and increment current_epoch
+            match self.process_epoch(&epoch).await {
+                Ok(()) => self.db.set_last_epoch_da_height(&height)?,
+                Err(e) => match e.downcast_ref::<EpochVerificationError>() {
+                    Some(reason) if !reason.is_fault() => {
+                        warn!("ignoring epoch at height {}: {}", height, reason);
+                    }
+                    Some(reason) => {
+                        self.record_fault(height, &epoch, reason).await?;
+                        return Err(e);
+                    }
+                    None => return Err(e),
+                },
+            }
+        }
 
-        if is_real_time && !buffered_transactions.is_empty() && self.cfg.prover {
-            let all_transactions: Vec<Transaction> = buffered_transactions.drain(..).collect();
-            self.finalize_new_epoch(current_epoch, all_transactions).await?;
+        if is_real_time && self.cfg.prover {
+            let buffered: Vec<Transaction> =
+                self.db.get_buffered_transactions()?.into_values().flatten().collect();
+            let last_epoch_da_height =
+                self.db.get_last_epoch_da_height()?.unwrap_or(self.cfg.start_height);
+            let heights_since_epoch = height.saturating_sub(last_epoch_da_height);
+
+            if self.cfg.epoch_policy.should_finalize(heights_since_epoch, &buffered) {
+                let epoch_size = self.cfg.epoch_policy.epoch_size(&buffered);
+                if epoch_size < buffered.len() {
+                    info!(
+                        "carrying over {} transactions to the next epoch",
+                        buffered.len() - epoch_size
+                    );
+                }
+                let transactions = self.take_buffered_transactions(epoch_size)?;
+                self.finalize_new_epoch(current_epoch, transactions).await?;
+                self.db.set_last_epoch_da_height(&height)?;
+            }
         }
 
-        // If there are new transactions at this height, add them to the queue to
+        // If there are new transactions at this height, add them to the buffer to
         // be included in the next finalized epoch.
         if !transactions.is_empty() {
-            buffered_transactions.extend(transactions);
+            self.db.set_buffered_transactions(&height, &transactions)?;
         }
 
         Ok(())
     }
 
-    async fn process_epoch(
+    /// Removes the first `count` buffered transactions from the database and returns them in
+    /// DA height order, together with the DA height each transaction was read from.
+    fn take_buffered_transactions(&self, count: usize) -> Result<Vec<(u64, Transaction)>> {
+        let mut taken = Vec::new();
+        for (height, mut transactions) in self.db.get_buffered_transactions()? {
+            let remaining = count - taken.len();
+            if remaining == 0 {
+                break;
+            }
+
+            if transactions.len() > remaining {
+                let carried_over = transactions.split_off(remaining);
+                self.db.set_buffered_transactions(&height, &carried_over)?;
+            } else {
+                self.db.remove_buffered_transactions(&height)?;
+            }
+            taken.extend(transactions.into_iter().map(|transaction| (height, transaction)));
+        }
+        Ok(taken)
+    }
+
+    /// Halts syncing at `da_height` because `epoch` failed verification. The writes staged for
+    /// the height are dropped, so the state stays at the last verified epoch, and the epoch
+    /// is persisted as evidence.
+    async fn record_fault(
         &self,
-        epoch: FinalizedEpoch,
-        buffered_transactions: &mut VecDeque<Transaction>,
+        da_height: u64,
+        epoch: &FinalizedEpoch,
+        reason: &EpochVerificationError,
     ) -> Result<()> {
+        self.db.discard();
+        *self.tree.write().await = KeyDirectoryTree::load(self.db.clone(), self.db.get_epoch()?);
+
+        let fault = EpochFault {
+            da_height,
+            epoch_height: epoch.height,
+            reason: reason.to_string(),
+            blob: bincode::serialize(epoch)?,
+        };
+        self.db.put_epoch_fault(&fault)?;
+        self.halted.store(true, Ordering::SeqCst);
+
+        error!(
+            target: "prism::fault",
+            "halting sync: epoch {} at DA height {} failed verification: {}",
+            epoch.height, da_height, reason
+        );
+        Ok(())
+    }
+
+    async fn process_epoch(&self, epoch: &FinalizedEpoch) -> Result<()> {
         let mut current_epoch = self.db.get_epoch()?;
 
         // If prover is enabled and is actively producing new epochs, it has
@@ -269,53 +595,53 @@ impl Prover {
             return Ok(());
         }
 
-        // TODO: Issue #144
-        match epoch.verify_signature(self.cfg.verifying_key) {
-            Ok(_) => trace!("valid signature for epoch {}", epoch.height),
-            Err(e) => panic!("invalid signature in epoch {}: {:?}", epoch.height, e),
+        if let Err(e) = epoch.verify_signature(self.cfg.verifying_key) {
+            bail!(EpochVerificationError::InvalidSignature(e.to_string()));
         }
+        trace!("valid signature for epoch {}", epoch.height);
 
         let prev_commitment = self.db.get_commitment(&current_epoch)?;
 
         if epoch.height != current_epoch {
-            return Err(anyhow!(
-                "epoch height mismatch: expected {}, got {}",
+            bail!(EpochVerificationError::HeightMismatch(
                 current_epoch,
                 epoch.height
             ));
         }
 
         if epoch.prev_commitment != prev_commitment {
-            return Err(anyhow!(
-                "previous commitment mismatch at epoch {}",
+            bail!(EpochVerificationError::PreviousCommitmentMismatch(
                 current_epoch
             ));
         }
 
-        let all_transactions: Vec<Transaction> = buffered_transactions.drain(..).collect();
-        if !all_transactions.is_empty() {
-            self.execute_block(all_transactions).await?;
+        let transactions = self.take_buffered_transactions(epoch.transaction_count as usize)?;
+        if transactions.len() as u64 != epoch.transaction_count {
+            bail!(EpochVerificationError::MissingTransactions(
+                current_epoch,
+                epoch.transaction_count,
+                transactions.len() as u64
+            ));
+        }
+        if !transactions.is_empty() {
+            self.execute_block(current_epoch, transactions).await?;
         }
 
         let new_commitment = self.get_commitment().await?;
         if epoch.current_commitment != new_commitment {
-            return Err(anyhow!(
-                "new commitment mismatch at epoch {}",
-                current_epoch
-            ));
+            bail!(EpochVerificationError::NewCommitmentMismatch(current_epoch));
         }
 
-        let client = self.prover_client.read().await;
-        match client.verify(&epoch.proof, &self.verifying_key) {
-            Ok(_) => info!(
-                "zkSNARK for epoch {} was validated successfully",
-                epoch.height
-            ),
-            Err(err) => panic!(
-                "failed to validate epoch at height {}: {:?}",
-                epoch.height, err
-            ),
+        if let Err(e) = self.proving_backend.verify(&epoch.proof).await {
+            bail!(EpochVerificationError::InvalidProof(
+                epoch.height,
+                e.to_string()
+            ));
         }
+        info!(
+            "zkSNARK for epoch {} was validated successfully",
+            epoch.height
+        );
 
         debug!(
             "processed epoch {}. new commitment: {:?}",
@@ -329,84 +655,377 @@ impl Prover {
         Ok(())
     }
 
-    async fn execute_block(&self, transactions: Vec<Transaction>) -> Result<Vec<Proof>> {
+    /// Applies `transactions` to the state as part of the epoch with height `epoch_height`,
+    /// recording for each transaction whether it was included or rejected. Each transaction
+    /// is paired with the DA height it was read from.
+    async fn execute_block(
+        &self,
+        epoch_height: u64,
+        transactions: Vec<(u64, Transaction)>,
+    ) -> Result<Vec<Proof>> {
         debug!("executing block with {} transactions", transactions.len());
 
         let mut proofs = Vec::new();
+        let mut receipts = Vec::new();
+
+        for (da_height, transaction) in transactions {
+            let hash = transaction.hash()?;
+            // a batch is posted again if the node stopped before removing it from the outbox.
+            // the duplicate would be rejected, but must not replace the status of the original
+            if let Some(TransactionStatus::Included { epoch }) =
+                self.db.get_transaction_status(&hash)?
+            {
+                debug!(
+                    "skipping transaction {} at height {}, already included in epoch {}",
+                    hash, da_height, epoch
+                );
+                self.mempool.lock().await.remove(&transaction)?;
+                continue;
+            }
 
-        for transaction in transactions {
-            match self.process_transaction(transaction.clone()).await {
-                Ok(proof) => proofs.push(proof),
+            let result = self.process_transaction(transaction.clone()).await;
+            self.mempool.lock().await.remove(&transaction)?;
+
+            match result {
+                Ok(proof) => {
+                    proofs.push(proof);
+                    self.db.set_transaction_status(
+                        &hash,
+                        &TransactionStatus::Included {
+                            epoch: epoch_height,
+                        },
+                    )?;
+                }
                 Err(e) => {
+                    // only errors caused by the transaction itself are the same on every node
+                    let Some(error) = e.downcast_ref::<TransactionError>() else {
+                        return Err(e.context(format!(
+                            "Failed to execute transaction {} at height {}",
+                            hash, da_height
+                        )));
+                    };
                     // Log the error and continue with the next transaction
                     warn!(
                         "Failed to process transaction: {:?}. Error: {}",
-                        transaction, e
+                        transaction, error
                     );
+                    let receipt = RejectionReceipt {
+                        transaction_hash: hash,
+                        da_height,
+                        epoch: epoch_height,
+                        kind: RejectionKind::from(error),
+                        message: error.to_string(),
+                    };
+                    self.db.set_transaction_status(
+                        &hash,
+                        &TransactionStatus::Rejected(receipt.clone()),
+                    )?;
+                    receipts.push(receipt);
                 }
             }
         }
 
+        if !receipts.is_empty() {
+            self.db.set_rejection_receipts(&epoch_height, &receipts)?;
+        }
+
         Ok(proofs)
     }
 
+    /// Applies `transactions` as the epoch with height `epoch_height` and queues it for proving.
+    /// Proving and submission happen on the proving worker, so the next DA height can be
+    /// processed while the epoch is proven.
     async fn finalize_new_epoch(
         &self,
         epoch_height: u64,
-        transactions: Vec<Transaction>,
+        transactions: Vec<(u64, Transaction)>,
     ) -> Result<()> {
         let prev_commitment = self.get_commitment().await?;
 
-        let proofs = self.execute_block(transactions).await?;
+        let transaction_count = transactions.len() as u64;
+        let proofs = self.execute_block(epoch_height, transactions).await?;
 
         let new_commitment = self.get_commitment().await?;
 
-        let finalized_epoch =
-            self.prove_epoch(epoch_height, prev_commitment, new_commitment, proofs).await?;
-
-        self.da.submit_finalized_epoch(finalized_epoch).await?;
+        let id = match self.find_outbox_epoch(
+            epoch_height,
+            prev_commitment,
+            new_commitment,
+            transaction_count,
+        )? {
+            Some(id) => {
+                info!("reusing epoch {} from the outbox", epoch_height);
+                id
+            }
+            None => self.outbox.push(&OutboxItem::EpochJob(EpochJob {
+                height: epoch_height,
+                transaction_count,
+                batch: Batch {
+                    prev_root: prev_commitment,
+                    new_root: new_commitment,
+                    proofs,
+                },
+            }))?,
+        };
 
         let new_epoch_height = epoch_height + 1;
         self.db.set_commitment(&new_epoch_height, &new_commitment)?;
         self.db.set_epoch(&new_epoch_height)?;
 
-        info!("finalized new epoch at height {}", epoch_height);
+        self.finalized_epochs.lock().await.push(id);
+
+        info!(
+            "finalized new epoch at height {}, proving once committed",
+            epoch_height
+        );
 
         Ok(())
     }
 
-    async fn prove_epoch(
+    /// Returns the outbox entry of an epoch that was executed before but not yet submitted,
+    /// e.g. because the node restarted after its submission failed. Entries for this or later
+    /// epochs that do not match the executed state are outdated and dropped from the outbox.
+    fn find_outbox_epoch(
         &self,
         epoch_height: u64,
         prev_commitment: Digest,
         new_commitment: Digest,
-        proofs: Vec<Proof>,
-    ) -> Result<FinalizedEpoch> {
-        let batch = Batch {
-            prev_root: prev_commitment,
-            new_root: new_commitment,
-            proofs,
+        transaction_count: u64,
+    ) -> Result<Option<u64>> {
+        let mut found = None;
+        for (entry, item) in self.outbox.entries()? {
+            let (height, matches) = match item {
+                OutboxItem::EpochJob(job) => (
+                    job.height,
+                    job.batch.prev_root == prev_commitment
+                        && job.batch.new_root == new_commitment
+                        && job.transaction_count == transaction_count,
+                ),
+                OutboxItem::Epoch(epoch) => (
+                    epoch.height,
+                    epoch.prev_commitment == prev_commitment
+                        && epoch.current_commitment == new_commitment
+                        && epoch.transaction_count == transaction_count,
+                ),
+                _ => continue,
+            };
+
+            if found.is_none() && height == epoch_height && matches {
+                found = Some(entry.id);
+            } else if height >= epoch_height {
+                warn!(
+                    "dropping outdated epoch {} from the outbox after {} failed attempts",
+                    height, entry.attempts
+                );
+                self.outbox.remove(&[entry.id])?;
+            }
+        }
+        Ok(found)
+    }
+
+    /// Proves and submits the epochs queued by [`Prover::commit_height`], strictly in order.
+    async fn proving_loop(self: Arc<Self>) -> Result<()> {
+        let mut queue = self
+            .proving_queue_rx
+            .lock()
+            .await
+            .take()
+            .ok_or_else(|| anyhow!("Proving worker is already running"))?;
+
+        self.submit_pending_epochs().await?;
+
+        while let Some(id) = queue.recv().await {
+            self.prove_and_submit(id).await?;
+        }
+        Err(anyhow!("Proving queue was closed"))
+    }
+
+    /// Proves and submits all epochs left in the outbox whose state transition was already
+    /// applied, e.g. after a restart. Epochs that were not applied yet are left to
+    /// [`Prover::finalize_new_epoch`], which reuses or drops them.
+    async fn submit_pending_epochs(&self) -> Result<()> {
+        let current_epoch = self.db.get_epoch()?;
+
+        let mut pending: Vec<(u64, u64)> = self
+            .outbox
+            .entries()?
+            .into_iter()
+            .filter_map(|(entry, item)| match item {
+                OutboxItem::EpochJob(job) => Some((job.height, entry.id)),
+                OutboxItem::Epoch(epoch) => Some((epoch.height, entry.id)),
+                _ => None,
+            })
+            .filter(|(height, _)| *height < current_epoch)
+            .collect();
+        pending.sort();
+
+        for (_, id) in pending {
+            self.prove_and_submit(id).await?;
+        }
+        Ok(())
+    }
+
+    /// Proves the epoch stored in the outbox entry `id` if necessary and submits it. The proof
+    /// is stored in the outbox before submission, so it is not generated again if the
+    /// submission fails. Entries that were already submitted are skipped.
+    async fn prove_and_submit(&self, id: u64) -> Result<()> {
+        let Some((_, item)) = self.outbox.get(id)? else {
+            trace!("outbox entry {} was already submitted", id);
+            return Ok(());
+        };
+
+        let (id, epoch) = match item {
+            OutboxItem::EpochJob(job) => {
+                let epoch = self.prove_epoch(job).await?;
+                let epoch_id = self.outbox.push(&OutboxItem::Epoch(epoch.clone()))?;
+                self.outbox.remove(&[id])?;
+                (epoch_id, epoch)
+            }
+            OutboxItem::Epoch(epoch) => (id, epoch),
+            _ => bail!("outbox entry {} is not an epoch", id),
         };
 
-        let mut stdin = SP1Stdin::new();
-        stdin.write(&batch);
-        let client = self.prover_client.read().await;
+        self.submit_with_retries(&[id], || self.da.submit_finalized_epoch(epoch.clone())).await?;
+        info!("submitted epoch {}", epoch.height);
 
-        info!("generating proof for epoch at height {}", epoch_height);
-        #[cfg(not(feature = "groth16"))]
-        let proof = client.prove(&self.proving_key, stdin).run()?;
+        let Some(interval) = self.cfg.checkpoint_interval else {
+            self.outbox.remove(&[id])?;
+            return Ok(());
+        };
+
+        // the proof is kept until it is aggregated into the next checkpoint
+        self.outbox.push(&OutboxItem::CheckpointEpoch(epoch.clone()))?;
+        self.outbox.remove(&[id])?;
+
+        if (epoch.height + 1) % interval == 0 {
+            // epochs stay in the outbox if this fails and are aggregated into the next checkpoint
+            if let Err(e) = self.create_checkpoint().await {
+                error!(
+                    "failed to create checkpoint after epoch {}: {:?}",
+                    epoch.height, e
+                );
+            }
+        }
+        Ok(())
+    }
 
-        #[cfg(feature = "groth16")]
-        let proof = client.prove(&self.proving_key, stdin).groth16().run()?;
+    /// Aggregates the submitted epochs since the latest checkpoint into a new checkpoint and
+    /// submits it. The new checkpoint replaces the latest one in the outbox.
+    async fn create_checkpoint(&self) -> Result<()> {
+        let previous = self.outbox.latest_checkpoint()?;
+        let covered_height = previous.as_ref().map_or(0, |(_, checkpoint)| checkpoint.epoch_height);
+
+        // epochs below the covered height are left over from an interrupted checkpoint
+        let (mut ids, epochs): (Vec<u64>, Vec<FinalizedEpoch>) = self
+            .outbox
+            .checkpoint_epochs()?
+            .into_iter()
+            .map(|(entry, epoch)| (entry.id, epoch))
+            .unzip();
+        let epochs: Vec<FinalizedEpoch> =
+            epochs.into_iter().filter(|epoch| epoch.height >= covered_height).collect();
+
+        let Some(last_epoch) = epochs.last() else {
+            return Ok(());
+        };
+        for (expected_height, epoch) in (covered_height..).zip(&epochs) {
+            if epoch.height != expected_height {
+                bail!(
+                    "epoch {} is missing for checkpoint, found epoch {}",
+                    expected_height,
+                    epoch.height
+                );
+            }
+        }
+
+        info!(
+            "generating checkpoint for epochs {} to {}",
+            covered_height, last_epoch.height
+        );
+        let proofs: Vec<_> = epochs.iter().map(|epoch| epoch.proof.clone()).collect();
+        let proof = self
+            .proving_backend
+            .prove_checkpoint(
+                previous.as_ref().map(|(_, checkpoint)| &checkpoint.proof),
+                &proofs,
+            )
+            .await?;
+        let output = self.proving_backend.verify_checkpoint(&proof).await?;
+
+        let mut checkpoint = Checkpoint {
+            epoch_height: output.epoch_count,
+            first_commitment: output.first_commitment,
+            last_commitment: output.last_commitment,
+            proof,
+            signature: None,
+        };
+        checkpoint.insert_signature(&self.cfg.signing_key);
+
+        let checkpoint_id = self.outbox.push(&OutboxItem::Checkpoint(checkpoint.clone()))?;
+        if let Some((entry, _)) = previous {
+            ids.push(entry.id);
+        }
+        self.outbox.remove(&ids)?;
+
+        self.submit_with_retries(&[checkpoint_id], || {
+            self.da.submit_checkpoint(checkpoint.clone())
+        })
+        .await?;
+
+        info!(
+            "submitted checkpoint up to epoch {}",
+            checkpoint.epoch_height
+        );
+        Ok(())
+    }
+
+    /// Calls `submit` until it succeeds, retrying up to [`Config::da_retry_count`] times with
+    /// exponential backoff. Failed attempts are recorded on the outbox entries `ids`, which stay
+    /// in the outbox if all attempts fail.
+    async fn submit_with_retries<F, Fut>(&self, ids: &[u64], mut submit: F) -> Result<u64>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<u64>>,
+    {
+        let mut delay = self.cfg.da_retry_interval;
+        let mut attempt = 1;
+        loop {
+            match submit().await {
+                Ok(height) => return Ok(height),
+                Err(e) => {
+                    self.outbox.record_failure(ids, &e)?;
+                    if attempt >= self.cfg.da_retry_count {
+                        return Err(
+                            e.context(format!("DA submission failed after {} attempts", attempt))
+                        );
+                    }
+                    warn!(
+                        "DA submission attempt {}/{} failed: {}. Retrying in {:?}",
+                        attempt, self.cfg.da_retry_count, e, delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    async fn prove_epoch(&self, job: EpochJob) -> Result<FinalizedEpoch> {
+        let epoch_height = job.height;
+
+        info!("generating proof for epoch at height {}", epoch_height);
+        let proof = self.proving_backend.prove(&job.batch).await?;
         info!("successfully generated proof for epoch {}", epoch_height);
 
-        client.verify(&proof, &self.verifying_key)?;
+        self.proving_backend.verify(&proof).await?;
         info!("verified proof for epoch {}", epoch_height);
 
         let mut epoch_json = FinalizedEpoch {
             height: epoch_height,
-            prev_commitment,
-            current_commitment: new_commitment,
+            prev_commitment: job.batch.prev_root,
+            current_commitment: job.batch.new_root,
+            transaction_count: job.transaction_count,
             proof,
             signature: None,
         };
@@ -419,44 +1038,112 @@ impl Prover {
         let mut height_rx = self.da.subscribe_to_heights();
 
         loop {
-            let height = height_rx.recv().await?;
+            let height = match height_rx.recv().await {
+                Ok(height) => height,
+                // retries can take longer than a block, missed heights don't matter here
+                Err(RecvError::Lagged(skipped)) => {
+                    debug!("post_batch_loop: skipped {} heights", skipped);
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
             trace!("received height {}", height);
 
-            // Get pending transactions
-            let pending_transactions = {
-                let mut ops = self.pending_transactions.write().await;
-                std::mem::take(&mut *ops)
-            };
+            if let Err(e) = self.submit_pending_transactions(height).await {
+                error!(
+                    "post_batch_loop: Failed to submit transactions, keeping them in the outbox: {}",
+                    e
+                );
+            }
+        }
+    }
 
-            let tx_count = pending_transactions.len();
+    /// Submits all transactions from the outbox in one batch. Once posted, they are kept in
+    /// the outbox as [`OutboxItem::PostedTransaction`] until they are executed.
+    async fn submit_pending_transactions(&self, height: u64) -> Result<()> {
+        let (entries, transactions): (Vec<_>, Vec<_>) =
+            self.outbox.transactions()?.into_iter().unzip();
+
+        if transactions.is_empty() {
+            debug!(
+                "post_batch_loop: No pending transactions to submit at height {}",
+                height
+            );
+            return Ok(());
+        }
 
-            // If there are pending transactions, submit them
-            if !pending_transactions.clone().is_empty() {
-                match self.da.submit_transactions(pending_transactions).await {
-                    Ok(submitted_height) => {
-                        info!(
-                            "post_batch_loop: submitted {} transactions at height {}",
-                            tx_count, submitted_height
-                        );
-                    }
-                    Err(e) => {
-                        error!("post_batch_loop: Failed to submit transactions: {}", e);
-                    }
-                }
-            } else {
-                debug!(
-                    "post_batch_loop: No pending transactions to submit at height {}",
-                    height
-                );
+        let ids: Vec<u64> = entries.iter().map(|entry| entry.id).collect();
+        let submitted_height = self
+            .submit_with_retries(&ids, || self.da.submit_transactions(transactions.clone()))
+            .await?;
+
+        for (id, transaction) in ids.iter().zip(&transactions) {
+            self.outbox.replace(*id, &OutboxItem::PostedTransaction(transaction.clone()))?;
+            self.mark_posted(&transaction.hash()?, submitted_height)?;
+        }
+
+        info!(
+            "post_batch_loop: submitted {} transactions at height {}",
+            transactions.len(),
+            submitted_height
+        );
+        Ok(())
+    }
+
+    /// Records that a transaction was posted, unless a later status was already recorded,
+    /// e.g. because the epoch including it was processed in the meantime.
+    fn mark_posted(&self, hash: &Digest, da_height: u64) -> Result<()> {
+        match self.db.get_transaction_status(hash)? {
+            None | Some(TransactionStatus::Queued) => {
+                // posting happens independently of the synced state, so it is not staged
+                self.db
+                    .inner()
+                    .set_transaction_status(hash, &TransactionStatus::Posted { da_height })
             }
+            Some(_) => Ok(()),
         }
     }
 
+    pub fn get_transaction_status(&self, hash: &Digest) -> Result<Option<TransactionStatus>> {
+        self.db.get_transaction_status(hash)
+    }
+
+    pub fn get_rejection_receipts(&self, epoch: u64) -> Result<Vec<RejectionReceipt>> {
+        self.db.get_rejection_receipts(&epoch)
+    }
+
     pub async fn get_commitment(&self) -> Result<Digest> {
         let tree = self.tree.read().await;
         tree.get_commitment().context("Failed to get commitment")
     }
 
+    /// Returns the commitment after `epoch` epochs, signed with [`Config::signing_key`], so
+    /// that light clients can cross-check a trusted checkpoint against this node. Returns
+    /// `None` if the epoch was not processed yet.
+    pub fn get_signed_commitment(&self, epoch: u64) -> Result<Option<SignedCommitment>> {
+        let latest = match self.db.get_epoch() {
+            Ok(latest) => latest,
+            Err(e)
+                if matches!(
+                    e.downcast_ref::<DatabaseError>(),
+                    Some(DatabaseError::NotFoundError(_))
+                ) =>
+            {
+                0
+            }
+            Err(e) => return Err(e.context("Failed to get the latest epoch")),
+        };
+        if epoch > latest {
+            return Ok(None);
+        }
+
+        let commitment = self
+            .db
+            .get_commitment(&epoch)
+            .with_context(|| format!("No commitment for epoch {}", epoch))?;
+        SignedCommitment::new(epoch, commitment, &self.cfg.signing_key).map(Some)
+    }
+
     pub async fn get_hashchain(&self, id: &String) -> Result<HashchainResponse> {
         let tree = self.tree.read().await;
         let hashed_id = Digest::hash(id);
@@ -472,33 +1159,35 @@ impl Prover {
     }
 
     /// Adds an transaction to be posted to the DA layer and applied in the next epoch.
+    /// Returns the hash the transaction's status can be queried with.
     pub async fn validate_and_queue_update(
         self: Arc<Self>,
         transaction: Transaction,
-    ) -> Result<()> {
+    ) -> Result<Digest> {
         if !self.cfg.batcher {
             bail!("Batcher is disabled, cannot queue transactions");
         }
 
-        // validate against existing hashchain if necessary, including signature checks
-        match transaction.entry.operation {
-            Operation::RegisterService { .. } | Operation::CreateAccount { .. } => {
-                Hashchain::empty().add_entry(transaction.entry.clone())?
-            }
-            Operation::AddKey { .. } | Operation::RevokeKey { .. } | Operation::AddData { .. } => {
-                let hc_response = self.get_hashchain(&transaction.id).await?;
+        let hash = transaction.hash()?;
 
-                let Found(mut hc, _) = hc_response else {
-                    bail!("Hashchain not found for id: {}", transaction.id)
-                };
+        // the lock is held until the transaction is queued, so concurrent updates of the
+        // same account are validated against each other
+        let mut mempool = self.mempool.lock().await;
+        if mempool.contains(&hash) {
+            debug!("transaction {} is already pending", hash);
+            return Ok(hash);
+        }
 
-                hc.add_entry(transaction.entry.clone())?;
-            }
+        // validate against the committed and pending hashchain, including signature checks
+        let committed = match self.get_hashchain(&transaction.id).await? {
+            Found(hashchain, _) => Some(hashchain),
+            NotFound(_) => None,
         };
+        mempool.insert(&transaction, committed)?;
 
-        let mut pending = self.pending_transactions.write().await;
-        pending.push(transaction);
-        Ok(())
+        self.outbox.push(&OutboxItem::Transaction(transaction))?;
+        self.db.inner().set_transaction_status(&hash, &TransactionStatus::Queued)?;
+        Ok(hash)
     }
 }
 
@@ -0,0 +1,222 @@
+use anyhow::{Context, Result};
+use prism_common::{transaction::Transaction, tree::Batch};
+use prism_da::{Checkpoint, FinalizedEpoch};
+use prism_storage::{Database, OutboxEntry};
+use serde::{Deserialize, Serialize};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+/// An item that is persisted before it is submitted to the DA layer.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum OutboxItem {
+    Transaction(Transaction),
+    /// An epoch that was executed, but not yet proven.
+    EpochJob(EpochJob),
+    /// An epoch that was proven, but not yet submitted.
+    Epoch(FinalizedEpoch),
+    /// An epoch that was submitted, but not yet aggregated into a checkpoint.
+    CheckpointEpoch(FinalizedEpoch),
+    /// The latest checkpoint, which the next checkpoint builds on.
+    Checkpoint(Checkpoint),
+    /// A transaction that was posted, but not yet executed. It is not submitted again, but
+    /// kept to restore the mempool after a restart.
+    PostedTransaction(Transaction),
+}
+
+/// The variant of an [`OutboxItem`]. bincode encodes the variant index as a `u32` in front of
+/// the item, so the kind of an entry can be read without decoding its payload. The variants
+/// must stay in the same order as those of [`OutboxItem`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum OutboxKind {
+    Transaction,
+    EpochJob,
+    Epoch,
+    CheckpointEpoch,
+    Checkpoint,
+    PostedTransaction,
+}
+
+impl OutboxKind {
+    fn of(entry: &OutboxEntry) -> Result<u32> {
+        bincode::deserialize(&entry.payload)
+            .with_context(|| format!("Failed to decode kind of outbox entry {}", entry.id))
+    }
+}
+
+impl OutboxItem {
+    pub fn kind(&self) -> OutboxKind {
+        match self {
+            OutboxItem::Transaction(_) => OutboxKind::Transaction,
+            OutboxItem::EpochJob(_) => OutboxKind::EpochJob,
+            OutboxItem::Epoch(_) => OutboxKind::Epoch,
+            OutboxItem::CheckpointEpoch(_) => OutboxKind::CheckpointEpoch,
+            OutboxItem::Checkpoint(_) => OutboxKind::Checkpoint,
+            OutboxItem::PostedTransaction(_) => OutboxKind::PostedTransaction,
+        }
+    }
+}
+
+/// The inputs needed to prove an epoch whose state transition was already applied.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EpochJob {
+    pub height: u64,
+    pub transaction_count: u64,
+    pub batch: Batch,
+}
+
+/// [`Outbox`] is a durable queue of items waiting to be submitted to the DA layer.
+/// Items are only removed once their submission succeeded, so they survive both
+/// failed submissions and restarts.
+pub struct Outbox {
+    db: Arc<Box<dyn Database>>,
+    next_id: AtomicU64,
+}
+
+impl Outbox {
+    pub fn new(db: Arc<Box<dyn Database>>) -> Result<Self> {
+        let next_id = db.get_outbox_entries()?.last().map_or(0, |entry| entry.id + 1);
+        Ok(Outbox {
+            db,
+            next_id: AtomicU64::new(next_id),
+        })
+    }
+
+    pub fn push(&self, item: &OutboxItem) -> Result<u64> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        self.db.put_outbox_entry(&OutboxEntry {
+            id,
+            payload: bincode::serialize(item)?,
+            attempts: 0,
+            last_error: None,
+        })?;
+        Ok(id)
+    }
+
+    /// Returns all entries together with their decoded items, ordered by id.
+    pub fn entries(&self) -> Result<Vec<(OutboxEntry, OutboxItem)>> {
+        self.db.get_outbox_entries()?.into_iter().map(decode).collect()
+    }
+
+    /// Returns the entries holding items of `kind`, ordered by id. The payloads of all other
+    /// entries are skipped without being decoded.
+    fn entries_of(&self, kind: OutboxKind) -> Result<Vec<(OutboxEntry, OutboxItem)>> {
+        let mut entries = Vec::new();
+        for entry in self.db.get_outbox_entries()? {
+            if OutboxKind::of(&entry)? == kind as u32 {
+                entries.push(decode(entry)?);
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Replaces the item of the entry with `id`, keeping its position in the outbox.
+    pub fn replace(&self, id: u64, item: &OutboxItem) -> Result<()> {
+        self.db.put_outbox_entry(&OutboxEntry {
+            id,
+            payload: bincode::serialize(item)?,
+            attempts: 0,
+            last_error: None,
+        })
+    }
+
+    pub fn get(&self, id: u64) -> Result<Option<(OutboxEntry, OutboxItem)>> {
+        self.db.get_outbox_entry(&id)?.map(decode).transpose()
+    }
+
+    pub fn transactions(&self) -> Result<Vec<(OutboxEntry, Transaction)>> {
+        Ok(self
+            .entries_of(OutboxKind::Transaction)?
+            .into_iter()
+            .filter_map(|(entry, item)| match item {
+                OutboxItem::Transaction(transaction) => Some((entry, transaction)),
+                _ => None,
+            })
+            .collect())
+    }
+
+    pub fn posted_transactions(&self) -> Result<Vec<(OutboxEntry, Transaction)>> {
+        Ok(self
+            .entries_of(OutboxKind::PostedTransaction)?
+            .into_iter()
+            .filter_map(|(entry, item)| match item {
+                OutboxItem::PostedTransaction(transaction) => Some((entry, transaction)),
+                _ => None,
+            })
+            .collect())
+    }
+
+    pub fn epochs(&self) -> Result<Vec<(OutboxEntry, FinalizedEpoch)>> {
+        Ok(self
+            .entries_of(OutboxKind::Epoch)?
+            .into_iter()
+            .filter_map(|(entry, item)| match item {
+                OutboxItem::Epoch(epoch) => Some((entry, epoch)),
+                _ => None,
+            })
+            .collect())
+    }
+
+    pub fn epoch_jobs(&self) -> Result<Vec<(OutboxEntry, EpochJob)>> {
+        Ok(self
+            .entries_of(OutboxKind::EpochJob)?
+            .into_iter()
+            .filter_map(|(entry, item)| match item {
+                OutboxItem::EpochJob(job) => Some((entry, job)),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Returns the submitted epochs waiting to be aggregated, ordered by height.
+    pub fn checkpoint_epochs(&self) -> Result<Vec<(OutboxEntry, FinalizedEpoch)>> {
+        let mut epochs: Vec<_> = self
+            .entries_of(OutboxKind::CheckpointEpoch)?
+            .into_iter()
+            .filter_map(|(entry, item)| match item {
+                OutboxItem::CheckpointEpoch(epoch) => Some((entry, epoch)),
+                _ => None,
+            })
+            .collect();
+        epochs.sort_by_key(|(_, epoch)| epoch.height);
+        Ok(epochs)
+    }
+
+    pub fn latest_checkpoint(&self) -> Result<Option<(OutboxEntry, Checkpoint)>> {
+        Ok(self
+            .entries_of(OutboxKind::Checkpoint)?
+            .into_iter()
+            .filter_map(|(entry, item)| match item {
+                OutboxItem::Checkpoint(checkpoint) => Some((entry, checkpoint)),
+                _ => None,
+            })
+            .max_by_key(|(_, checkpoint)| checkpoint.epoch_height))
+    }
+
+    /// Records a failed submission attempt on all entries in `ids`.
+    pub fn record_failure(&self, ids: &[u64], error: &anyhow::Error) -> Result<()> {
+        for id in ids {
+            if let Some(mut entry) = self.db.get_outbox_entry(id)? {
+                entry.attempts += 1;
+                entry.last_error = Some(error.to_string());
+                self.db.put_outbox_entry(&entry)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn remove(&self, ids: &[u64]) -> Result<()> {
+        for id in ids {
+            self.db.remove_outbox_entry(id)?;
+        }
+        Ok(())
+    }
+}
+
+fn decode(entry: OutboxEntry) -> Result<(OutboxEntry, OutboxItem)> {
+    let item = bincode::deserialize(&entry.payload)
+        .with_context(|| format!("Failed to decode outbox entry {}", entry.id))?;
+    Ok((entry, item))
+}
@@ -1,11 +1,28 @@
-use super::*;
+use super::{outbox::OutboxKind, *};
+use async_trait::async_trait;
+use jmt::{
+    storage::{LeafNode, Node, NodeBatch, NodeKey, TreeReader, TreeWriter},
+    OwnedValue, Version,
+};
 use prism_common::{keys::VerifyingKey, transaction_builder::TransactionBuilder, tree::Proof};
-use std::{self, sync::Arc, time::Duration};
+use std::{
+    self,
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 use tokio::spawn;
 
 use prism_common::test_utils::create_mock_signing_key;
-use prism_da::memory::InMemoryDataAvailabilityLayer;
-use prism_storage::{inmemory::InMemoryDatabase, Database};
+use prism_da::{
+    memory::{Block, InMemoryDataAvailabilityLayer},
+    DaHealth, SkippedEpochBlobs,
+};
+use prism_proving::{ProvingServer, Sp1Backend};
+use prism_storage::{inmemory::InMemoryDatabase, Database, EpochFault, OutboxEntry, WriteBatch};
 
 // Helper function to create a test prover instance
 async fn create_test_prover() -> Arc<Prover> {
@@ -16,6 +33,266 @@ async fn create_test_prover() -> Arc<Prover> {
     Arc::new(Prover::new(db.clone(), da_layer, &cfg).unwrap())
 }
 
+/// Wraps an [`InMemoryDatabase`] and rejects every atomic commit once [`crash`] was called,
+/// simulating a node that dies before its state reaches the disk.
+struct CrashingDatabase {
+    inner: Arc<InMemoryDatabase>,
+    crashed: AtomicBool,
+}
+
+impl CrashingDatabase {
+    fn new(inner: Arc<InMemoryDatabase>) -> Self {
+        Self {
+            inner,
+            crashed: AtomicBool::new(false),
+        }
+    }
+
+    fn crash(&self) {
+        self.crashed.store(true, Ordering::SeqCst);
+    }
+}
+
+impl TreeReader for CrashingDatabase {
+    fn get_node_option(&self, node_key: &NodeKey) -> Result<Option<Node>> {
+        self.inner.get_node_option(node_key)
+    }
+
+    fn get_rightmost_leaf(&self) -> Result<Option<(NodeKey, LeafNode)>> {
+        self.inner.get_rightmost_leaf()
+    }
+
+    fn get_value_option(
+        &self,
+        max_version: Version,
+        key_hash: KeyHash,
+    ) -> Result<Option<OwnedValue>> {
+        self.inner.get_value_option(max_version, key_hash)
+    }
+}
+
+impl TreeWriter for CrashingDatabase {
+    fn write_node_batch(&self, node_batch: &NodeBatch) -> Result<()> {
+        self.inner.write_node_batch(node_batch)
+    }
+}
+
+impl Database for CrashingDatabase {
+    fn get_commitment(&self, epoch: &u64) -> Result<Digest> {
+        self.inner.get_commitment(epoch)
+    }
+
+    fn set_commitment(&self, epoch: &u64, commitment: &Digest) -> Result<()> {
+        self.inner.set_commitment(epoch, commitment)
+    }
+
+    fn get_epoch(&self) -> Result<u64> {
+        self.inner.get_epoch()
+    }
+
+    fn set_epoch(&self, epoch: &u64) -> Result<()> {
+        self.inner.set_epoch(epoch)
+    }
+
+    fn get_last_synced_height(&self) -> Result<u64> {
+        self.inner.get_last_synced_height()
+    }
+
+    fn set_last_synced_height(&self, height: &u64) -> Result<()> {
+        self.inner.set_last_synced_height(height)
+    }
+
+    fn get_last_epoch_da_height(&self) -> Result<Option<u64>> {
+        self.inner.get_last_epoch_da_height()
+    }
+
+    fn set_last_epoch_da_height(&self, height: &u64) -> Result<()> {
+        self.inner.set_last_epoch_da_height(height)
+    }
+
+    fn get_buffered_transactions(&self) -> Result<BTreeMap<u64, Vec<Transaction>>> {
+        self.inner.get_buffered_transactions()
+    }
+
+    fn set_buffered_transactions(&self, height: &u64, transactions: &[Transaction]) -> Result<()> {
+        self.inner.set_buffered_transactions(height, transactions)
+    }
+
+    fn remove_buffered_transactions(&self, height: &u64) -> Result<()> {
+        self.inner.remove_buffered_transactions(height)
+    }
+
+    fn get_transaction_status(&self, hash: &Digest) -> Result<Option<TransactionStatus>> {
+        self.inner.get_transaction_status(hash)
+    }
+
+    fn set_transaction_status(&self, hash: &Digest, status: &TransactionStatus) -> Result<()> {
+        self.inner.set_transaction_status(hash, status)
+    }
+
+    fn get_rejection_receipts(&self, epoch: &u64) -> Result<Vec<RejectionReceipt>> {
+        self.inner.get_rejection_receipts(epoch)
+    }
+
+    fn set_rejection_receipts(&self, epoch: &u64, receipts: &[RejectionReceipt]) -> Result<()> {
+        self.inner.set_rejection_receipts(epoch, receipts)
+    }
+
+    fn get_outbox_entries(&self) -> Result<Vec<OutboxEntry>> {
+        self.inner.get_outbox_entries()
+    }
+
+    fn get_outbox_entry(&self, id: &u64) -> Result<Option<OutboxEntry>> {
+        self.inner.get_outbox_entry(id)
+    }
+
+    fn put_outbox_entry(&self, entry: &OutboxEntry) -> Result<()> {
+        self.inner.put_outbox_entry(entry)
+    }
+
+    fn remove_outbox_entry(&self, id: &u64) -> Result<()> {
+        self.inner.remove_outbox_entry(id)
+    }
+
+    fn get_epoch_faults(&self) -> Result<Vec<EpochFault>> {
+        self.inner.get_epoch_faults()
+    }
+
+    fn put_epoch_fault(&self, fault: &EpochFault) -> Result<()> {
+        self.inner.put_epoch_fault(fault)
+    }
+
+    fn get_equivocation_evidence(&self) -> Result<Vec<Vec<u8>>> {
+        self.inner.get_equivocation_evidence()
+    }
+
+    fn put_equivocation_evidence(&self, epoch_height: &u64, evidence: &[u8]) -> Result<()> {
+        self.inner.put_equivocation_evidence(epoch_height, evidence)
+    }
+
+    fn get_signed_epoch_header(&self, epoch_height: &u64) -> Result<Option<Vec<u8>>> {
+        self.inner.get_signed_epoch_header(epoch_height)
+    }
+
+    fn put_signed_epoch_header(&self, epoch_height: &u64, header: &[u8]) -> Result<()> {
+        self.inner.put_signed_epoch_header(epoch_height, header)
+    }
+
+    fn commit_batch(&self, batch: &WriteBatch) -> Result<()> {
+        if self.crashed.load(Ordering::SeqCst) {
+            bail!("simulated crash during commit");
+        }
+        self.inner.commit_batch(batch)
+    }
+
+    fn flush_database(&self) -> Result<()> {
+        self.inner.flush_database()
+    }
+}
+
+/// Wraps an [`InMemoryDataAvailabilityLayer`] and fails the next `failures` submissions.
+struct FailingDataAvailabilityLayer {
+    inner: Arc<InMemoryDataAvailabilityLayer>,
+    failures: AtomicU64,
+    /// Heights of all successfully submitted epochs, in submission order.
+    submitted_epochs: std::sync::Mutex<Vec<u64>>,
+}
+
+impl FailingDataAvailabilityLayer {
+    fn new(inner: Arc<InMemoryDataAvailabilityLayer>, failures: u64) -> Self {
+        Self {
+            inner,
+            failures: AtomicU64::new(failures),
+            submitted_epochs: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    fn check_failure(&self) -> Result<()> {
+        let remaining = self.failures.load(Ordering::SeqCst);
+        if remaining > 0 {
+            self.failures.store(remaining - 1, Ordering::SeqCst);
+            bail!("simulated DA submission failure");
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DataAvailabilityLayer for FailingDataAvailabilityLayer {
+    async fn get_latest_height(&self) -> Result<u64> {
+        self.inner.get_latest_height().await
+    }
+
+    async fn initialize_sync_target(&self) -> Result<u64> {
+        self.inner.initialize_sync_target().await
+    }
+
+    async fn get_finalized_epochs(
+        &self,
+        height: u64,
+        provers: &[VerificationKey],
+    ) -> Result<Vec<FinalizedEpoch>> {
+        self.inner.get_finalized_epochs(height, provers).await
+    }
+
+    fn skipped_epoch_blobs(&self) -> SkippedEpochBlobs {
+        self.inner.skipped_epoch_blobs()
+    }
+
+    async fn submit_finalized_epoch(&self, epoch: FinalizedEpoch) -> Result<u64> {
+        self.check_failure()?;
+        self.submitted_epochs.lock().unwrap().push(epoch.height);
+        self.inner.submit_finalized_epoch(epoch).await
+    }
+
+    async fn get_checkpoints(
+        &self,
+        height: u64,
+        provers: &[VerificationKey],
+    ) -> Result<Vec<Checkpoint>> {
+        self.inner.get_checkpoints(height, provers).await
+    }
+
+    async fn submit_checkpoint(&self, checkpoint: Checkpoint) -> Result<u64> {
+        self.check_failure()?;
+        self.inner.submit_checkpoint(checkpoint).await
+    }
+
+    async fn get_transactions(&self, height: u64) -> Result<Vec<Transaction>> {
+        self.inner.get_transactions(height).await
+    }
+
+    async fn submit_transactions(&self, transactions: Vec<Transaction>) -> Result<u64> {
+        self.check_failure()?;
+        self.inner.submit_transactions(transactions).await
+    }
+
+    async fn start(&self) -> Result<()> {
+        self.inner.start().await
+    }
+
+    fn subscribe_to_heights(&self) -> broadcast::Receiver<u64> {
+        self.inner.subscribe_to_heights()
+    }
+
+    fn health(&self) -> DaHealth {
+        self.inner.health()
+    }
+}
+
+fn retrying_config(retry_count: u64) -> Config {
+    Config {
+        da_retry_count: retry_count,
+        da_retry_interval: Duration::from_millis(10),
+        ..Config::default()
+    }
+}
+
+// Pairs transactions with the DA height they were read from.
+fn at_height(height: u64, transactions: Vec<Transaction>) -> Vec<(u64, Transaction)> {
+    transactions.into_iter().map(|transaction| (height, transaction)).collect()
+}
+
 fn create_mock_transactions(service_id: String) -> Vec<Transaction> {
     let mut transaction_builder = TransactionBuilder::new();
 
@@ -39,12 +316,48 @@ async fn test_validate_and_queue_update() {
     let transaction =
         transaction_builder.register_service_with_random_keys("test_service").commit();
 
-    prover.clone().validate_and_queue_update(transaction.clone()).await.unwrap();
+    let hash = prover.clone().validate_and_queue_update(transaction.clone()).await.unwrap();
 
-    prover.clone().validate_and_queue_update(transaction.clone()).await.unwrap();
+    // resubmitting a pending transaction is idempotent
+    let resubmitted_hash =
+        prover.clone().validate_and_queue_update(transaction.clone()).await.unwrap();
+    assert_eq!(hash, resubmitted_hash);
+
+    let pending_transactions = prover.outbox.transactions().unwrap();
+    assert_eq!(pending_transactions.len(), 1);
+}
+
+#[tokio::test]
+async fn test_validate_against_pending_transactions() {
+    let prover = create_test_prover().await;
+
+    let mut transaction_builder = TransactionBuilder::new();
+    let service_tx = transaction_builder.register_service_with_random_keys("test_service").commit();
+    let account_tx =
+        transaction_builder.create_account_with_random_key("test_account", "test_service").commit();
+    let conflicting_tx =
+        transaction_builder.add_random_key_verified_with_root("test_account").build();
+    let add_key_tx = transaction_builder.add_random_key_verified_with_root("test_account").commit();
 
-    let pending_transactions = prover.pending_transactions.read().await;
-    assert_eq!(pending_transactions.len(), 2);
+    // the account only exists in the pending state, yet updates to it are accepted
+    for tx in [&service_tx, &account_tx, &add_key_tx] {
+        prover.clone().validate_and_queue_update(tx.clone()).await.unwrap();
+    }
+
+    // a second update on top of the same previous entry conflicts with the pending one
+    assert!(prover.clone().validate_and_queue_update(conflicting_tx).await.is_err());
+    // creating the pending account again is rejected as well
+    assert!(prover.clone().validate_and_queue_update(account_tx).await.is_err());
+
+    assert_eq!(prover.outbox.transactions().unwrap().len(), 3);
+
+    // once applied, transactions leave the mempool and build on the committed state
+    let txs = prover.outbox.transactions().unwrap().into_iter().map(|(_, tx)| (0, tx)).collect();
+    prover.execute_block(0, txs).await.unwrap();
+    assert!(prover.mempool.lock().await.is_empty());
+
+    let next_tx = transaction_builder.add_random_key_verified_with_root("test_account").commit();
+    prover.clone().validate_and_queue_update(next_tx).await.unwrap();
 }
 
 #[tokio::test]
@@ -106,7 +419,7 @@ async fn test_execute_block_with_invalid_tx() {
         tx_builder.add_random_key("account_id", &new_key_1, 1).build(),
     ];
 
-    let proofs = prover.execute_block(transactions).await.unwrap();
+    let proofs = prover.execute_block(0, at_height(1, transactions)).await.unwrap();
     assert_eq!(proofs.len(), 4);
 }
 
@@ -116,7 +429,7 @@ async fn test_execute_block() {
 
     let transactions = create_mock_transactions("test_service".to_string());
 
-    let proofs = prover.execute_block(transactions).await.unwrap();
+    let proofs = prover.execute_block(0, at_height(1, transactions)).await.unwrap();
     assert_eq!(proofs.len(), 4);
 }
 
@@ -126,12 +439,44 @@ async fn test_finalize_new_epoch() {
     let transactions = create_mock_transactions("test_service".to_string());
 
     let prev_commitment = prover.get_commitment().await.unwrap();
-    prover.finalize_new_epoch(0, transactions).await.unwrap();
+    prover.finalize_new_epoch(0, at_height(1, transactions)).await.unwrap();
 
     let new_commitment = prover.get_commitment().await.unwrap();
     assert_ne!(prev_commitment, new_commitment);
 }
 
+#[tokio::test]
+async fn test_signed_commitment() {
+    let prover = create_test_prover().await;
+    let transactions = create_mock_transactions("test_service".to_string());
+    prover.finalize_new_epoch(0, at_height(1, transactions)).await.unwrap();
+
+    let signed = prover.get_signed_commitment(1).unwrap().unwrap();
+    assert_eq!(signed.epoch, 1);
+    assert_eq!(signed.commitment, prover.get_commitment().await.unwrap());
+    signed.verify_signature(prover.cfg.signing_key.verification_key()).unwrap();
+
+    // a different commitment doesn't match the signature
+    let forged = SignedCommitment {
+        commitment: Digest([1; 32]),
+        ..signed
+    };
+    assert!(forged.verify_signature(prover.cfg.signing_key.verification_key()).is_err());
+
+    // the signature only holds for the signed commitment context
+    let signature: [u8; 64] = hex::decode(&signed.signature).unwrap().try_into().unwrap();
+    let bare_message = bincode::serialize(&(signed.epoch, signed.commitment)).unwrap();
+    assert!(prover
+        .cfg
+        .signing_key
+        .verification_key()
+        .verify(&signature.into(), &bare_message)
+        .is_err());
+
+    // epochs that were not processed yet can't be attested
+    assert_eq!(prover.get_signed_commitment(2).unwrap(), None);
+}
+
 #[tokio::test]
 async fn test_restart_sync_from_scratch() {
     let (da_layer, _rx, mut brx) = InMemoryDataAvailabilityLayer::new(1);
@@ -151,7 +496,7 @@ async fn test_restart_sync_from_scratch() {
     for transaction in transactions {
         prover.clone().validate_and_queue_update(transaction).await.unwrap();
         while let Ok(new_block) = brx.recv().await {
-            if new_block.epoch.is_some() {
+            if !new_block.epoch_blobs.is_empty() {
                 break;
             }
         }
@@ -194,7 +539,7 @@ async fn test_load_persisted_state() {
     for transaction in transactions {
         prover.clone().validate_and_queue_update(transaction).await.unwrap();
         while let Ok(new_block) = brx.recv().await {
-            if new_block.epoch.is_some() {
+            if !new_block.epoch_blobs.is_empty() {
                 break;
             }
         }
@@ -212,3 +557,930 @@ async fn test_load_persisted_state() {
         prover2.get_commitment().await.unwrap()
     );
 }
+
+// Starts a prover on top of `db` with the genesis commitment already persisted.
+async fn create_committed_prover(
+    db: Arc<Box<dyn Database>>,
+    da_layer: Arc<InMemoryDataAvailabilityLayer>,
+) -> Arc<Prover> {
+    let prover = Arc::new(Prover::new(db, da_layer, &Config::default()).unwrap());
+    let initial_commitment = prover.get_commitment().await.unwrap();
+    prover.db.set_commitment(&0, &initial_commitment).unwrap();
+    prover.commit_height(1).await.unwrap();
+    prover
+}
+
+#[tokio::test]
+async fn test_crash_before_commit_resumes_from_previous_epoch() {
+    let (da_layer, _rx, _brx) = InMemoryDataAvailabilityLayer::new(1);
+    let da_layer = Arc::new(da_layer);
+    let inner = Arc::new(InMemoryDatabase::new());
+    let crashing_db = Arc::new(CrashingDatabase::new(inner.clone()));
+
+    let prover = create_committed_prover(
+        Arc::new(Box::new(crashing_db.clone()) as Box<dyn Database>),
+        da_layer.clone(),
+    )
+    .await;
+    let initial_commitment = prover.get_commitment().await.unwrap();
+
+    let mut tx_builder = TransactionBuilder::new();
+    let transaction = tx_builder.register_service_with_random_keys("service_id").commit();
+    prover.finalize_new_epoch(0, at_height(1, vec![transaction])).await.unwrap();
+    assert_eq!(prover.db.get_epoch().unwrap(), 1);
+
+    crashing_db.crash();
+    assert!(prover.commit_height(2).await.is_err());
+
+    // nothing of the failed commit reached the database
+    assert_eq!(inner.get_epoch().unwrap(), 0);
+    assert_eq!(inner.get_last_synced_height().unwrap(), 1);
+    assert!(inner.get_commitment(&1).is_err());
+
+    let restarted = Prover::new(
+        Arc::new(Box::new(inner.clone()) as Box<dyn Database>),
+        da_layer,
+        &Config::default(),
+    )
+    .unwrap();
+    assert_eq!(restarted.db.get_epoch().unwrap(), 0);
+    assert_eq!(restarted.db.get_commitment(&0).unwrap(), initial_commitment);
+    assert_eq!(
+        restarted.get_commitment().await.unwrap(),
+        initial_commitment
+    );
+}
+
+#[tokio::test]
+async fn test_commit_persists_epoch_commitment_and_height_together() {
+    let (da_layer, _rx, _brx) = InMemoryDataAvailabilityLayer::new(1);
+    let da_layer = Arc::new(da_layer);
+    let inner = Arc::new(InMemoryDatabase::new());
+    let crashing_db = Arc::new(CrashingDatabase::new(inner.clone()));
+
+    let prover = create_committed_prover(
+        Arc::new(Box::new(crashing_db.clone()) as Box<dyn Database>),
+        da_layer.clone(),
+    )
+    .await;
+
+    let mut tx_builder = TransactionBuilder::new();
+    let transaction = tx_builder.register_service_with_random_keys("service_id").commit();
+    prover.finalize_new_epoch(0, at_height(1, vec![transaction])).await.unwrap();
+    prover.commit_height(2).await.unwrap();
+    let new_commitment = prover.get_commitment().await.unwrap();
+
+    // a crash after the commit must not affect the persisted epoch
+    crashing_db.crash();
+
+    assert_eq!(inner.get_epoch().unwrap(), 1);
+    assert_eq!(inner.get_last_synced_height().unwrap(), 2);
+    assert_eq!(inner.get_commitment(&1).unwrap(), new_commitment);
+
+    let restarted = Prover::new(
+        Arc::new(Box::new(inner.clone()) as Box<dyn Database>),
+        da_layer,
+        &Config::default(),
+    )
+    .unwrap();
+    assert_eq!(restarted.db.get_epoch().unwrap(), 1);
+    assert_eq!(restarted.get_commitment().await.unwrap(), new_commitment);
+}
+
+#[tokio::test]
+async fn test_epochs_are_queued_for_proving_once_committed() {
+    let (da_layer, _rx, _brx) = InMemoryDataAvailabilityLayer::new(1);
+    let da_layer = Arc::new(da_layer);
+    let inner = Arc::new(InMemoryDatabase::new());
+    let crashing_db = Arc::new(CrashingDatabase::new(inner.clone()));
+
+    let prover = create_committed_prover(
+        Arc::new(Box::new(crashing_db.clone()) as Box<dyn Database>),
+        da_layer.clone(),
+    )
+    .await;
+    let mut queue = prover.proving_queue_rx.lock().await.take().unwrap();
+
+    let mut tx_builder = TransactionBuilder::new();
+    let transaction = tx_builder.register_service_with_random_keys("service_id").commit();
+    prover.finalize_new_epoch(0, at_height(1, vec![transaction])).await.unwrap();
+    assert!(queue.try_recv().is_err());
+
+    prover.commit_height(2).await.unwrap();
+    let jobs = prover.outbox.epoch_jobs().unwrap();
+    assert_eq!(queue.try_recv().unwrap(), jobs[0].0.id);
+
+    // the transition of a failed commit is discarded, so it must not be proven
+    let transaction = tx_builder.register_service_with_random_keys("other_service").commit();
+    prover.finalize_new_epoch(1, at_height(2, vec![transaction])).await.unwrap();
+    crashing_db.crash();
+    assert!(prover.commit_height(3).await.is_err());
+    assert!(queue.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_buffered_transactions_survive_restart() {
+    let (da_layer, _rx, mut brx) = InMemoryDataAvailabilityLayer::new(1);
+    let da_layer = Arc::new(da_layer);
+    da_layer.start().await.unwrap();
+    let inner = Arc::new(InMemoryDatabase::new());
+
+    let prover = create_committed_prover(
+        Arc::new(Box::new(inner.clone()) as Box<dyn Database>),
+        da_layer.clone(),
+    )
+    .await;
+
+    let transactions = create_mock_transactions("test_service".to_string());
+    da_layer.submit_transactions(transactions.clone()).await.unwrap();
+    let height = loop {
+        let block = brx.recv().await.unwrap();
+        if !block.transactions.is_empty() {
+            break block.height;
+        }
+    };
+
+    // the transactions are only buffered during historical sync, no epoch is created yet
+    prover.process_da_height(height, false).await.unwrap();
+    prover.commit_height(height + 1).await.unwrap();
+    drop(prover);
+
+    let restarted = Prover::new(
+        Arc::new(Box::new(inner.clone()) as Box<dyn Database>),
+        da_layer,
+        &Config::default(),
+    )
+    .unwrap();
+    let buffered = restarted.db.get_buffered_transactions().unwrap();
+    assert_eq!(buffered.get(&height), Some(&transactions));
+
+    // the restored transactions are included in the next epoch and removed from the buffer
+    restarted.process_da_height(height + 1, true).await.unwrap();
+    restarted.commit_height(height + 2).await.unwrap();
+
+    assert_eq!(inner.get_epoch().unwrap(), 1);
+    assert!(inner.get_buffered_transactions().unwrap().is_empty());
+    let Found(hashchain, _) =
+        restarted.get_hashchain(&"user1@example.com".to_string()).await.unwrap()
+    else {
+        panic!("account from restored transactions not found");
+    };
+    assert_eq!(hashchain.len(), 1);
+}
+
+#[tokio::test]
+async fn test_outbox_retries_failed_transaction_submissions() {
+    let (da_layer, _rx, _brx) = InMemoryDataAvailabilityLayer::new(1);
+    let da_layer = Arc::new(FailingDataAvailabilityLayer::new(Arc::new(da_layer), 2));
+    let db: Arc<Box<dyn Database>> = Arc::new(Box::new(InMemoryDatabase::new()));
+    let prover = Arc::new(Prover::new(db, da_layer.clone(), &retrying_config(3)).unwrap());
+
+    let mut tx_builder = TransactionBuilder::new();
+    let transaction = tx_builder.register_service_with_random_keys("service_id").commit();
+    prover.clone().validate_and_queue_update(transaction).await.unwrap();
+
+    prover.submit_pending_transactions(1).await.unwrap();
+
+    assert_eq!(da_layer.failures.load(Ordering::SeqCst), 0);
+    assert!(prover.outbox.transactions().unwrap().is_empty());
+    assert_eq!(prover.outbox.posted_transactions().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_outbox_keeps_transactions_across_restarts() {
+    let (da_layer, _rx, _brx) = InMemoryDataAvailabilityLayer::new(1);
+    let da_layer = Arc::new(FailingDataAvailabilityLayer::new(Arc::new(da_layer), 5));
+    let db: Arc<Box<dyn Database>> = Arc::new(Box::new(InMemoryDatabase::new()));
+    let prover = Arc::new(Prover::new(db.clone(), da_layer.clone(), &retrying_config(2)).unwrap());
+
+    let mut tx_builder = TransactionBuilder::new();
+    let transaction = tx_builder.register_service_with_random_keys("service_id").commit();
+    prover.clone().validate_and_queue_update(transaction.clone()).await.unwrap();
+
+    assert!(prover.submit_pending_transactions(1).await.is_err());
+    let pending = prover.outbox.transactions().unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].0.attempts, 2);
+    assert!(pending[0].0.last_error.is_some());
+    drop(prover);
+
+    da_layer.failures.store(0, Ordering::SeqCst);
+    let restarted = Prover::new(db, da_layer.clone(), &retrying_config(2)).unwrap();
+    let pending = restarted.outbox.transactions().unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].1, transaction);
+
+    restarted.submit_pending_transactions(2).await.unwrap();
+    assert!(restarted.outbox.transactions().unwrap().is_empty());
+    drop(restarted);
+
+    // a posted transaction is still pending after another restart, until it is executed
+    let restarted = Arc::new(Prover::new(db, da_layer, &retrying_config(2)).unwrap());
+    assert!(restarted.mempool.lock().await.contains(&transaction.hash().unwrap()));
+    restarted.clone().validate_and_queue_update(transaction.clone()).await.unwrap();
+    assert!(restarted.outbox.transactions().unwrap().is_empty());
+
+    restarted.finalize_new_epoch(0, at_height(2, vec![transaction])).await.unwrap();
+    restarted.commit_height(3).await.unwrap();
+    assert!(restarted.outbox.posted_transactions().unwrap().is_empty());
+    assert!(restarted.mempool.lock().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_outbox_only_decodes_entries_of_the_requested_kind() {
+    let prover = create_test_prover().await;
+    let mut tx_builder = TransactionBuilder::new();
+    let transaction = tx_builder.register_service_with_random_keys("service_id").commit();
+
+    for item in [
+        OutboxItem::Transaction(transaction.clone()),
+        OutboxItem::PostedTransaction(transaction.clone()),
+    ] {
+        let id = prover.outbox.push(&item).unwrap();
+        let (_, stored) = prover.outbox.get(id).unwrap().unwrap();
+        assert_eq!(stored.kind(), item.kind());
+    }
+
+    // a checkpoint entry whose payload ends after its kind cannot be decoded
+    let id = prover.outbox.entries().unwrap().len() as u64;
+    prover
+        .db
+        .put_outbox_entry(&OutboxEntry {
+            id,
+            payload: bincode::serialize(&(OutboxKind::Checkpoint as u32)).unwrap(),
+            attempts: 0,
+            last_error: None,
+        })
+        .unwrap();
+
+    assert_eq!(prover.outbox.transactions().unwrap()[0].1, transaction);
+    assert_eq!(prover.outbox.posted_transactions().unwrap().len(), 1);
+    assert!(prover.outbox.latest_checkpoint().is_err());
+    assert!(prover.outbox.get(id).is_err());
+    assert!(prover.outbox.get(id + 1).unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_failed_epoch_submission_is_reused_after_restart() {
+    let (da_layer, _rx, _brx) = InMemoryDataAvailabilityLayer::new(1);
+    let da_layer = Arc::new(FailingDataAvailabilityLayer::new(Arc::new(da_layer), 2));
+    let db: Arc<Box<dyn Database>> = Arc::new(Box::new(InMemoryDatabase::new()));
+    let transactions = create_mock_transactions("test_service".to_string());
+
+    let prover = Arc::new(Prover::new(db.clone(), da_layer.clone(), &retrying_config(2)).unwrap());
+    prover.finalize_new_epoch(0, at_height(1, transactions.clone())).await.unwrap();
+    assert!(prover.submit_pending_epochs().await.is_err());
+    // the proof is kept, only its submission failed
+    assert!(prover.outbox.epoch_jobs().unwrap().is_empty());
+    let epochs = prover.outbox.epochs().unwrap();
+    assert_eq!(epochs.len(), 1);
+    assert_eq!(epochs[0].0.attempts, 2);
+    drop(prover);
+
+    // the staged state of the failed attempt was never committed, so the restarted
+    // prover executes the same transactions again and submits the stored epoch
+    let restarted = Prover::new(db, da_layer.clone(), &retrying_config(2)).unwrap();
+    restarted.finalize_new_epoch(0, at_height(1, transactions)).await.unwrap();
+    restarted.submit_pending_epochs().await.unwrap();
+
+    assert!(restarted.outbox.entries().unwrap().is_empty());
+    assert_eq!(*da_layer.submitted_epochs.lock().unwrap(), vec![0]);
+    assert_eq!(restarted.db.get_epoch().unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_transaction_status_lifecycle() {
+    let (da_layer, _rx, _brx) = InMemoryDataAvailabilityLayer::new(1);
+    let da_layer = Arc::new(da_layer);
+    let db: Arc<Box<dyn Database>> = Arc::new(Box::new(InMemoryDatabase::new()));
+    let prover = Arc::new(Prover::new(db, da_layer, &Config::default()).unwrap());
+
+    let mut tx_builder = TransactionBuilder::new();
+    let valid = tx_builder.register_service_with_random_keys("service_id").commit();
+    // the service of this account only exists in the other builder's state
+    let mut other_builder = TransactionBuilder::new();
+    other_builder.register_service_with_random_keys("other_service").commit();
+    let invalid =
+        other_builder.create_account_with_random_key("account_id", "other_service").commit();
+
+    let hash = prover.clone().validate_and_queue_update(valid.clone()).await.unwrap();
+    assert_eq!(hash, valid.hash().unwrap());
+    assert_eq!(
+        prover.get_transaction_status(&hash).unwrap(),
+        Some(TransactionStatus::Queued)
+    );
+
+    prover.submit_pending_transactions(1).await.unwrap();
+    assert!(matches!(
+        prover.get_transaction_status(&hash).unwrap(),
+        Some(TransactionStatus::Posted { .. })
+    ));
+
+    prover.finalize_new_epoch(0, at_height(1, vec![valid, invalid.clone()])).await.unwrap();
+    assert_eq!(
+        prover.get_transaction_status(&hash).unwrap(),
+        Some(TransactionStatus::Included { epoch: 0 })
+    );
+    assert!(matches!(
+        prover.get_transaction_status(&invalid.hash().unwrap()).unwrap(),
+        Some(TransactionStatus::Rejected(_))
+    ));
+
+    // a late posting confirmation must not downgrade the status
+    prover.mark_posted(&hash, 5).unwrap();
+    assert_eq!(
+        prover.get_transaction_status(&hash).unwrap(),
+        Some(TransactionStatus::Included { epoch: 0 })
+    );
+}
+
+#[tokio::test]
+async fn test_reposted_batch_keeps_included_status() {
+    let prover = create_test_prover().await;
+    let mut tx_builder = TransactionBuilder::new();
+    let transaction = tx_builder.register_service_with_random_keys("service_id").commit();
+
+    prover.finalize_new_epoch(0, at_height(1, vec![transaction.clone()])).await.unwrap();
+    let commitment = prover.get_commitment().await.unwrap();
+
+    // the same batch posted again after a crash, before it was removed from the outbox
+    prover.finalize_new_epoch(1, at_height(2, vec![transaction.clone()])).await.unwrap();
+    assert_eq!(
+        prover.get_transaction_status(&transaction.hash().unwrap()).unwrap(),
+        Some(TransactionStatus::Included { epoch: 0 })
+    );
+    assert!(prover.get_rejection_receipts(1).unwrap().is_empty());
+    assert_eq!(prover.get_commitment().await.unwrap(), commitment);
+}
+
+#[tokio::test]
+async fn test_rejection_receipts_are_deterministic() {
+    let mut tx_builder = TransactionBuilder::new();
+    let transactions = vec![
+        tx_builder.register_service_with_random_keys("service_id").commit(),
+        tx_builder.create_account_with_random_key("account_id", "service_id").commit(),
+    ];
+    let mut other_builder = TransactionBuilder::new();
+    other_builder.register_service_with_random_keys("other_service").commit();
+    let missing_service =
+        other_builder.create_account_with_random_key("other_account", "other_service").commit();
+    let duplicate_account =
+        tx_builder.create_account_with_random_key("account_id", "service_id").build();
+
+    let mut block = at_height(3, transactions);
+    block.push((4, missing_service.clone()));
+    block.push((5, duplicate_account.clone()));
+
+    // a prover and a full node executing the same epoch produce the same receipts
+    let prover = create_test_prover().await;
+    let full_node = create_test_prover().await;
+    prover.execute_block(2, block.clone()).await.unwrap();
+    full_node.execute_block(2, block).await.unwrap();
+
+    let receipts = prover.get_rejection_receipts(2).unwrap();
+    assert_eq!(receipts, full_node.get_rejection_receipts(2).unwrap());
+    assert_eq!(receipts.len(), 2);
+
+    assert_eq!(
+        receipts[0].transaction_hash,
+        missing_service.hash().unwrap()
+    );
+    assert_eq!(receipts[0].da_height, 4);
+    assert_eq!(receipts[0].epoch, 2);
+    assert_eq!(receipts[0].kind, RejectionKind::NotFound);
+    assert_eq!(
+        receipts[1].transaction_hash,
+        duplicate_account.hash().unwrap()
+    );
+    assert_eq!(receipts[1].kind, RejectionKind::AlreadyExists);
+    assert_eq!(
+        receipts[1].message,
+        "Account for ID account_id already exists"
+    );
+
+    assert_eq!(
+        prover.get_transaction_status(&duplicate_account.hash().unwrap()).unwrap(),
+        Some(TransactionStatus::Rejected(receipts[1].clone()))
+    );
+    assert!(prover.get_rejection_receipts(1).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_epoch_policy_carries_over_transactions() {
+    let (da_layer, _rx, _brx) = InMemoryDataAvailabilityLayer::new(1);
+    let da_layer = Arc::new(da_layer);
+    let db: Arc<Box<dyn Database>> = Arc::new(Box::new(InMemoryDatabase::new()));
+    let cfg = Config {
+        epoch_policy: EpochPolicy {
+            max_transactions: 3,
+            ..EpochPolicy::default()
+        },
+        ..Config::default()
+    };
+    let prover = Arc::new(Prover::new(db, da_layer, &cfg).unwrap());
+
+    let transactions = create_mock_transactions("test_service".to_string());
+    prover.db.set_buffered_transactions(&2, &transactions[..2]).unwrap();
+    prover.db.set_buffered_transactions(&3, &transactions[2..]).unwrap();
+
+    prover.process_da_height(4, true).await.unwrap();
+    prover.commit_height(5).await.unwrap();
+
+    // the last transaction did not fit into the epoch and stays buffered
+    assert_eq!(prover.db.get_epoch().unwrap(), 1);
+    let buffered = prover.db.get_buffered_transactions().unwrap();
+    assert_eq!(buffered.len(), 1);
+    assert_eq!(buffered.get(&3), Some(&transactions[3..].to_vec()));
+
+    prover.process_da_height(5, true).await.unwrap();
+    prover.commit_height(6).await.unwrap();
+    assert_eq!(prover.db.get_epoch().unwrap(), 2);
+    assert!(prover.db.get_buffered_transactions().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_epoch_policy_heartbeat() {
+    let (da_layer, _rx, _brx) = InMemoryDataAvailabilityLayer::new(1);
+    let da_layer = Arc::new(da_layer);
+    let db: Arc<Box<dyn Database>> = Arc::new(Box::new(InMemoryDatabase::new()));
+    let cfg = Config {
+        epoch_policy: EpochPolicy {
+            heartbeat_interval: Some(3),
+            ..EpochPolicy::default()
+        },
+        ..Config::default()
+    };
+    let prover = Arc::new(Prover::new(db.clone(), da_layer.clone(), &cfg).unwrap());
+    let initial_commitment = prover.get_commitment().await.unwrap();
+
+    for height in 2..=6 {
+        prover.process_da_height(height, true).await.unwrap();
+        prover.commit_height(height + 1).await.unwrap();
+    }
+
+    // an empty epoch is finalized every 3 heights, starting from the start height
+    assert_eq!(prover.db.get_epoch().unwrap(), 1);
+    assert_eq!(db.get_last_epoch_da_height().unwrap(), Some(4));
+
+    // the interval continues from the last epoch after a restart
+    drop(prover);
+    let prover = Arc::new(Prover::new(db, da_layer, &cfg).unwrap());
+    prover.process_da_height(7, true).await.unwrap();
+    assert_eq!(prover.db.get_epoch().unwrap(), 2);
+    assert_eq!(prover.db.get_commitment(&2).unwrap(), initial_commitment);
+}
+
+#[tokio::test]
+async fn test_epochs_are_executed_before_they_are_proven() {
+    let (da_layer, _rx, _brx) = InMemoryDataAvailabilityLayer::new(1);
+    let da_layer = Arc::new(FailingDataAvailabilityLayer::new(Arc::new(da_layer), 0));
+    let db: Arc<Box<dyn Database>> = Arc::new(Box::new(InMemoryDatabase::new()));
+    let prover = Arc::new(Prover::new(db, da_layer.clone(), &Config::default()).unwrap());
+
+    let mut tx_builder = TransactionBuilder::new();
+    let first = tx_builder.register_service_with_random_keys("first_service").commit();
+    let second = tx_builder.register_service_with_random_keys("second_service").commit();
+
+    // both epochs are applied to the state without waiting for their proofs
+    prover.finalize_new_epoch(0, at_height(1, vec![first])).await.unwrap();
+    prover.finalize_new_epoch(1, at_height(2, vec![second])).await.unwrap();
+    assert_eq!(prover.db.get_epoch().unwrap(), 2);
+    assert_eq!(prover.outbox.epoch_jobs().unwrap().len(), 2);
+    assert!(da_layer.submitted_epochs.lock().unwrap().is_empty());
+
+    let worker = spawn(prover.clone().proving_loop());
+    while da_layer.submitted_epochs.lock().unwrap().len() < 2 {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    worker.abort();
+
+    assert_eq!(*da_layer.submitted_epochs.lock().unwrap(), vec![0, 1]);
+    assert!(prover.outbox.entries().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_prove_with_remote_backend() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    spawn(ProvingServer::new(Arc::new(Sp1Backend::mock())).serve(listener));
+
+    let (da_layer, _rx, _brx) = InMemoryDataAvailabilityLayer::new(1);
+    let da_layer = Arc::new(FailingDataAvailabilityLayer::new(Arc::new(da_layer), 0));
+    let db: Arc<Box<dyn Database>> = Arc::new(Box::new(InMemoryDatabase::new()));
+    let cfg = Config {
+        proving_backend: ProvingBackendConfig::Remote { address },
+        ..Config::default()
+    };
+    let prover = Arc::new(Prover::new(db, da_layer.clone(), &cfg).unwrap());
+
+    let transactions = create_mock_transactions("test_service".to_string());
+    prover.finalize_new_epoch(0, at_height(1, transactions)).await.unwrap();
+    prover.submit_pending_epochs().await.unwrap();
+
+    assert_eq!(*da_layer.submitted_epochs.lock().unwrap(), vec![0]);
+}
+
+// Posts `epoch` and processes the DA height it lands at.
+async fn submit_and_process_epoch(
+    prover: &Prover,
+    da_layer: &InMemoryDataAvailabilityLayer,
+    blocks: &mut broadcast::Receiver<Block>,
+    epoch: &FinalizedEpoch,
+) -> (u64, Result<()>) {
+    da_layer.submit_finalized_epoch(epoch.clone()).await.unwrap();
+    let height = loop {
+        let block = blocks.recv().await.unwrap();
+        if !block.epoch_blobs.is_empty() {
+            break block.height;
+        }
+    };
+    (height, prover.process_da_height(height, false).await)
+}
+
+#[tokio::test]
+async fn test_faulty_epoch_halts_sync_and_is_recorded() {
+    let (da_layer, _rx, mut brx) = InMemoryDataAvailabilityLayer::new(1);
+    let da_layer = Arc::new(da_layer);
+    da_layer.start().await.unwrap();
+    let db: Arc<Box<dyn Database>> = Arc::new(Box::new(InMemoryDatabase::new()));
+    let prover = create_committed_prover(db.clone(), da_layer.clone()).await;
+    let initial_commitment = prover.get_commitment().await.unwrap();
+
+    let proof = Sp1Backend::mock()
+        .prove(&Batch {
+            prev_root: initial_commitment,
+            new_root: initial_commitment,
+            proofs: Vec::new(),
+        })
+        .await
+        .unwrap();
+    let mut epoch = FinalizedEpoch {
+        height: 0,
+        prev_commitment: initial_commitment,
+        current_commitment: Digest::hash("not the executed state"),
+        transaction_count: 0,
+        proof,
+        signature: None,
+    };
+
+    // anyone can post to the DA layer, so unsigned epochs are ignored
+    let (_, result) = submit_and_process_epoch(&prover, &da_layer, &mut brx, &epoch).await;
+    assert!(result.is_ok());
+    assert_eq!(da_layer.skipped_epoch_blobs().invalid_signature, 1);
+    assert!(!prover.is_halted());
+    assert!(prover.get_epoch_faults().unwrap().is_empty());
+
+    epoch.insert_signature(&prover.cfg.signing_key);
+    let (height, result) = submit_and_process_epoch(&prover, &da_layer, &mut brx, &epoch).await;
+    assert_eq!(
+        result.unwrap_err().downcast_ref::<EpochVerificationError>(),
+        Some(&EpochVerificationError::NewCommitmentMismatch(0))
+    );
+    assert!(prover.is_halted());
+
+    let faults = prover.get_epoch_faults().unwrap();
+    assert_eq!(faults.len(), 1);
+    assert_eq!(faults[0].da_height, height);
+    assert_eq!(faults[0].epoch_height, 0);
+    assert_eq!(faults[0].blob, bincode::serialize(&epoch).unwrap());
+
+    // the last verified state is still served
+    assert_eq!(prover.db.get_epoch().unwrap(), 0);
+    assert_eq!(prover.get_commitment().await.unwrap(), initial_commitment);
+}
+
+#[tokio::test]
+async fn test_conflicting_epochs_produce_equivocation_evidence() {
+    let (da_layer, _rx, mut brx) = InMemoryDataAvailabilityLayer::new(1);
+    let da_layer = Arc::new(da_layer);
+    da_layer.start().await.unwrap();
+    let db: Arc<Box<dyn Database>> = Arc::new(Box::new(InMemoryDatabase::new()));
+    let prover = create_committed_prover(db.clone(), da_layer.clone()).await;
+    let initial_commitment = prover.get_commitment().await.unwrap();
+
+    let proof = Sp1Backend::mock()
+        .prove(&Batch {
+            prev_root: initial_commitment,
+            new_root: initial_commitment,
+            proofs: Vec::new(),
+        })
+        .await
+        .unwrap();
+    let signed_epoch = |current_commitment| {
+        let mut epoch = FinalizedEpoch {
+            height: 0,
+            prev_commitment: initial_commitment,
+            current_commitment,
+            transaction_count: 0,
+            proof: proof.clone(),
+            signature: None,
+        };
+        epoch.insert_signature(&prover.cfg.signing_key);
+        epoch
+    };
+
+    da_layer.submit_finalized_epoch(signed_epoch(initial_commitment)).await.unwrap();
+    da_layer.submit_finalized_epoch(signed_epoch(Digest::hash("fork"))).await.unwrap();
+    let height = loop {
+        let block = brx.recv().await.unwrap();
+        if !block.epoch_blobs.is_empty() {
+            break block.height;
+        }
+    };
+
+    let result = prover.process_da_height(height, false).await;
+    assert_eq!(
+        result.unwrap_err().downcast_ref::<EpochVerificationError>(),
+        Some(&EpochVerificationError::Equivocation(0))
+    );
+    assert!(prover.is_halted());
+
+    let evidence = prover.get_equivocation_evidence().unwrap();
+    assert_eq!(evidence.len(), 1);
+    evidence[0].verify(prover.cfg.verifying_key).unwrap();
+
+    // the evidence convinces other nodes following the same prover
+    let other_cfg = Config {
+        verifying_key: prover.cfg.verifying_key,
+        ..Config::default()
+    };
+    let other = Prover::new(
+        Arc::new(Box::new(InMemoryDatabase::new()) as Box<dyn Database>),
+        da_layer.clone(),
+        &other_cfg,
+    )
+    .unwrap();
+
+    let mut forged = evidence[0].clone();
+    forged.second.header.current_commitment = Digest::hash("forged");
+    assert!(other.submit_equivocation_evidence(&forged).is_err());
+    assert!(!other.is_halted());
+
+    other.submit_equivocation_evidence(&evidence[0]).unwrap();
+    assert!(other.is_halted());
+    assert_eq!(other.get_equivocation_evidence().unwrap(), evidence);
+}
+
+#[tokio::test]
+async fn test_conflicting_epochs_at_different_da_heights_produce_equivocation_evidence() {
+    let (da_layer, _rx, mut brx) = InMemoryDataAvailabilityLayer::new(1);
+    let da_layer = Arc::new(da_layer);
+    da_layer.start().await.unwrap();
+    let db: Arc<Box<dyn Database>> = Arc::new(Box::new(InMemoryDatabase::new()));
+    let prover = create_committed_prover(db.clone(), da_layer.clone()).await;
+    let initial_commitment = prover.get_commitment().await.unwrap();
+
+    let proof = Sp1Backend::mock()
+        .prove(&Batch {
+            prev_root: initial_commitment,
+            new_root: initial_commitment,
+            proofs: Vec::new(),
+        })
+        .await
+        .unwrap();
+    let signed_epoch = |current_commitment| {
+        let mut epoch = FinalizedEpoch {
+            height: 0,
+            prev_commitment: initial_commitment,
+            current_commitment,
+            transaction_count: 0,
+            proof: proof.clone(),
+            signature: None,
+        };
+        epoch.insert_signature(&prover.cfg.signing_key);
+        epoch
+    };
+
+    let valid = signed_epoch(initial_commitment);
+    let (first_height, result) =
+        submit_and_process_epoch(&prover, &da_layer, &mut brx, &valid).await;
+    result.unwrap();
+    assert_eq!(prover.db.get_epoch().unwrap(), 1);
+
+    // the fork is posted later, after the epoch was already applied
+    let fork = signed_epoch(Digest::hash("fork"));
+    let (second_height, result) =
+        submit_and_process_epoch(&prover, &da_layer, &mut brx, &fork).await;
+    assert!(second_height > first_height);
+    assert_eq!(
+        result.unwrap_err().downcast_ref::<EpochVerificationError>(),
+        Some(&EpochVerificationError::Equivocation(0))
+    );
+    assert!(prover.is_halted());
+
+    let evidence = prover.get_equivocation_evidence().unwrap();
+    assert_eq!(evidence.len(), 1);
+    evidence[0].verify(prover.cfg.verifying_key).unwrap();
+    assert_eq!(evidence[0].first, valid.signed_header().unwrap().unwrap());
+    assert_eq!(evidence[0].second, fork.signed_header().unwrap().unwrap());
+}
+
+#[tokio::test]
+async fn test_reproven_epoch_is_not_equivocation() {
+    let signing_key = create_signing_key();
+    let vk = signing_key.verification_key();
+    let signed_epoch = |proof| {
+        let mut epoch = FinalizedEpoch {
+            height: 0,
+            prev_commitment: Digest::zero(),
+            current_commitment: Digest::zero(),
+            transaction_count: 0,
+            proof,
+            signature: None,
+        };
+        epoch.insert_signature(&signing_key);
+        epoch
+    };
+    let prove = |root| async move {
+        Sp1Backend::mock()
+            .prove(&Batch {
+                prev_root: root,
+                new_root: root,
+                proofs: Vec::new(),
+            })
+            .await
+            .unwrap()
+    };
+
+    // the same state transition with another proof, e.g. after a restart of the prover
+    let first = signed_epoch(prove(Digest::zero()).await);
+    let second = signed_epoch(prove(Digest::hash("other proof")).await);
+    assert_ne!(
+        first.header().unwrap().proof_hash,
+        second.header().unwrap().proof_hash
+    );
+    assert!(EquivocationEvidence::find(&[first.clone(), second.clone()], vk).unwrap().is_none());
+
+    let evidence = EquivocationEvidence {
+        first: first.signed_header().unwrap().unwrap(),
+        second: second.signed_header().unwrap().unwrap(),
+    };
+    assert!(evidence.verify(vk).is_err());
+}
+
+#[tokio::test]
+async fn test_spam_epochs_do_not_hide_signed_epoch() {
+    let (da_layer, _rx, mut brx) = InMemoryDataAvailabilityLayer::new(1);
+    da_layer.start().await.unwrap();
+    let signing_key = create_signing_key();
+
+    let proof = Sp1Backend::mock()
+        .prove(&Batch {
+            prev_root: Digest::zero(),
+            new_root: Digest::zero(),
+            proofs: Vec::new(),
+        })
+        .await
+        .unwrap();
+    let epoch = |current_commitment| FinalizedEpoch {
+        height: 0,
+        prev_commitment: Digest::zero(),
+        current_commitment,
+        transaction_count: 0,
+        proof: proof.clone(),
+        signature: None,
+    };
+
+    let mut signed = epoch(Digest::zero());
+    signed.insert_signature(&signing_key);
+    let mut foreign = epoch(Digest::hash("foreign"));
+    foreign.insert_signature(&create_signing_key());
+
+    da_layer.submit_finalized_epoch(epoch(Digest::hash("spam"))).await.unwrap();
+    da_layer.submit_finalized_epoch(foreign).await.unwrap();
+    da_layer.submit_finalized_epoch(signed.clone()).await.unwrap();
+    let height = loop {
+        let block = brx.recv().await.unwrap();
+        if !block.epoch_blobs.is_empty() {
+            break block.height;
+        }
+    };
+
+    let epochs =
+        da_layer.get_finalized_epochs(height, &[signing_key.verification_key()]).await.unwrap();
+    assert_eq!(epochs.len(), 1);
+    assert_eq!(epochs[0].current_commitment, signed.current_commitment);
+    assert_eq!(
+        da_layer.skipped_epoch_blobs(),
+        SkippedEpochBlobs {
+            undecodable: 0,
+            invalid_signature: 2,
+        }
+    );
+
+    // without a configured prover every candidate is returned
+    assert_eq!(
+        da_layer.get_finalized_epochs(height, &[]).await.unwrap().len(),
+        3
+    );
+}
+
+#[tokio::test]
+async fn test_spam_checkpoints_do_not_hide_signed_checkpoint() {
+    let (da_layer, _rx, mut brx) = InMemoryDataAvailabilityLayer::new(1);
+    da_layer.start().await.unwrap();
+    let signing_key = create_signing_key();
+
+    let proof = Sp1Backend::mock()
+        .prove(&Batch {
+            prev_root: Digest::zero(),
+            new_root: Digest::zero(),
+            proofs: Vec::new(),
+        })
+        .await
+        .unwrap();
+    let checkpoint = |last_commitment| Checkpoint {
+        epoch_height: 2,
+        first_commitment: Digest::zero(),
+        last_commitment,
+        proof: proof.clone(),
+        signature: None,
+    };
+
+    let mut signed = checkpoint(Digest::zero());
+    signed.insert_signature(&signing_key);
+    let mut foreign = checkpoint(Digest::hash("foreign"));
+    foreign.insert_signature(&create_signing_key());
+
+    da_layer.submit_checkpoint(checkpoint(Digest::hash("spam"))).await.unwrap();
+    da_layer.submit_checkpoint(foreign).await.unwrap();
+    da_layer.submit_checkpoint(signed.clone()).await.unwrap();
+    let height = loop {
+        let block = brx.recv().await.unwrap();
+        if !block.checkpoints.is_empty() {
+            break block.height;
+        }
+    };
+
+    let checkpoints =
+        da_layer.get_checkpoints(height, &[signing_key.verification_key()]).await.unwrap();
+    assert_eq!(checkpoints.len(), 1);
+    assert_eq!(checkpoints[0].last_commitment, signed.last_commitment);
+    assert_eq!(da_layer.skipped_epoch_blobs().invalid_signature, 2);
+}
+
+#[tokio::test]
+async fn test_oversized_epochs_are_chunked() {
+    let signing_key = create_signing_key();
+    let proof = Sp1Backend::mock()
+        .prove(&Batch {
+            prev_root: Digest::zero(),
+            new_root: Digest::zero(),
+            proofs: Vec::new(),
+        })
+        .await
+        .unwrap();
+    let mut epoch = FinalizedEpoch {
+        height: 0,
+        prev_commitment: Digest::zero(),
+        current_commitment: Digest::zero(),
+        transaction_count: 0,
+        proof,
+        signature: None,
+    };
+    epoch.insert_signature(&signing_key);
+
+    // two chunks and the header referencing them, in blocks that only hold one of them
+    let size = bincode::serialized_size(&epoch).unwrap() as usize;
+    let max_blob_size = size / 2 + prism_da::chunks::CHUNK_MAGIC.len();
+    let (da_layer, _rx, mut brx) = InMemoryDataAvailabilityLayer::new(1);
+    let da_layer =
+        da_layer.with_max_blob_size(max_blob_size).with_max_submission_size(max_blob_size);
+    da_layer.start().await.unwrap();
+
+    let header_height = da_layer.submit_finalized_epoch(epoch.clone()).await.unwrap();
+    let mut blocks = Vec::new();
+    loop {
+        let block = brx.recv().await.unwrap();
+        let height = block.height;
+        if !block.epoch_blobs.is_empty() {
+            blocks.push(block);
+        }
+        if height == header_height {
+            break;
+        }
+    }
+    assert_eq!(blocks.len(), 3);
+    assert!(blocks.iter().all(|block| block.epoch_blobs.len() == 1));
+    assert!(blocks.iter().all(|block| block.epoch_blobs[0].len() <= max_blob_size));
+
+    let vk = signing_key.verification_key();
+    let epochs = da_layer.get_finalized_epochs(header_height, &[vk]).await.unwrap();
+    assert_eq!(epochs.len(), 1);
+    assert_eq!(epochs[0].header().unwrap(), epoch.header().unwrap());
+    // the chunks themselves are not epochs
+    for block in &blocks[..2] {
+        assert!(da_layer.get_finalized_epochs(block.height, &[vk]).await.unwrap().is_empty());
+    }
+    assert_eq!(da_layer.skipped_epoch_blobs(), SkippedEpochBlobs::default());
+
+    // blobs too small to even hold the header are rejected on submission
+    let (da_layer, _rx, _brx) = InMemoryDataAvailabilityLayer::new(1);
+    let da_layer = da_layer.with_max_blob_size(64);
+    assert!(da_layer.submit_finalized_epoch(epoch.clone()).await.is_err());
+
+    // so are blobs that don't fit into a submission
+    let (da_layer, _rx, _brx) = InMemoryDataAvailabilityLayer::new(1);
+    let da_layer = da_layer.with_max_blob_size(max_blob_size).with_max_submission_size(64);
+    assert!(da_layer.submit_finalized_epoch(epoch).await.is_err());
+}
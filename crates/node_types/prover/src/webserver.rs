@@ -1,7 +1,7 @@
 use crate::Prover;
 use anyhow::{bail, Context, Result};
 use axum::{
-    extract::State,
+    extract::{Path, State},
     http::StatusCode,
     response::IntoResponse,
     routing::{get, post},
@@ -13,11 +13,14 @@ use indexed_merkle_tree::{
 };
 use jmt::proof::SparseMerkleProof;
 use prism_common::{
+    digest::Digest,
     hashchain::{Hashchain, HashchainEntry},
     hasher::Hasher,
-    transaction::Transaction,
+    transaction::{RejectionReceipt, Transaction, TransactionStatus},
     tree::HashchainResponse,
 };
+use prism_da::{DaHealth, EquivocationEvidence, SignedCommitment};
+use prism_storage::EpochFault;
 use serde::{Deserialize, Serialize};
 use std::{self, sync::Arc};
 use tower_http::cors::CorsLayer;
@@ -60,6 +63,64 @@ pub struct TransactionRequest {
     pub entry: HashchainEntry,
 }
 
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct TransactionResponse {
+    /// Hex encoded transaction hash, used to query the transaction's status.
+    pub hash: String,
+    pub status: TransactionStatus,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct RejectionReceiptsResponse {
+    pub epoch: u64,
+    pub receipts: Vec<RejectionReceipt>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct EpochFaultResponse {
+    pub da_height: u64,
+    pub epoch_height: u64,
+    pub reason: String,
+    /// Hex encoded epoch, as it was posted to the DA layer.
+    pub blob: String,
+}
+
+impl From<EpochFault> for EpochFaultResponse {
+    fn from(fault: EpochFault) -> Self {
+        EpochFaultResponse {
+            da_height: fault.da_height,
+            epoch_height: fault.epoch_height,
+            reason: fault.reason,
+            blob: hex::encode(fault.blob),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct FaultsResponse {
+    /// Whether syncing stopped at a faulty epoch.
+    pub halted: bool,
+    pub faults: Vec<EpochFaultResponse>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct DaHealthResponse {
+    /// Whether new heights are currently received from the DA layer.
+    pub connected: bool,
+    pub reconnects: u64,
+    pub last_error: Option<String>,
+}
+
+impl From<DaHealth> for DaHealthResponse {
+    fn from(health: DaHealth) -> Self {
+        DaHealthResponse {
+            connected: health.connected,
+            reconnects: health.reconnects,
+            last_error: health.last_error,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct UpdateProofResponse(UpdateProof);
 
@@ -79,9 +140,25 @@ pub struct UserKeyResponse {
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(post_transaction, get_hashchain, get_commitment),
+    paths(
+        post_transaction,
+        get_transaction_status,
+        get_rejection_receipts,
+        get_faults,
+        get_da_health,
+        get_equivocation_evidence,
+        post_equivocation_evidence,
+        get_hashchain,
+        get_commitment,
+        get_signed_commitment
+    ),
     components(schemas(
         TransactionRequest,
+        TransactionResponse,
+        RejectionReceiptsResponse,
+        EpochFaultResponse,
+        FaultsResponse,
+        DaHealthResponse,
         EpochData,
         UpdateProofResponse,
         Hash,
@@ -104,8 +181,17 @@ impl WebServer {
         info!("starting webserver on {}:{}", self.cfg.host, self.cfg.port);
         let app = Router::new()
             .route("/transaction", post(post_transaction))
+            .route("/transaction/:hash", get(get_transaction_status))
+            .route("/receipts/:epoch", get(get_rejection_receipts))
+            .route("/faults", get(get_faults))
+            .route("/da-health", get(get_da_health))
+            .route(
+                "/equivocation-evidence",
+                get(get_equivocation_evidence).post(post_equivocation_evidence),
+            )
             .route("/get-hashchain", post(get_hashchain))
             .route("/get-current-commitment", get(get_commitment))
+            .route("/commitment/:epoch", get(get_signed_commitment))
             .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
             .layer(CorsLayer::permissive())
             .with_state(self.session.clone());
@@ -127,7 +213,7 @@ impl WebServer {
     path = "/transaction",
     request_body = TransactionRequest,
     responses(
-        (status = 200, description = "Entry update queued for insertion into next epoch"),
+        (status = 200, description = "Entry update queued for insertion into next epoch", body = TransactionResponse),
         (status = 400, description = "Bad request"),
         (status = 500, description = "Internal server error")
     )
@@ -141,9 +227,12 @@ async fn post_transaction(
         entry: update_input.entry.clone(),
     };
     match session.validate_and_queue_update(transaction).await {
-        Ok(_) => (
+        Ok(hash) => (
             StatusCode::OK,
-            "Entry update queued for insertion into next epoch",
+            Json(TransactionResponse {
+                hash: hash.to_hex(),
+                status: TransactionStatus::Queued,
+            }),
         )
             .into_response(),
         Err(e) => (
@@ -154,6 +243,147 @@ async fn post_transaction(
     }
 }
 
+/// Returns the lifecycle status of the transaction with the given hex encoded hash.
+///
+#[utoipa::path(
+    get,
+    path = "/transaction/{hash}",
+    params(("hash" = String, Path, description = "Hex encoded transaction hash")),
+    responses(
+        (status = 200, description = "Successfully retrieved transaction status", body = TransactionResponse),
+        (status = 400, description = "Invalid transaction hash"),
+        (status = 404, description = "Transaction not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_transaction_status(
+    State(session): State<Arc<Prover>>,
+    Path(hash): Path<String>,
+) -> impl IntoResponse {
+    let Ok(digest) = Digest::from_hex(&hash) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("Invalid transaction hash: {}", hash),
+        )
+            .into_response();
+    };
+
+    match session.get_transaction_status(&digest) {
+        Ok(Some(status)) => {
+            (StatusCode::OK, Json(TransactionResponse { hash, status })).into_response()
+        }
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            format!("Transaction {} not found", hash),
+        )
+            .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Returns the receipts of all transactions that were rejected when executing the given epoch.
+///
+#[utoipa::path(
+    get,
+    path = "/receipts/{epoch}",
+    params(("epoch" = u64, Path, description = "Epoch height")),
+    responses(
+        (status = 200, description = "Successfully retrieved rejection receipts", body = RejectionReceiptsResponse),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_rejection_receipts(
+    State(session): State<Arc<Prover>>,
+    Path(epoch): Path<u64>,
+) -> impl IntoResponse {
+    match session.get_rejection_receipts(epoch) {
+        Ok(receipts) => (
+            StatusCode::OK,
+            Json(RejectionReceiptsResponse { epoch, receipts }),
+        )
+            .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Returns the epochs that were signed by the prover but failed verification, and whether
+/// syncing halted because of them.
+///
+#[utoipa::path(
+    get,
+    path = "/faults",
+    responses(
+        (status = 200, description = "Successfully retrieved faults", body = FaultsResponse),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_faults(State(session): State<Arc<Prover>>) -> impl IntoResponse {
+    match session.get_epoch_faults() {
+        Ok(faults) => (
+            StatusCode::OK,
+            Json(FaultsResponse {
+                halted: session.is_halted(),
+                faults: faults.into_iter().map(EpochFaultResponse::from).collect(),
+            }),
+        )
+            .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Returns whether the node is connected to the DA layer, and how often it had to reconnect.
+///
+#[utoipa::path(
+    get,
+    path = "/da-health",
+    responses(
+        (status = 200, description = "Successfully retrieved DA layer health", body = DaHealthResponse)
+    )
+)]
+async fn get_da_health(State(session): State<Arc<Prover>>) -> impl IntoResponse {
+    Json(DaHealthResponse::from(session.da.health()))
+}
+
+/// Returns the evidence of the prover signing conflicting epochs, found on the DA layer or
+/// submitted by other nodes.
+///
+#[utoipa::path(
+    get,
+    path = "/equivocation-evidence",
+    responses(
+        (status = 200, description = "Successfully retrieved evidence", body = Vec<EquivocationEvidence>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_equivocation_evidence(State(session): State<Arc<Prover>>) -> impl IntoResponse {
+    match session.get_equivocation_evidence() {
+        Ok(evidence) => (StatusCode::OK, Json(evidence)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Submits evidence of the prover signing conflicting epochs. Valid evidence is stored and
+/// halts syncing.
+///
+#[utoipa::path(
+    post,
+    path = "/equivocation-evidence",
+    request_body = EquivocationEvidence,
+    responses(
+        (status = 200, description = "Evidence was verified and stored"),
+        (status = 400, description = "Evidence is invalid")
+    )
+)]
+async fn post_equivocation_evidence(
+    State(session): State<Arc<Prover>>,
+    Json(evidence): Json<EquivocationEvidence>,
+) -> impl IntoResponse {
+    match session.submit_equivocation_evidence(&evidence) {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, format!("Invalid evidence: {}", e)).into_response(),
+    }
+}
+
 /// The /get-hashchain endpoint returns all added keys for a given user id.
 ///
 /// If the ID is not found in the database, the endpoint will return a 400 response with the message "Could not calculate values".
@@ -219,3 +449,31 @@ async fn get_commitment(State(session): State<Arc<Prover>>) -> impl IntoResponse
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     }
 }
+
+/// Returns the commitment after the given number of epochs, signed by this node. Light clients
+/// cross-check their trusted checkpoint against it.
+///
+#[utoipa::path(
+    get,
+    path = "/commitment/{epoch}",
+    params(("epoch" = u64, Path, description = "Number of epochs the commitment was reached after")),
+    responses(
+        (status = 200, description = "Successfully retrieved signed commitment", body = SignedCommitment),
+        (status = 404, description = "Epoch was not processed yet"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_signed_commitment(
+    State(session): State<Arc<Prover>>,
+    Path(epoch): Path<u64>,
+) -> impl IntoResponse {
+    match session.get_signed_commitment(epoch) {
+        Ok(Some(commitment)) => (StatusCode::OK, Json(commitment)).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            format!("Epoch {} was not processed yet", epoch),
+        )
+            .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
@@ -1,20 +1,142 @@
 use anyhow::Result;
 use auto_impl::auto_impl;
-use jmt::storage::{TreeReader, TreeWriter};
-use prism_common::digest::Digest;
+use jmt::storage::{NodeBatch, TreeReader, TreeWriter};
+use prism_common::{
+    digest::Digest,
+    transaction::{RejectionReceipt, Transaction, TransactionStatus},
+};
 use prism_errors::{DatabaseError, PrismError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A set of writes that is applied to a [`Database`] in one atomic step.
+///
+/// Either all of the contained state (tree nodes, commitments, epoch counter,
+/// sync height, DA height of the last epoch, transaction buffer, transaction statuses and
+/// rejection receipts) is persisted, or none of it is.
+#[derive(Debug, Clone, Default)]
+pub struct WriteBatch {
+    pub node_batch: NodeBatch,
+    pub commitments: BTreeMap<u64, Digest>,
+    pub epoch: Option<u64>,
+    pub sync_height: Option<u64>,
+    pub last_epoch_da_height: Option<u64>,
+    /// Buffered transactions by DA height. `None` removes the height from the buffer.
+    pub buffered_transactions: BTreeMap<u64, Option<Vec<Transaction>>>,
+    pub transaction_statuses: BTreeMap<Digest, TransactionStatus>,
+    pub rejection_receipts: BTreeMap<u64, Vec<RejectionReceipt>>,
+}
+
+impl WriteBatch {
+    pub fn is_empty(&self) -> bool {
+        self.node_batch.nodes().is_empty()
+            && self.node_batch.values().is_empty()
+            && self.commitments.is_empty()
+            && self.epoch.is_none()
+            && self.sync_height.is_none()
+            && self.last_epoch_da_height.is_none()
+            && self.buffered_transactions.is_empty()
+            && self.transaction_statuses.is_empty()
+            && self.rejection_receipts.is_empty()
+    }
+
+    /// Merges `other` into this batch. Writes from `other` take precedence.
+    pub fn merge(&mut self, other: WriteBatch) {
+        self.node_batch.merge(other.node_batch);
+        self.commitments.extend(other.commitments);
+        if other.epoch.is_some() {
+            self.epoch = other.epoch;
+        }
+        if other.sync_height.is_some() {
+            self.sync_height = other.sync_height;
+        }
+        if other.last_epoch_da_height.is_some() {
+            self.last_epoch_da_height = other.last_epoch_da_height;
+        }
+        self.buffered_transactions.extend(other.buffered_transactions);
+        self.transaction_statuses.extend(other.transaction_statuses);
+        self.rejection_receipts.extend(other.rejection_receipts);
+    }
+}
+
+/// An item waiting to be submitted to the DA layer. The payload is opaque to the
+/// storage layer, callers decide how it is encoded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    pub id: u64,
+    pub payload: Vec<u8>,
+    /// Number of failed submission attempts so far.
+    pub attempts: u64,
+    pub last_error: Option<String>,
+}
+
+/// Evidence of a finalized epoch that was signed by the prover but failed verification.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EpochFault {
+    /// DA height the epoch was read from.
+    pub da_height: u64,
+    pub epoch_height: u64,
+    /// Why the epoch was rejected.
+    pub reason: String,
+    /// The offending epoch, as it was posted to the DA layer.
+    pub blob: Vec<u8>,
+}
 
 #[auto_impl(&, Box, Arc)]
 pub trait Database: Send + Sync + TreeReader + TreeWriter {
     fn get_commitment(&self, epoch: &u64) -> Result<Digest>;
     fn set_commitment(&self, epoch: &u64, commitment: &Digest) -> Result<()>;
 
+    /// Returns the current epoch. Fails with [`DatabaseError::NotFoundError`] if none was
+    /// stored yet, or returns 0.
     fn get_epoch(&self) -> Result<u64>;
     fn set_epoch(&self, epoch: &u64) -> Result<()>;
 
     fn get_last_synced_height(&self) -> Result<u64>;
     fn set_last_synced_height(&self, height: &u64) -> Result<()>;
 
+    /// Returns the DA height the latest epoch was finalized or read at, if there was one.
+    fn get_last_epoch_da_height(&self) -> Result<Option<u64>>;
+    fn set_last_epoch_da_height(&self, height: &u64) -> Result<()>;
+
+    /// Returns all transactions that were read from the DA layer but not yet
+    /// applied in an epoch, keyed by the DA height they were found at.
+    fn get_buffered_transactions(&self) -> Result<BTreeMap<u64, Vec<Transaction>>>;
+    fn set_buffered_transactions(&self, height: &u64, transactions: &[Transaction]) -> Result<()>;
+    fn remove_buffered_transactions(&self, height: &u64) -> Result<()>;
+
+    fn get_transaction_status(&self, hash: &Digest) -> Result<Option<TransactionStatus>>;
+    fn set_transaction_status(&self, hash: &Digest, status: &TransactionStatus) -> Result<()>;
+
+    /// Returns the receipts of all transactions rejected in `epoch`, in execution order.
+    fn get_rejection_receipts(&self, epoch: &u64) -> Result<Vec<RejectionReceipt>>;
+    fn set_rejection_receipts(&self, epoch: &u64, receipts: &[RejectionReceipt]) -> Result<()>;
+
+    /// Returns all outbox entries, ordered by id.
+    fn get_outbox_entries(&self) -> Result<Vec<OutboxEntry>>;
+    fn get_outbox_entry(&self, id: &u64) -> Result<Option<OutboxEntry>>;
+    fn put_outbox_entry(&self, entry: &OutboxEntry) -> Result<()>;
+    fn remove_outbox_entry(&self, id: &u64) -> Result<()>;
+
+    /// Returns all recorded epoch faults, ordered by DA height.
+    fn get_epoch_faults(&self) -> Result<Vec<EpochFault>>;
+    /// Records `fault`, replacing an earlier fault at the same DA height.
+    fn put_epoch_fault(&self, fault: &EpochFault) -> Result<()>;
+
+    /// Returns the encoded evidence of the prover signing conflicting epochs, ordered by epoch
+    /// height. Like outbox payloads, the evidence is opaque to the storage layer.
+    fn get_equivocation_evidence(&self) -> Result<Vec<Vec<u8>>>;
+    /// Records `evidence` for `epoch_height`, replacing earlier evidence for the same epoch.
+    fn put_equivocation_evidence(&self, epoch_height: &u64, evidence: &[u8]) -> Result<()>;
+
+    /// Returns the encoded prover-signed header first seen for `epoch_height`, used to detect
+    /// conflicting epochs posted at different DA heights.
+    fn get_signed_epoch_header(&self, epoch_height: &u64) -> Result<Option<Vec<u8>>>;
+    fn put_signed_epoch_header(&self, epoch_height: &u64, header: &[u8]) -> Result<()>;
+
+    /// Atomically applies all writes contained in `batch`.
+    fn commit_batch(&self, batch: &WriteBatch) -> Result<()>;
+
     fn flush_database(&self) -> Result<()>;
 }
 
@@ -3,14 +3,17 @@ use jmt::{
     storage::{LeafNode, Node, NodeBatch, NodeKey, TreeReader, TreeWriter},
     KeyHash, OwnedValue, Version,
 };
-use prism_common::digest::Digest;
+use prism_common::{
+    digest::Digest,
+    transaction::{RejectionReceipt, Transaction, TransactionStatus},
+};
 use prism_errors::DatabaseError;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     sync::{Arc, Mutex},
 };
 
-use crate::database::Database;
+use crate::database::{Database, EpochFault, OutboxEntry, WriteBatch};
 
 pub struct InMemoryDatabase {
     nodes: Arc<Mutex<HashMap<NodeKey, Node>>>,
@@ -18,6 +21,14 @@ pub struct InMemoryDatabase {
     commitments: Arc<Mutex<HashMap<u64, Digest>>>,
     current_epoch: Arc<Mutex<u64>>,
     sync_height: Arc<Mutex<u64>>,
+    last_epoch_da_height: Arc<Mutex<Option<u64>>>,
+    buffered_transactions: Arc<Mutex<BTreeMap<u64, Vec<Transaction>>>>,
+    outbox: Arc<Mutex<BTreeMap<u64, OutboxEntry>>>,
+    epoch_faults: Arc<Mutex<BTreeMap<u64, EpochFault>>>,
+    equivocation_evidence: Arc<Mutex<BTreeMap<u64, Vec<u8>>>>,
+    signed_epoch_headers: Arc<Mutex<BTreeMap<u64, Vec<u8>>>>,
+    transaction_statuses: Arc<Mutex<HashMap<Digest, TransactionStatus>>>,
+    rejection_receipts: Arc<Mutex<HashMap<u64, Vec<RejectionReceipt>>>>,
 }
 
 impl InMemoryDatabase {
@@ -28,6 +39,14 @@ impl InMemoryDatabase {
             commitments: Arc::new(Mutex::new(HashMap::new())),
             current_epoch: Arc::new(Mutex::new(0)),
             sync_height: Arc::new(Mutex::new(1)),
+            last_epoch_da_height: Arc::new(Mutex::new(None)),
+            buffered_transactions: Arc::new(Mutex::new(BTreeMap::new())),
+            outbox: Arc::new(Mutex::new(BTreeMap::new())),
+            epoch_faults: Arc::new(Mutex::new(BTreeMap::new())),
+            equivocation_evidence: Arc::new(Mutex::new(BTreeMap::new())),
+            signed_epoch_headers: Arc::new(Mutex::new(BTreeMap::new())),
+            transaction_statuses: Arc::new(Mutex::new(HashMap::new())),
+            rejection_receipts: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 }
@@ -77,16 +96,22 @@ impl TreeWriter for InMemoryDatabase {
     fn write_node_batch(&self, node_batch: &NodeBatch) -> Result<()> {
         let mut nodes = self.nodes.lock().unwrap();
         let mut values = self.values.lock().unwrap();
+        apply_node_batch(&mut nodes, &mut values, node_batch);
+        Ok(())
+    }
+}
 
-        for (node_key, node) in node_batch.nodes() {
-            nodes.insert(node_key.clone(), node.clone());
-        }
-
-        for ((version, key_hash), value) in node_batch.values() {
-            values.insert((*version, *key_hash), value.clone().unwrap_or_default());
-        }
+fn apply_node_batch(
+    nodes: &mut HashMap<NodeKey, Node>,
+    values: &mut HashMap<(Version, KeyHash), OwnedValue>,
+    node_batch: &NodeBatch,
+) {
+    for (node_key, node) in node_batch.nodes() {
+        nodes.insert(node_key.clone(), node.clone());
+    }
 
-        Ok(())
+    for ((version, key_hash), value) in node_batch.values() {
+        values.insert((*version, *key_hash), value.clone().unwrap_or_default());
     }
 }
 
@@ -125,11 +150,143 @@ impl Database for InMemoryDatabase {
         Ok(())
     }
 
+    fn get_last_epoch_da_height(&self) -> Result<Option<u64>> {
+        Ok(*self.last_epoch_da_height.lock().unwrap())
+    }
+
+    fn set_last_epoch_da_height(&self, height: &u64) -> Result<()> {
+        *self.last_epoch_da_height.lock().unwrap() = Some(*height);
+        Ok(())
+    }
+
+    fn get_buffered_transactions(&self) -> Result<BTreeMap<u64, Vec<Transaction>>> {
+        Ok(self.buffered_transactions.lock().unwrap().clone())
+    }
+
+    fn set_buffered_transactions(&self, height: &u64, transactions: &[Transaction]) -> Result<()> {
+        self.buffered_transactions.lock().unwrap().insert(*height, transactions.to_vec());
+        Ok(())
+    }
+
+    fn remove_buffered_transactions(&self, height: &u64) -> Result<()> {
+        self.buffered_transactions.lock().unwrap().remove(height);
+        Ok(())
+    }
+
+    fn get_transaction_status(&self, hash: &Digest) -> Result<Option<TransactionStatus>> {
+        Ok(self.transaction_statuses.lock().unwrap().get(hash).cloned())
+    }
+
+    fn set_transaction_status(&self, hash: &Digest, status: &TransactionStatus) -> Result<()> {
+        self.transaction_statuses.lock().unwrap().insert(*hash, status.clone());
+        Ok(())
+    }
+
+    fn get_rejection_receipts(&self, epoch: &u64) -> Result<Vec<RejectionReceipt>> {
+        Ok(self.rejection_receipts.lock().unwrap().get(epoch).cloned().unwrap_or_default())
+    }
+
+    fn set_rejection_receipts(&self, epoch: &u64, receipts: &[RejectionReceipt]) -> Result<()> {
+        self.rejection_receipts.lock().unwrap().insert(*epoch, receipts.to_vec());
+        Ok(())
+    }
+
+    fn get_outbox_entries(&self) -> Result<Vec<OutboxEntry>> {
+        Ok(self.outbox.lock().unwrap().values().cloned().collect())
+    }
+
+    fn get_outbox_entry(&self, id: &u64) -> Result<Option<OutboxEntry>> {
+        Ok(self.outbox.lock().unwrap().get(id).cloned())
+    }
+
+    fn put_outbox_entry(&self, entry: &OutboxEntry) -> Result<()> {
+        self.outbox.lock().unwrap().insert(entry.id, entry.clone());
+        Ok(())
+    }
+
+    fn remove_outbox_entry(&self, id: &u64) -> Result<()> {
+        self.outbox.lock().unwrap().remove(id);
+        Ok(())
+    }
+
+    fn get_epoch_faults(&self) -> Result<Vec<EpochFault>> {
+        Ok(self.epoch_faults.lock().unwrap().values().cloned().collect())
+    }
+
+    fn put_epoch_fault(&self, fault: &EpochFault) -> Result<()> {
+        self.epoch_faults.lock().unwrap().insert(fault.da_height, fault.clone());
+        Ok(())
+    }
+
+    fn get_equivocation_evidence(&self) -> Result<Vec<Vec<u8>>> {
+        Ok(self.equivocation_evidence.lock().unwrap().values().cloned().collect())
+    }
+
+    fn put_equivocation_evidence(&self, epoch_height: &u64, evidence: &[u8]) -> Result<()> {
+        self.equivocation_evidence.lock().unwrap().insert(*epoch_height, evidence.to_vec());
+        Ok(())
+    }
+
+    fn get_signed_epoch_header(&self, epoch_height: &u64) -> Result<Option<Vec<u8>>> {
+        Ok(self.signed_epoch_headers.lock().unwrap().get(epoch_height).cloned())
+    }
+
+    fn put_signed_epoch_header(&self, epoch_height: &u64, header: &[u8]) -> Result<()> {
+        self.signed_epoch_headers.lock().unwrap().insert(*epoch_height, header.to_vec());
+        Ok(())
+    }
+
+    fn commit_batch(&self, batch: &WriteBatch) -> Result<()> {
+        // all locks are taken up front so that readers never observe a partially applied batch
+        let mut nodes = self.nodes.lock().unwrap();
+        let mut values = self.values.lock().unwrap();
+        let mut commitments = self.commitments.lock().unwrap();
+        let mut current_epoch = self.current_epoch.lock().unwrap();
+        let mut sync_height = self.sync_height.lock().unwrap();
+        let mut last_epoch_da_height = self.last_epoch_da_height.lock().unwrap();
+        let mut buffered_transactions = self.buffered_transactions.lock().unwrap();
+        let mut transaction_statuses = self.transaction_statuses.lock().unwrap();
+        let mut rejection_receipts = self.rejection_receipts.lock().unwrap();
+
+        apply_node_batch(&mut nodes, &mut values, &batch.node_batch);
+        commitments.extend(batch.commitments.iter().map(|(epoch, c)| (*epoch, *c)));
+        if let Some(epoch) = batch.epoch {
+            *current_epoch = epoch;
+        }
+        if let Some(height) = batch.sync_height {
+            *sync_height = height;
+        }
+        if let Some(height) = batch.last_epoch_da_height {
+            *last_epoch_da_height = Some(height);
+        }
+        for (height, transactions) in &batch.buffered_transactions {
+            match transactions {
+                Some(transactions) => buffered_transactions.insert(*height, transactions.clone()),
+                None => buffered_transactions.remove(height),
+            };
+        }
+        transaction_statuses.extend(
+            batch.transaction_statuses.iter().map(|(hash, status)| (*hash, status.clone())),
+        );
+        rejection_receipts.extend(
+            batch.rejection_receipts.iter().map(|(epoch, receipts)| (*epoch, receipts.clone())),
+        );
+        Ok(())
+    }
+
     fn flush_database(&self) -> Result<()> {
         self.nodes.lock().unwrap().clear();
         self.values.lock().unwrap().clear();
         self.commitments.lock().unwrap().clear();
         *self.current_epoch.lock().unwrap() = 0;
+        *self.last_epoch_da_height.lock().unwrap() = None;
+        self.buffered_transactions.lock().unwrap().clear();
+        self.outbox.lock().unwrap().clear();
+        self.epoch_faults.lock().unwrap().clear();
+        self.equivocation_evidence.lock().unwrap().clear();
+        self.signed_epoch_headers.lock().unwrap().clear();
+        self.transaction_statuses.lock().unwrap().clear();
+        self.rejection_receipts.lock().unwrap().clear();
         Ok(())
     }
 }
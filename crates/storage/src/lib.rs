@@ -2,5 +2,11 @@ pub mod database;
 pub mod inmemory;
 pub mod redis;
 pub mod rocksdb;
+pub mod staged;
 
-pub use crate::{database::Database, redis::RedisConnection};
+pub use crate::{
+    database::{Database, EpochFault, OutboxEntry, WriteBatch},
+    redis::RedisConnection,
+    rocksdb::RocksDBConnection,
+    staged::StagedDatabase,
+};
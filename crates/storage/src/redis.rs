@@ -4,11 +4,15 @@ use jmt::{
     KeyHash, OwnedValue, Version,
 };
 use mockall::predicate::*;
-use prism_common::digest::Digest;
+use prism_common::{
+    digest::Digest,
+    transaction::{RejectionReceipt, Transaction, TransactionStatus},
+};
 use redis::{Client, Commands, Connection};
 use serde::{Deserialize, Serialize};
 use std::{
     self,
+    collections::BTreeMap,
     process::Command,
     sync::{Mutex, MutexGuard},
     thread::sleep,
@@ -17,7 +21,7 @@ use std::{
 
 use prism_errors::DatabaseError;
 
-use crate::database::{convert_to_connection_error, Database};
+use crate::database::{convert_to_connection_error, Database, EpochFault, OutboxEntry, WriteBatch};
 use log::debug;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -36,6 +40,13 @@ impl Default for RedisConfig {
 // there are different key prefixes for the different tables in the database
 // app_state:key => app state (just epoch counter for now)
 // commitments:key => epoch commitments
+// app_state:buffered_transactions => hash of DA height => buffered transactions
+// outbox => hash of entry id => outbox entry
+// transaction_status:hash => lifecycle status of a transaction
+// rejection_receipts:epoch_N => receipts of the transactions rejected in an epoch
+// epoch_faults => hash of DA height => evidence of an epoch that failed verification
+// equivocation_evidence => hash of epoch height => conflicting epochs signed by the prover
+// signed_epoch_headers => hash of epoch height => first prover-signed header seen for the epoch
 pub struct RedisConnection {
     connection: Mutex<Connection>,
 }
@@ -127,22 +138,33 @@ impl TreeReader for RedisConnection {
     }
 }
 
+fn add_node_batch_to_pipeline(pipe: &mut redis::Pipeline, node_batch: &NodeBatch) -> Result<()> {
+    for (node_key, node) in node_batch.nodes() {
+        let serialized_key = hex::encode(bincode::serialize(node_key)?);
+        let node_data = bincode::serialize(node)?;
+        pipe.set(format!("node:{}", serialized_key), node_data);
+    }
+
+    for ((version, key_hash), value) in node_batch.values() {
+        let value_key = format!("value_history:{}", hex::encode(key_hash.0));
+        let encoded_value = value.as_ref().map(hex::encode).unwrap_or_default();
+        pipe.zadd(&value_key, encoded_value, *version as f64);
+    }
+
+    Ok(())
+}
+
+const BUFFERED_TRANSACTIONS_KEY: &str = "app_state:buffered_transactions";
+const OUTBOX_KEY: &str = "outbox";
+const EPOCH_FAULTS_KEY: &str = "epoch_faults";
+const EQUIVOCATION_EVIDENCE_KEY: &str = "equivocation_evidence";
+const SIGNED_EPOCH_HEADERS_KEY: &str = "signed_epoch_headers";
+
 impl TreeWriter for RedisConnection {
     fn write_node_batch(&self, node_batch: &NodeBatch) -> Result<()> {
         let mut con = self.lock_connection()?;
         let mut pipe = redis::pipe();
-
-        for (node_key, node) in node_batch.nodes() {
-            let serialized_key = hex::encode(bincode::serialize(node_key)?);
-            let node_data = bincode::serialize(node)?;
-            pipe.set(format!("node:{}", serialized_key), node_data);
-        }
-
-        for ((version, key_hash), value) in node_batch.values() {
-            let value_key = format!("value_history:{}", hex::encode(key_hash.0));
-            let encoded_value = value.as_ref().map(hex::encode).unwrap_or_default();
-            pipe.zadd(&value_key, encoded_value, *version as f64);
-        }
+        add_node_batch_to_pipeline(&mut pipe, node_batch)?;
 
         pipe.execute(&mut con);
         Ok(())
@@ -181,10 +203,28 @@ impl Database for RedisConnection {
         })
     }
 
+    fn get_last_epoch_da_height(&self) -> Result<Option<u64>> {
+        let mut con = self.lock_connection()?;
+        con.get("app_state:last_epoch_da_height")
+            .map_err(|_| anyhow!(DatabaseError::ReadError("last epoch DA height".to_string())))
+    }
+
+    fn set_last_epoch_da_height(&self, height: &u64) -> Result<()> {
+        let mut con = self.lock_connection()?;
+        con.set::<&str, &u64, ()>("app_state:last_epoch_da_height", height).map_err(|_| {
+            anyhow!(DatabaseError::WriteError(format!(
+                "last_epoch_da_height: {}",
+                height
+            )))
+        })
+    }
+
     fn get_epoch(&self) -> Result<u64> {
         let mut con = self.lock_connection()?;
-        con.get("app_state:epoch")
-            .map_err(|_| anyhow!(DatabaseError::NotFoundError("current epoch".to_string())))
+        let epoch: Option<u64> = con
+            .get("app_state:epoch")
+            .map_err(|_| anyhow!(DatabaseError::ReadError("current epoch".to_string())))?;
+        epoch.ok_or_else(|| anyhow!(DatabaseError::NotFoundError("current epoch".to_string())))
     }
 
     fn set_epoch(&self, epoch: &u64) -> Result<()> {
@@ -204,6 +244,246 @@ impl Database for RedisConnection {
             })
     }
 
+    fn get_buffered_transactions(&self) -> Result<BTreeMap<u64, Vec<Transaction>>> {
+        let mut con = self.lock_connection()?;
+        let encoded: BTreeMap<u64, Vec<u8>> =
+            con.hgetall(BUFFERED_TRANSACTIONS_KEY).map_err(|_| {
+                anyhow!(DatabaseError::ReadError(
+                    "buffered transactions".to_string()
+                ))
+            })?;
+
+        encoded
+            .into_iter()
+            .map(|(height, data)| Ok((height, bincode::deserialize(&data)?)))
+            .collect()
+    }
+
+    fn set_buffered_transactions(&self, height: &u64, transactions: &[Transaction]) -> Result<()> {
+        let mut con = self.lock_connection()?;
+        let encoded = bincode::serialize(transactions)?;
+        con.hset::<&str, &u64, Vec<u8>, ()>(BUFFERED_TRANSACTIONS_KEY, height, encoded).map_err(
+            |_| {
+                anyhow!(DatabaseError::WriteError(format!(
+                    "buffered transactions for height {}",
+                    height
+                )))
+            },
+        )
+    }
+
+    fn remove_buffered_transactions(&self, height: &u64) -> Result<()> {
+        let mut con = self.lock_connection()?;
+        con.hdel::<&str, &u64, ()>(BUFFERED_TRANSACTIONS_KEY, height).map_err(|_| {
+            anyhow!(DatabaseError::DeleteError(format!(
+                "buffered transactions for height {}",
+                height
+            )))
+        })
+    }
+
+    fn get_transaction_status(&self, hash: &Digest) -> Result<Option<TransactionStatus>> {
+        let mut con = self.lock_connection()?;
+        let status_data: Option<Vec<u8>> =
+            con.get(format!("transaction_status:{}", hash.to_hex())).map_err(|_| {
+                anyhow!(DatabaseError::ReadError(format!(
+                    "status of transaction {}",
+                    hash
+                )))
+            })?;
+        status_data.map(|data| Ok(bincode::deserialize(&data)?)).transpose()
+    }
+
+    fn set_transaction_status(&self, hash: &Digest, status: &TransactionStatus) -> Result<()> {
+        let mut con = self.lock_connection()?;
+        let encoded = bincode::serialize(status)?;
+        con.set::<String, Vec<u8>, ()>(format!("transaction_status:{}", hash.to_hex()), encoded)
+            .map_err(|_| {
+                anyhow!(DatabaseError::WriteError(format!(
+                    "status of transaction {}",
+                    hash
+                )))
+            })
+    }
+
+    fn get_rejection_receipts(&self, epoch: &u64) -> Result<Vec<RejectionReceipt>> {
+        let mut con = self.lock_connection()?;
+        let receipts_data: Option<Vec<u8>> =
+            con.get(format!("rejection_receipts:epoch_{}", epoch)).map_err(|_| {
+                anyhow!(DatabaseError::ReadError(format!(
+                    "rejection receipts for epoch {}",
+                    epoch
+                )))
+            })?;
+        match receipts_data {
+            Some(data) => Ok(bincode::deserialize(&data)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn set_rejection_receipts(&self, epoch: &u64, receipts: &[RejectionReceipt]) -> Result<()> {
+        let mut con = self.lock_connection()?;
+        let encoded = bincode::serialize(receipts)?;
+        con.set::<String, Vec<u8>, ()>(format!("rejection_receipts:epoch_{}", epoch), encoded)
+            .map_err(|_| {
+                anyhow!(DatabaseError::WriteError(format!(
+                    "rejection receipts for epoch {}",
+                    epoch
+                )))
+            })
+    }
+
+    fn get_outbox_entries(&self) -> Result<Vec<OutboxEntry>> {
+        let mut con = self.lock_connection()?;
+        let encoded: BTreeMap<u64, Vec<u8>> = con
+            .hgetall(OUTBOX_KEY)
+            .map_err(|_| anyhow!(DatabaseError::ReadError("outbox".to_string())))?;
+
+        encoded.values().map(|data| Ok(bincode::deserialize(data)?)).collect()
+    }
+
+    fn get_outbox_entry(&self, id: &u64) -> Result<Option<OutboxEntry>> {
+        let mut con = self.lock_connection()?;
+        let encoded: Option<Vec<u8>> = con
+            .hget(OUTBOX_KEY, id)
+            .map_err(|_| anyhow!(DatabaseError::ReadError(format!("outbox entry {}", id))))?;
+
+        encoded.map(|data| Ok(bincode::deserialize(&data)?)).transpose()
+    }
+
+    fn put_outbox_entry(&self, entry: &OutboxEntry) -> Result<()> {
+        let mut con = self.lock_connection()?;
+        let encoded = bincode::serialize(entry)?;
+        con.hset::<&str, &u64, Vec<u8>, ()>(OUTBOX_KEY, &entry.id, encoded).map_err(|_| {
+            anyhow!(DatabaseError::WriteError(format!(
+                "outbox entry {}",
+                entry.id
+            )))
+        })
+    }
+
+    fn remove_outbox_entry(&self, id: &u64) -> Result<()> {
+        let mut con = self.lock_connection()?;
+        con.hdel::<&str, &u64, ()>(OUTBOX_KEY, id)
+            .map_err(|_| anyhow!(DatabaseError::DeleteError(format!("outbox entry {}", id))))
+    }
+
+    fn get_epoch_faults(&self) -> Result<Vec<EpochFault>> {
+        let mut con = self.lock_connection()?;
+        let encoded: BTreeMap<u64, Vec<u8>> = con
+            .hgetall(EPOCH_FAULTS_KEY)
+            .map_err(|_| anyhow!(DatabaseError::ReadError("epoch faults".to_string())))?;
+
+        encoded.values().map(|data| Ok(bincode::deserialize(data)?)).collect()
+    }
+
+    fn put_epoch_fault(&self, fault: &EpochFault) -> Result<()> {
+        let mut con = self.lock_connection()?;
+        let encoded = bincode::serialize(fault)?;
+        con.hset::<&str, &u64, Vec<u8>, ()>(EPOCH_FAULTS_KEY, &fault.da_height, encoded).map_err(
+            |_| {
+                anyhow!(DatabaseError::WriteError(format!(
+                    "epoch fault at height {}",
+                    fault.da_height
+                )))
+            },
+        )
+    }
+
+    fn get_equivocation_evidence(&self) -> Result<Vec<Vec<u8>>> {
+        let mut con = self.lock_connection()?;
+        let evidence: BTreeMap<u64, Vec<u8>> =
+            con.hgetall(EQUIVOCATION_EVIDENCE_KEY).map_err(|_| {
+                anyhow!(DatabaseError::ReadError(
+                    "equivocation evidence".to_string()
+                ))
+            })?;
+        Ok(evidence.into_values().collect())
+    }
+
+    fn put_equivocation_evidence(&self, epoch_height: &u64, evidence: &[u8]) -> Result<()> {
+        let mut con = self.lock_connection()?;
+        con.hset::<&str, &u64, Vec<u8>, ()>(
+            EQUIVOCATION_EVIDENCE_KEY,
+            epoch_height,
+            evidence.to_vec(),
+        )
+        .map_err(|_| {
+            anyhow!(DatabaseError::WriteError(format!(
+                "equivocation evidence for epoch {}",
+                epoch_height
+            )))
+        })
+    }
+
+    fn get_signed_epoch_header(&self, epoch_height: &u64) -> Result<Option<Vec<u8>>> {
+        let mut con = self.lock_connection()?;
+        con.hget(SIGNED_EPOCH_HEADERS_KEY, epoch_height).map_err(|_| {
+            anyhow!(DatabaseError::ReadError(format!(
+                "signed header of epoch {}",
+                epoch_height
+            )))
+        })
+    }
+
+    fn put_signed_epoch_header(&self, epoch_height: &u64, header: &[u8]) -> Result<()> {
+        let mut con = self.lock_connection()?;
+        con.hset::<&str, &u64, Vec<u8>, ()>(SIGNED_EPOCH_HEADERS_KEY, epoch_height, header.to_vec())
+            .map_err(|_| {
+                anyhow!(DatabaseError::WriteError(format!(
+                    "signed header of epoch {}",
+                    epoch_height
+                )))
+            })
+    }
+
+    fn commit_batch(&self, batch: &WriteBatch) -> Result<()> {
+        let mut con = self.lock_connection()?;
+        // wraps all commands into MULTI/EXEC, so they are applied atomically
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+
+        add_node_batch_to_pipeline(&mut pipe, &batch.node_batch)?;
+        for (epoch, commitment) in &batch.commitments {
+            pipe.set(format!("commitments:epoch_{}", epoch), &commitment.0).ignore();
+        }
+        if let Some(epoch) = batch.epoch {
+            pipe.set("app_state:epoch", epoch).ignore();
+        }
+        if let Some(height) = batch.sync_height {
+            pipe.set("app_state:sync_height", height).ignore();
+        }
+        if let Some(height) = batch.last_epoch_da_height {
+            pipe.set("app_state:last_epoch_da_height", height).ignore();
+        }
+        for (height, transactions) in &batch.buffered_transactions {
+            match transactions {
+                Some(transactions) => {
+                    let encoded = bincode::serialize(transactions)?;
+                    pipe.hset(BUFFERED_TRANSACTIONS_KEY, height, encoded).ignore();
+                }
+                None => {
+                    pipe.hdel(BUFFERED_TRANSACTIONS_KEY, height).ignore();
+                }
+            }
+        }
+        for (hash, status) in &batch.transaction_statuses {
+            let encoded = bincode::serialize(status)?;
+            pipe.set(format!("transaction_status:{}", hash.to_hex()), encoded).ignore();
+        }
+        for (epoch, receipts) in &batch.rejection_receipts {
+            let encoded = bincode::serialize(receipts)?;
+            pipe.set(format!("rejection_receipts:epoch_{}", epoch), encoded).ignore();
+        }
+
+        pipe.query::<()>(&mut *con).map_err(|e| {
+            anyhow!(DatabaseError::WriteError(format!(
+                "batch for epoch {:?}: {}",
+                batch.epoch, e
+            )))
+        })
+    }
+
     fn flush_database(&self) -> Result<()> {
         let mut conn = self.lock_connection()?;
         redis::cmd("FLUSHALL")
@@ -1,15 +1,77 @@
-use crate::Database;
-use anyhow::Result;
+use crate::database::{Database, EpochFault, OutboxEntry, WriteBatch};
+use anyhow::{anyhow, Result};
 use jmt::{
     storage::{LeafNode, Node, NodeBatch, NodeKey, TreeReader, TreeWriter},
     KeyHash, OwnedValue, Version,
 };
-use prism_common::digest::Digest;
+use prism_common::{
+    digest::Digest,
+    transaction::{RejectionReceipt, Transaction, TransactionStatus},
+};
 use prism_errors::DatabaseError;
-use rocksdb::{DBWithThreadMode, MultiThreaded, Options, DB};
+use rocksdb::{
+    BoundColumnFamily, DBWithThreadMode, Direction, IteratorMode, MultiThreaded, Options,
+    WriteBatch as RocksDBWriteBatch,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc};
 
 type RocksDB = DBWithThreadMode<MultiThreaded>;
 
+// column families used to separate the different tables of the database
+// nodes => serialized JMT nodes, keyed by their serialized NodeKey
+// leaves => serialized NodeKey of the newest leaf of each key, keyed by key_hash
+// values => versioned JMT values, keyed by key_hash ++ version (big endian)
+// app_state => epoch counter, sync height, DA height of the last epoch and epoch commitments
+// buffered_transactions => transactions not yet applied in an epoch, keyed by DA height (big endian)
+// outbox => items waiting to be submitted to the DA layer, keyed by id (big endian)
+// transaction_status => lifecycle status of transactions, keyed by transaction hash
+// rejection_receipts => receipts of the transactions rejected in an epoch, keyed by epoch (big endian)
+// epoch_faults => evidence of epochs that failed verification, keyed by DA height (big endian)
+// equivocation_evidence => conflicting epochs signed by the prover, keyed by epoch height (big endian)
+// signed_epoch_headers => first prover-signed header seen for an epoch, keyed by epoch height (big endian)
+const CF_NODES: &str = "nodes";
+const CF_LEAVES: &str = "leaves";
+const CF_VALUES: &str = "values";
+const CF_APP_STATE: &str = "app_state";
+const CF_BUFFERED_TRANSACTIONS: &str = "buffered_transactions";
+const CF_OUTBOX: &str = "outbox";
+const CF_TRANSACTION_STATUS: &str = "transaction_status";
+const CF_REJECTION_RECEIPTS: &str = "rejection_receipts";
+const CF_EPOCH_FAULTS: &str = "epoch_faults";
+const CF_EQUIVOCATION_EVIDENCE: &str = "equivocation_evidence";
+const CF_SIGNED_EPOCH_HEADERS: &str = "signed_epoch_headers";
+const COLUMN_FAMILIES: [&str; 11] = [
+    CF_NODES,
+    CF_LEAVES,
+    CF_VALUES,
+    CF_APP_STATE,
+    CF_BUFFERED_TRANSACTIONS,
+    CF_OUTBOX,
+    CF_TRANSACTION_STATUS,
+    CF_REJECTION_RECEIPTS,
+    CF_EPOCH_FAULTS,
+    CF_EQUIVOCATION_EVIDENCE,
+    CF_SIGNED_EPOCH_HEADERS,
+];
+
+const KEY_EPOCH: &[u8] = b"epoch";
+const KEY_SYNC_HEIGHT: &[u8] = b"sync_height";
+const KEY_LAST_EPOCH_DA_HEIGHT: &[u8] = b"last_epoch_da_height";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RocksDBConfig {
+    pub path: String,
+}
+
+impl RocksDBConfig {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+        }
+    }
+}
+
 pub struct RocksDBConnection {
     connection: RocksDB,
     path: String,
@@ -17,19 +79,103 @@ pub struct RocksDBConnection {
 
 impl RocksDBConnection {
     pub fn new(path: &str) -> Result<RocksDBConnection> {
-        let db = DB::open_default(path)?;
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+
+        let db = RocksDB::open_cf(&opts, path, COLUMN_FAMILIES)
+            .map_err(|e| DatabaseError::InitializationError(e.to_string()))?;
 
-        Ok(Self {
+        let connection = Self {
             connection: db,
             path: path.to_string(),
-        })
+        };
+        connection.index_leaves()?;
+        Ok(connection)
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn cf_handle(&self, name: &str) -> Result<Arc<BoundColumnFamily>> {
+        self.connection
+            .cf_handle(name)
+            .ok_or_else(|| anyhow!(DatabaseError::ReadError(format!("column family {}", name))))
+    }
+
+    /// Builds the leaf index for databases written before it existed.
+    fn index_leaves(&self) -> Result<()> {
+        let nodes_cf = self.cf_handle(CF_NODES)?;
+        let leaves_cf = self.cf_handle(CF_LEAVES)?;
+        if self.connection.iterator_cf(&leaves_cf, IteratorMode::Start).next().is_some() {
+            return Ok(());
+        }
+
+        let mut batch = RocksDBWriteBatch::default();
+        let mut newest: BTreeMap<KeyHash, NodeKey> = BTreeMap::new();
+        for item in self.connection.iterator_cf(&nodes_cf, IteratorMode::Start) {
+            let (key, value) = item?;
+            if let Node::Leaf(leaf_node) = bincode::deserialize(&value)? {
+                let node_key: NodeKey = bincode::deserialize(&key)?;
+                match newest.get(&leaf_node.key_hash()) {
+                    Some(existing) if existing.version() >= node_key.version() => {}
+                    _ => {
+                        newest.insert(leaf_node.key_hash(), node_key);
+                    }
+                }
+            }
+        }
+        for (key_hash, node_key) in newest {
+            batch.put_cf(&leaves_cf, key_hash.0, bincode::serialize(&node_key)?);
+        }
+
+        self.connection
+            .write(batch)
+            .map_err(|e| anyhow!(DatabaseError::WriteError(format!("leaf index: {}", e))))
+    }
+
+    fn add_node_batch(&self, batch: &mut RocksDBWriteBatch, node_batch: &NodeBatch) -> Result<()> {
+        let nodes_cf = self.cf_handle(CF_NODES)?;
+        let leaves_cf = self.cf_handle(CF_LEAVES)?;
+        let values_cf = self.cf_handle(CF_VALUES)?;
+
+        // node batches are written in version order, so the index keeps the newest leaf
+        for (node_key, node) in node_batch.nodes() {
+            let serialized_key = bincode::serialize(node_key)?;
+            let node_data = bincode::serialize(node)?;
+            if let Node::Leaf(leaf_node) = node {
+                batch.put_cf(&leaves_cf, leaf_node.key_hash().0, &serialized_key);
+            }
+            batch.put_cf(&nodes_cf, serialized_key, node_data);
+        }
+
+        for ((version, key_hash), value) in node_batch.values() {
+            let encoded_value = bincode::serialize(value)?;
+            batch.put_cf(&values_cf, value_key(*version, key_hash), encoded_value);
+        }
+
+        Ok(())
     }
 }
 
+fn commitment_key(epoch: &u64) -> Vec<u8> {
+    format!("commitments:epoch_{}", epoch).into_bytes()
+}
+
+// values are keyed by key_hash ++ version, so that all versions of a key are
+// stored next to each other and sorted by version
+fn value_key(version: Version, key_hash: &KeyHash) -> Vec<u8> {
+    let mut key = Vec::with_capacity(40);
+    key.extend_from_slice(&key_hash.0);
+    key.extend_from_slice(&version.to_be_bytes());
+    key
+}
+
 impl Database for RocksDBConnection {
     fn get_commitment(&self, epoch: &u64) -> anyhow::Result<Digest> {
-        let key = format!("commitments:epoch_{}", epoch);
-        let raw_bytes = self.connection.get(key.as_bytes())?.ok_or_else(|| {
+        let cf = self.cf_handle(CF_APP_STATE)?;
+        let raw_bytes = self.connection.get_cf(&cf, commitment_key(epoch))?.ok_or_else(|| {
             DatabaseError::NotFoundError(format!("commitment from epoch_{}", epoch))
         })?;
 
@@ -40,70 +186,315 @@ impl Database for RocksDBConnection {
     }
 
     fn set_commitment(&self, epoch: &u64, commitment: &Digest) -> anyhow::Result<()> {
-        Ok(self.connection.put::<&[u8], [u8; 32]>(
-            format!("commitments:epoch_{}", epoch).as_bytes(),
-            commitment.0,
-        )?)
+        let cf = self.cf_handle(CF_APP_STATE)?;
+        Ok(self.connection.put_cf(&cf, commitment_key(epoch), commitment.0)?)
     }
 
     fn get_last_synced_height(&self) -> anyhow::Result<u64> {
+        let cf = self.cf_handle(CF_APP_STATE)?;
         let res = self
             .connection
-            .get(b"app_state:sync_height")?
+            .get_cf(&cf, KEY_SYNC_HEIGHT)?
             .ok_or_else(|| DatabaseError::NotFoundError("current sync height".to_string()))?;
 
         Ok(u64::from_be_bytes(res.try_into().unwrap()))
     }
 
     fn set_last_synced_height(&self, height: &u64) -> anyhow::Result<()> {
-        Ok(self.connection.put(b"app_state:sync_height", height.to_be_bytes())?)
+        let cf = self.cf_handle(CF_APP_STATE)?;
+        Ok(self.connection.put_cf(&cf, KEY_SYNC_HEIGHT, height.to_be_bytes())?)
+    }
+
+    fn get_last_epoch_da_height(&self) -> Result<Option<u64>> {
+        let cf = self.cf_handle(CF_APP_STATE)?;
+        let res = self.connection.get_cf(&cf, KEY_LAST_EPOCH_DA_HEIGHT)?;
+
+        Ok(res.map(|bytes| u64::from_be_bytes(bytes.try_into().unwrap())))
+    }
+
+    fn set_last_epoch_da_height(&self, height: &u64) -> Result<()> {
+        let cf = self.cf_handle(CF_APP_STATE)?;
+        Ok(self.connection.put_cf(&cf, KEY_LAST_EPOCH_DA_HEIGHT, height.to_be_bytes())?)
     }
 
     fn get_epoch(&self) -> anyhow::Result<u64> {
+        let cf = self.cf_handle(CF_APP_STATE)?;
         let res = self
             .connection
-            .get(b"app_state:epoch")?
+            .get_cf(&cf, KEY_EPOCH)?
             .ok_or_else(|| DatabaseError::NotFoundError("current epoch".to_string()))?;
 
         Ok(u64::from_be_bytes(res.try_into().unwrap()))
     }
 
     fn set_epoch(&self, epoch: &u64) -> anyhow::Result<()> {
-        Ok(self.connection.put(b"app_state:epoch", epoch.to_be_bytes())?)
+        let cf = self.cf_handle(CF_APP_STATE)?;
+        Ok(self.connection.put_cf(&cf, KEY_EPOCH, epoch.to_be_bytes())?)
+    }
+
+    fn get_buffered_transactions(&self) -> Result<BTreeMap<u64, Vec<Transaction>>> {
+        let cf = self.cf_handle(CF_BUFFERED_TRANSACTIONS)?;
+        let mut buffered = BTreeMap::new();
+        for item in self.connection.iterator_cf(&cf, IteratorMode::Start) {
+            let (key, value) = item?;
+            let height = u64::from_be_bytes(key.as_ref().try_into()?);
+            buffered.insert(height, bincode::deserialize(&value)?);
+        }
+        Ok(buffered)
+    }
+
+    fn set_buffered_transactions(&self, height: &u64, transactions: &[Transaction]) -> Result<()> {
+        let cf = self.cf_handle(CF_BUFFERED_TRANSACTIONS)?;
+        let encoded = bincode::serialize(transactions)?;
+        Ok(self.connection.put_cf(&cf, height.to_be_bytes(), encoded)?)
+    }
+
+    fn remove_buffered_transactions(&self, height: &u64) -> Result<()> {
+        let cf = self.cf_handle(CF_BUFFERED_TRANSACTIONS)?;
+        Ok(self.connection.delete_cf(&cf, height.to_be_bytes())?)
+    }
+
+    fn get_transaction_status(&self, hash: &Digest) -> Result<Option<TransactionStatus>> {
+        let cf = self.cf_handle(CF_TRANSACTION_STATUS)?;
+        let status_data = self.connection.get_cf(&cf, hash.0)?;
+        status_data.map(|data| Ok(bincode::deserialize(&data)?)).transpose()
+    }
+
+    fn set_transaction_status(&self, hash: &Digest, status: &TransactionStatus) -> Result<()> {
+        let cf = self.cf_handle(CF_TRANSACTION_STATUS)?;
+        let encoded = bincode::serialize(status)?;
+        Ok(self.connection.put_cf(&cf, hash.0, encoded)?)
+    }
+
+    fn get_rejection_receipts(&self, epoch: &u64) -> Result<Vec<RejectionReceipt>> {
+        let cf = self.cf_handle(CF_REJECTION_RECEIPTS)?;
+        match self.connection.get_cf(&cf, epoch.to_be_bytes())? {
+            Some(data) => Ok(bincode::deserialize(&data)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn set_rejection_receipts(&self, epoch: &u64, receipts: &[RejectionReceipt]) -> Result<()> {
+        let cf = self.cf_handle(CF_REJECTION_RECEIPTS)?;
+        let encoded = bincode::serialize(receipts)?;
+        Ok(self.connection.put_cf(&cf, epoch.to_be_bytes(), encoded)?)
+    }
+
+    fn get_outbox_entries(&self) -> Result<Vec<OutboxEntry>> {
+        let cf = self.cf_handle(CF_OUTBOX)?;
+        self.connection
+            .iterator_cf(&cf, IteratorMode::Start)
+            .map(|item| {
+                let (_, value) = item?;
+                Ok(bincode::deserialize(&value)?)
+            })
+            .collect()
+    }
+
+    fn get_outbox_entry(&self, id: &u64) -> Result<Option<OutboxEntry>> {
+        let cf = self.cf_handle(CF_OUTBOX)?;
+        match self.connection.get_cf(&cf, id.to_be_bytes())? {
+            Some(value) => Ok(Some(bincode::deserialize(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn put_outbox_entry(&self, entry: &OutboxEntry) -> Result<()> {
+        let cf = self.cf_handle(CF_OUTBOX)?;
+        let encoded = bincode::serialize(entry)?;
+        Ok(self.connection.put_cf(&cf, entry.id.to_be_bytes(), encoded)?)
+    }
+
+    fn remove_outbox_entry(&self, id: &u64) -> Result<()> {
+        let cf = self.cf_handle(CF_OUTBOX)?;
+        Ok(self.connection.delete_cf(&cf, id.to_be_bytes())?)
+    }
+
+    fn get_epoch_faults(&self) -> Result<Vec<EpochFault>> {
+        let cf = self.cf_handle(CF_EPOCH_FAULTS)?;
+        self.connection
+            .iterator_cf(&cf, IteratorMode::Start)
+            .map(|item| {
+                let (_, value) = item?;
+                Ok(bincode::deserialize(&value)?)
+            })
+            .collect()
+    }
+
+    fn put_epoch_fault(&self, fault: &EpochFault) -> Result<()> {
+        let cf = self.cf_handle(CF_EPOCH_FAULTS)?;
+        let encoded = bincode::serialize(fault)?;
+        Ok(self.connection.put_cf(&cf, fault.da_height.to_be_bytes(), encoded)?)
+    }
+
+    fn get_equivocation_evidence(&self) -> Result<Vec<Vec<u8>>> {
+        let cf = self.cf_handle(CF_EQUIVOCATION_EVIDENCE)?;
+        self.connection
+            .iterator_cf(&cf, IteratorMode::Start)
+            .map(|item| {
+                let (_, value) = item?;
+                Ok(value.to_vec())
+            })
+            .collect()
+    }
+
+    fn put_equivocation_evidence(&self, epoch_height: &u64, evidence: &[u8]) -> Result<()> {
+        let cf = self.cf_handle(CF_EQUIVOCATION_EVIDENCE)?;
+        Ok(self.connection.put_cf(&cf, epoch_height.to_be_bytes(), evidence)?)
+    }
+
+    fn get_signed_epoch_header(&self, epoch_height: &u64) -> Result<Option<Vec<u8>>> {
+        let cf = self.cf_handle(CF_SIGNED_EPOCH_HEADERS)?;
+        Ok(self.connection.get_cf(&cf, epoch_height.to_be_bytes())?)
+    }
+
+    fn put_signed_epoch_header(&self, epoch_height: &u64, header: &[u8]) -> Result<()> {
+        let cf = self.cf_handle(CF_SIGNED_EPOCH_HEADERS)?;
+        Ok(self.connection.put_cf(&cf, epoch_height.to_be_bytes(), header)?)
+    }
+
+    fn commit_batch(&self, batch: &WriteBatch) -> Result<()> {
+        let app_state_cf = self.cf_handle(CF_APP_STATE)?;
+        let buffered_cf = self.cf_handle(CF_BUFFERED_TRANSACTIONS)?;
+        let status_cf = self.cf_handle(CF_TRANSACTION_STATUS)?;
+        let receipts_cf = self.cf_handle(CF_REJECTION_RECEIPTS)?;
+        let mut write_batch = RocksDBWriteBatch::default();
+
+        self.add_node_batch(&mut write_batch, &batch.node_batch)?;
+        for (epoch, commitment) in &batch.commitments {
+            write_batch.put_cf(&app_state_cf, commitment_key(epoch), commitment.0);
+        }
+        if let Some(epoch) = batch.epoch {
+            write_batch.put_cf(&app_state_cf, KEY_EPOCH, epoch.to_be_bytes());
+        }
+        if let Some(height) = batch.sync_height {
+            write_batch.put_cf(&app_state_cf, KEY_SYNC_HEIGHT, height.to_be_bytes());
+        }
+        if let Some(height) = batch.last_epoch_da_height {
+            write_batch.put_cf(
+                &app_state_cf,
+                KEY_LAST_EPOCH_DA_HEIGHT,
+                height.to_be_bytes(),
+            );
+        }
+        for (height, transactions) in &batch.buffered_transactions {
+            match transactions {
+                Some(transactions) => write_batch.put_cf(
+                    &buffered_cf,
+                    height.to_be_bytes(),
+                    bincode::serialize(transactions)?,
+                ),
+                None => write_batch.delete_cf(&buffered_cf, height.to_be_bytes()),
+            }
+        }
+        for (hash, status) in &batch.transaction_statuses {
+            write_batch.put_cf(&status_cf, hash.0, bincode::serialize(status)?);
+        }
+        for (epoch, receipts) in &batch.rejection_receipts {
+            write_batch.put_cf(
+                &receipts_cf,
+                epoch.to_be_bytes(),
+                bincode::serialize(receipts)?,
+            );
+        }
+
+        self.connection.write(write_batch).map_err(|e| {
+            anyhow!(DatabaseError::WriteError(format!(
+                "batch for epoch {:?}: {}",
+                batch.epoch, e
+            )))
+        })
     }
 
     fn flush_database(&self) -> Result<()> {
-        Ok(DB::destroy(&Options::default(), &self.path)?)
+        let mut batch = RocksDBWriteBatch::default();
+        for name in COLUMN_FAMILIES {
+            let cf = self.cf_handle(name)?;
+            for item in self.connection.iterator_cf(&cf, IteratorMode::Start) {
+                let (key, _) = item?;
+                batch.delete_cf(&cf, key);
+            }
+        }
+
+        self.connection
+            .write(batch)
+            .map_err(|_| anyhow!(DatabaseError::DeleteError("all entries".to_string())))
     }
 }
 
 impl TreeWriter for RocksDBConnection {
-    fn write_node_batch(&self, _node_batch: &NodeBatch) -> Result<()> {
-        todo!()
+    fn write_node_batch(&self, node_batch: &NodeBatch) -> Result<()> {
+        let mut batch = RocksDBWriteBatch::default();
+        self.add_node_batch(&mut batch, node_batch)?;
+
+        self.connection
+            .write(batch)
+            .map_err(|e| anyhow!(DatabaseError::WriteError(format!("node batch: {}", e))))
     }
 }
 
 impl TreeReader for RocksDBConnection {
-    fn get_node_option(&self, _node_key: &NodeKey) -> Result<Option<Node>> {
-        todo!()
+    fn get_node_option(&self, node_key: &NodeKey) -> Result<Option<Node>> {
+        let cf = self.cf_handle(CF_NODES)?;
+        let serialized_key = bincode::serialize(node_key)?;
+        let node_data = self.connection.get_cf(&cf, serialized_key)?;
+
+        node_data.map(|data| Ok(bincode::deserialize(&data)?)).transpose()
     }
 
     fn get_value_option(
         &self,
-        _max_version: Version,
-        _key_hash: KeyHash,
+        max_version: Version,
+        key_hash: KeyHash,
     ) -> Result<Option<OwnedValue>> {
-        todo!()
+        let cf = self.cf_handle(CF_VALUES)?;
+        let seek_key = value_key(max_version, &key_hash);
+
+        // seeking backwards positions the iterator at the newest version
+        // that is less than or equal to max_version
+        let mut iter =
+            self.connection.iterator_cf(&cf, IteratorMode::From(&seek_key, Direction::Reverse));
+
+        match iter.next() {
+            Some(Ok((key, value))) if key.starts_with(&key_hash.0) => {
+                let value: Option<OwnedValue> = bincode::deserialize(&value)?;
+                Ok(value)
+            }
+            Some(Err(e)) => Err(anyhow!(DatabaseError::ReadError(format!(
+                "value history: {}",
+                e
+            )))),
+            _ => Ok(None),
+        }
     }
 
     fn get_rightmost_leaf(&self) -> Result<Option<(NodeKey, LeafNode)>> {
-        todo!()
+        let leaves_cf = self.cf_handle(CF_LEAVES)?;
+
+        // key hashes are compared byte by byte, so the last entry of the index is the rightmost
+        let Some(item) = self.connection.iterator_cf(&leaves_cf, IteratorMode::End).next() else {
+            return Ok(None);
+        };
+        let (_, serialized_key) = item?;
+        let node_key: NodeKey = bincode::deserialize(&serialized_key)?;
+        match self.get_node_option(&node_key)? {
+            Some(Node::Leaf(leaf_node)) => Ok(Some((node_key, leaf_node))),
+            _ => Err(anyhow!(DatabaseError::ReadError(format!(
+                "rightmost leaf {:?}",
+                node_key
+            )))),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::inmemory::InMemoryDatabase;
+    use prism_common::{
+        transaction_builder::TransactionBuilder,
+        tree::{HashchainResponse::*, KeyDirectoryTree, SnarkableTree},
+    };
     use tempfile::TempDir;
 
     #[test]
@@ -137,6 +528,13 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         let db = RocksDBConnection::new(temp_dir.path().to_str().unwrap()).unwrap();
 
+        // a fresh database is told apart from one that fails to read
+        let missing = db.get_epoch().unwrap_err();
+        assert!(matches!(
+            missing.downcast_ref::<DatabaseError>(),
+            Some(DatabaseError::NotFoundError(_))
+        ));
+
         let epoch = 1;
         db.set_epoch(&epoch).unwrap();
 
@@ -155,4 +553,233 @@ mod tests {
         let result = db.get_epoch().unwrap();
         assert_eq!(result, epoch);
     }
+
+    #[test]
+    fn test_tree_matches_inmemory_database() {
+        let temp_dir = TempDir::new().unwrap();
+        let rocksdb = Arc::new(RocksDBConnection::new(temp_dir.path().to_str().unwrap()).unwrap());
+        let inmemory = Arc::new(InMemoryDatabase::new());
+
+        let mut rocksdb_tree = KeyDirectoryTree::new(rocksdb);
+        let mut inmemory_tree = KeyDirectoryTree::new(inmemory);
+
+        let mut tx_builder = TransactionBuilder::new();
+        let transactions = vec![
+            tx_builder.register_service_with_random_keys("service_id").commit(),
+            tx_builder.create_account_with_random_key("account_id", "service_id").commit(),
+            tx_builder.add_random_key_verified_with_root("account_id").commit(),
+        ];
+
+        for transaction in transactions {
+            rocksdb_tree.process_transaction(transaction.clone()).unwrap();
+            inmemory_tree.process_transaction(transaction).unwrap();
+        }
+
+        assert_eq!(
+            rocksdb_tree.get_commitment().unwrap(),
+            inmemory_tree.get_commitment().unwrap()
+        );
+
+        let key_hash = KeyHash::with::<prism_common::hasher::Hasher>(Digest::hash("account_id"));
+        let Found(rocksdb_hc, _) = rocksdb_tree.get(key_hash).unwrap() else {
+            panic!("hashchain not found in rocksdb");
+        };
+        let Found(inmemory_hc, _) = inmemory_tree.get(key_hash).unwrap() else {
+            panic!("hashchain not found in inmemory database");
+        };
+        assert_eq!(rocksdb_hc, inmemory_hc);
+        assert_eq!(rocksdb_hc.len(), 2);
+    }
+
+    #[test]
+    fn test_get_value_option_respects_max_version() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Arc::new(RocksDBConnection::new(temp_dir.path().to_str().unwrap()).unwrap());
+        let mut tree = KeyDirectoryTree::new(db.clone());
+
+        let mut tx_builder = TransactionBuilder::new();
+        tree.process_transaction(
+            tx_builder.register_service_with_random_keys("service_id").commit(),
+        )
+        .unwrap();
+        tree.process_transaction(
+            tx_builder.create_account_with_random_key("account_id", "service_id").commit(),
+        )
+        .unwrap();
+        tree.process_transaction(
+            tx_builder.add_random_key_verified_with_root("account_id").commit(),
+        )
+        .unwrap();
+
+        let key_hash = KeyHash::with::<prism_common::hasher::Hasher>(Digest::hash("account_id"));
+
+        // the account is created at version 2 and updated at version 3
+        assert!(db.get_value_option(1, key_hash).unwrap().is_none());
+        let created = db.get_value_option(2, key_hash).unwrap().unwrap();
+        let updated = db.get_value_option(3, key_hash).unwrap().unwrap();
+        assert_ne!(created, updated);
+        assert_eq!(
+            db.get_value_option(100, key_hash).unwrap().unwrap(),
+            updated
+        );
+    }
+
+    #[test]
+    fn test_get_rightmost_leaf() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Arc::new(RocksDBConnection::new(temp_dir.path().to_str().unwrap()).unwrap());
+        assert!(db.get_rightmost_leaf().unwrap().is_none());
+
+        let mut tree = KeyDirectoryTree::new(db.clone());
+        let mut tx_builder = TransactionBuilder::new();
+        tree.process_transaction(
+            tx_builder.register_service_with_random_keys("service_id").commit(),
+        )
+        .unwrap();
+        tree.process_transaction(
+            tx_builder.create_account_with_random_key("account_id", "service_id").commit(),
+        )
+        .unwrap();
+
+        let (_, leaf) = db.get_rightmost_leaf().unwrap().unwrap();
+        let service_hash =
+            KeyHash::with::<prism_common::hasher::Hasher>(Digest::hash("service_id"));
+        let account_hash =
+            KeyHash::with::<prism_common::hasher::Hasher>(Digest::hash("account_id"));
+        assert!(leaf.key_hash() >= service_hash);
+        assert!(leaf.key_hash() >= account_hash);
+    }
+
+    #[test]
+    fn test_rightmost_leaf_index_is_rebuilt() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().to_str().unwrap();
+
+        let rightmost = {
+            let db = Arc::new(RocksDBConnection::new(path).unwrap());
+            let mut tree = KeyDirectoryTree::new(db.clone());
+            let mut tx_builder = TransactionBuilder::new();
+            for id in ["service_1", "service_2", "service_3"] {
+                tree.process_transaction(tx_builder.register_service_with_random_keys(id).commit())
+                    .unwrap();
+            }
+            let rightmost = db.get_rightmost_leaf().unwrap().unwrap();
+
+            // drops the index, like a database written before it existed
+            let cf = db.cf_handle(CF_LEAVES).unwrap();
+            for item in db.connection.iterator_cf(&cf, IteratorMode::Start) {
+                db.connection.delete_cf(&cf, item.unwrap().0).unwrap();
+            }
+            rightmost
+        };
+
+        let db = RocksDBConnection::new(path).unwrap();
+        assert_eq!(db.get_rightmost_leaf().unwrap().unwrap(), rightmost);
+    }
+
+    #[test]
+    fn test_reopen_persists_tree() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().to_str().unwrap();
+
+        let commitment = {
+            let db = Arc::new(RocksDBConnection::new(path).unwrap());
+            let mut tree = KeyDirectoryTree::new(db.clone());
+            let mut tx_builder = TransactionBuilder::new();
+            tree.process_transaction(
+                tx_builder.register_service_with_random_keys("service_id").commit(),
+            )
+            .unwrap();
+            db.set_epoch(&1).unwrap();
+            tree.get_commitment().unwrap()
+        };
+
+        let db = Arc::new(RocksDBConnection::new(path).unwrap());
+        let epoch = db.get_epoch().unwrap();
+        let tree = KeyDirectoryTree::load(db, epoch);
+        assert_eq!(tree.get_commitment().unwrap(), commitment);
+    }
+
+    #[test]
+    fn test_buffered_transactions_survive_reopen() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().to_str().unwrap();
+
+        let mut tx_builder = TransactionBuilder::new();
+        let first = vec![tx_builder.register_service_with_random_keys("service_1").commit()];
+        let second = vec![tx_builder.register_service_with_random_keys("service_2").commit()];
+
+        {
+            let db = RocksDBConnection::new(path).unwrap();
+            db.set_buffered_transactions(&7, &second).unwrap();
+            db.set_buffered_transactions(&3, &first).unwrap();
+        }
+
+        let db = RocksDBConnection::new(path).unwrap();
+        let buffered = db.get_buffered_transactions().unwrap();
+        assert_eq!(buffered.keys().copied().collect::<Vec<_>>(), vec![3, 7]);
+        assert_eq!(buffered.get(&3), Some(&first));
+
+        let mut batch = WriteBatch::default();
+        batch.buffered_transactions.insert(3, None);
+        db.commit_batch(&batch).unwrap();
+        assert_eq!(db.get_buffered_transactions().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_outbox_entries_are_ordered_by_id() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = RocksDBConnection::new(temp_dir.path().to_str().unwrap()).unwrap();
+
+        for id in [256, 1, 2] {
+            db.put_outbox_entry(&OutboxEntry {
+                id,
+                payload: vec![id as u8],
+                attempts: 0,
+                last_error: None,
+            })
+            .unwrap();
+        }
+        db.remove_outbox_entry(&2).unwrap();
+
+        let ids: Vec<u64> = db.get_outbox_entries().unwrap().iter().map(|entry| entry.id).collect();
+        assert_eq!(ids, vec![1, 256]);
+        assert_eq!(db.get_outbox_entry(&256).unwrap().unwrap().payload, vec![0]);
+        assert!(db.get_outbox_entry(&2).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_epoch_faults_survive_reopen() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().to_str().unwrap();
+
+        let fault = EpochFault {
+            da_height: 7,
+            epoch_height: 2,
+            reason: "new commitment mismatch at epoch 2".to_string(),
+            blob: vec![1, 2, 3],
+        };
+        {
+            let db = RocksDBConnection::new(path).unwrap();
+            db.put_epoch_fault(&fault).unwrap();
+            // recording the same DA height again does not duplicate the evidence
+            db.put_epoch_fault(&fault).unwrap();
+        }
+
+        let db = RocksDBConnection::new(path).unwrap();
+        assert_eq!(db.get_epoch_faults().unwrap(), vec![fault]);
+    }
+
+    #[test]
+    fn test_flush_database() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = RocksDBConnection::new(temp_dir.path().to_str().unwrap()).unwrap();
+
+        db.set_epoch(&3).unwrap();
+        db.set_commitment(&3, &Digest::hash("commitment")).unwrap();
+        db.flush_database().unwrap();
+
+        assert!(db.get_epoch().is_err());
+        assert!(db.get_commitment(&3).is_err());
+    }
 }
@@ -0,0 +1,377 @@
+use anyhow::Result;
+use jmt::{
+    storage::{LeafNode, Node, NodeBatch, NodeKey, TreeReader, TreeWriter},
+    KeyHash, OwnedValue, Version,
+};
+use prism_common::{
+    digest::Digest,
+    transaction::{RejectionReceipt, Transaction, TransactionStatus},
+};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::database::{Database, EpochFault, OutboxEntry, WriteBatch};
+
+/// [`StagedDatabase`] buffers all writes in memory until [`StagedDatabase::commit`] is called,
+/// which persists them to the underlying [`Database`] as a single atomic [`WriteBatch`].
+///
+/// Reads are served from the staged writes first, so callers always observe their own
+/// uncommitted state. If the node crashes before `commit`, none of the staged writes
+/// reach the underlying database.
+pub struct StagedDatabase {
+    inner: Arc<Box<dyn Database>>,
+    staged: Mutex<WriteBatch>,
+}
+
+impl StagedDatabase {
+    pub fn new(inner: Arc<Box<dyn Database>>) -> Self {
+        StagedDatabase {
+            inner,
+            staged: Mutex::new(WriteBatch::default()),
+        }
+    }
+
+    /// Persists all staged writes in one atomic step. The staged writes are only
+    /// cleared if the underlying database accepted the batch.
+    pub fn commit(&self) -> Result<()> {
+        let mut staged = self.staged.lock().unwrap();
+        if staged.is_empty() {
+            return Ok(());
+        }
+
+        self.inner.commit_batch(&staged)?;
+        *staged = WriteBatch::default();
+        Ok(())
+    }
+
+    /// Drops all staged writes without persisting them.
+    pub fn discard(&self) {
+        *self.staged.lock().unwrap() = WriteBatch::default();
+    }
+
+    pub fn inner(&self) -> Arc<Box<dyn Database>> {
+        self.inner.clone()
+    }
+}
+
+impl TreeReader for StagedDatabase {
+    fn get_node_option(&self, node_key: &NodeKey) -> Result<Option<Node>> {
+        if let Some(node) = self.staged.lock().unwrap().node_batch.nodes().get(node_key) {
+            return Ok(Some(node.clone()));
+        }
+        self.inner.get_node_option(node_key)
+    }
+
+    fn get_rightmost_leaf(&self) -> Result<Option<(NodeKey, LeafNode)>> {
+        let staged_rightmost = self
+            .staged
+            .lock()
+            .unwrap()
+            .node_batch
+            .nodes()
+            .iter()
+            .filter_map(|(key, node)| match node {
+                Node::Leaf(leaf) => Some((key.clone(), leaf.clone())),
+                _ => None,
+            })
+            .max_by_key(|(_, leaf)| leaf.key_hash());
+
+        match (staged_rightmost, self.inner.get_rightmost_leaf()?) {
+            (Some(staged), Some(committed)) => {
+                if staged.1.key_hash() >= committed.1.key_hash() {
+                    Ok(Some(staged))
+                } else {
+                    Ok(Some(committed))
+                }
+            }
+            (staged, committed) => Ok(staged.or(committed)),
+        }
+    }
+
+    fn get_value_option(
+        &self,
+        max_version: Version,
+        key_hash: KeyHash,
+    ) -> Result<Option<OwnedValue>> {
+        // staged versions are always newer than the committed ones, so a staged
+        // value within max_version shadows everything in the underlying database
+        let staged_value = self
+            .staged
+            .lock()
+            .unwrap()
+            .node_batch
+            .values()
+            .iter()
+            .filter(|((version, hash), _)| *version <= max_version && *hash == key_hash)
+            .max_by_key(|((version, _), _)| *version)
+            .map(|(_, value)| value.clone());
+
+        match staged_value {
+            Some(value) => Ok(value),
+            None => self.inner.get_value_option(max_version, key_hash),
+        }
+    }
+}
+
+impl TreeWriter for StagedDatabase {
+    fn write_node_batch(&self, node_batch: &NodeBatch) -> Result<()> {
+        self.staged.lock().unwrap().node_batch.merge(node_batch.clone());
+        Ok(())
+    }
+}
+
+impl Database for StagedDatabase {
+    fn get_commitment(&self, epoch: &u64) -> Result<Digest> {
+        if let Some(commitment) = self.staged.lock().unwrap().commitments.get(epoch) {
+            return Ok(*commitment);
+        }
+        self.inner.get_commitment(epoch)
+    }
+
+    fn set_commitment(&self, epoch: &u64, commitment: &Digest) -> Result<()> {
+        self.staged.lock().unwrap().commitments.insert(*epoch, *commitment);
+        Ok(())
+    }
+
+    fn get_epoch(&self) -> Result<u64> {
+        match self.staged.lock().unwrap().epoch {
+            Some(epoch) => Ok(epoch),
+            None => self.inner.get_epoch(),
+        }
+    }
+
+    fn set_epoch(&self, epoch: &u64) -> Result<()> {
+        self.staged.lock().unwrap().epoch = Some(*epoch);
+        Ok(())
+    }
+
+    fn get_last_synced_height(&self) -> Result<u64> {
+        match self.staged.lock().unwrap().sync_height {
+            Some(height) => Ok(height),
+            None => self.inner.get_last_synced_height(),
+        }
+    }
+
+    fn set_last_synced_height(&self, height: &u64) -> Result<()> {
+        self.staged.lock().unwrap().sync_height = Some(*height);
+        Ok(())
+    }
+
+    fn get_last_epoch_da_height(&self) -> Result<Option<u64>> {
+        match self.staged.lock().unwrap().last_epoch_da_height {
+            Some(height) => Ok(Some(height)),
+            None => self.inner.get_last_epoch_da_height(),
+        }
+    }
+
+    fn set_last_epoch_da_height(&self, height: &u64) -> Result<()> {
+        self.staged.lock().unwrap().last_epoch_da_height = Some(*height);
+        Ok(())
+    }
+
+    fn get_buffered_transactions(&self) -> Result<BTreeMap<u64, Vec<Transaction>>> {
+        let mut buffered = self.inner.get_buffered_transactions()?;
+        for (height, transactions) in &self.staged.lock().unwrap().buffered_transactions {
+            match transactions {
+                Some(transactions) => buffered.insert(*height, transactions.clone()),
+                None => buffered.remove(height),
+            };
+        }
+        Ok(buffered)
+    }
+
+    fn set_buffered_transactions(&self, height: &u64, transactions: &[Transaction]) -> Result<()> {
+        self.staged
+            .lock()
+            .unwrap()
+            .buffered_transactions
+            .insert(*height, Some(transactions.to_vec()));
+        Ok(())
+    }
+
+    fn remove_buffered_transactions(&self, height: &u64) -> Result<()> {
+        self.staged.lock().unwrap().buffered_transactions.insert(*height, None);
+        Ok(())
+    }
+
+    fn get_transaction_status(&self, hash: &Digest) -> Result<Option<TransactionStatus>> {
+        if let Some(status) = self.staged.lock().unwrap().transaction_statuses.get(hash) {
+            return Ok(Some(status.clone()));
+        }
+        self.inner.get_transaction_status(hash)
+    }
+
+    fn set_transaction_status(&self, hash: &Digest, status: &TransactionStatus) -> Result<()> {
+        self.staged.lock().unwrap().transaction_statuses.insert(*hash, status.clone());
+        Ok(())
+    }
+
+    fn get_rejection_receipts(&self, epoch: &u64) -> Result<Vec<RejectionReceipt>> {
+        if let Some(receipts) = self.staged.lock().unwrap().rejection_receipts.get(epoch) {
+            return Ok(receipts.clone());
+        }
+        self.inner.get_rejection_receipts(epoch)
+    }
+
+    fn set_rejection_receipts(&self, epoch: &u64, receipts: &[RejectionReceipt]) -> Result<()> {
+        self.staged.lock().unwrap().rejection_receipts.insert(*epoch, receipts.to_vec());
+        Ok(())
+    }
+
+    // outbox writes must survive a crash independently of the staged state,
+    // so they bypass the staging area
+    fn get_outbox_entries(&self) -> Result<Vec<OutboxEntry>> {
+        self.inner.get_outbox_entries()
+    }
+
+    fn get_outbox_entry(&self, id: &u64) -> Result<Option<OutboxEntry>> {
+        self.inner.get_outbox_entry(id)
+    }
+
+    fn put_outbox_entry(&self, entry: &OutboxEntry) -> Result<()> {
+        self.inner.put_outbox_entry(entry)
+    }
+
+    fn remove_outbox_entry(&self, id: &u64) -> Result<()> {
+        self.inner.remove_outbox_entry(id)
+    }
+
+    // faults, equivocation evidence and signed epoch headers are recorded right before the
+    // staged state may be discarded, so they bypass it as well
+    fn get_epoch_faults(&self) -> Result<Vec<EpochFault>> {
+        self.inner.get_epoch_faults()
+    }
+
+    fn put_epoch_fault(&self, fault: &EpochFault) -> Result<()> {
+        self.inner.put_epoch_fault(fault)
+    }
+
+    fn get_equivocation_evidence(&self) -> Result<Vec<Vec<u8>>> {
+        self.inner.get_equivocation_evidence()
+    }
+
+    fn put_equivocation_evidence(&self, epoch_height: &u64, evidence: &[u8]) -> Result<()> {
+        self.inner.put_equivocation_evidence(epoch_height, evidence)
+    }
+
+    fn get_signed_epoch_header(&self, epoch_height: &u64) -> Result<Option<Vec<u8>>> {
+        self.inner.get_signed_epoch_header(epoch_height)
+    }
+
+    fn put_signed_epoch_header(&self, epoch_height: &u64, header: &[u8]) -> Result<()> {
+        self.inner.put_signed_epoch_header(epoch_height, header)
+    }
+
+    fn commit_batch(&self, batch: &WriteBatch) -> Result<()> {
+        self.staged.lock().unwrap().merge(batch.clone());
+        Ok(())
+    }
+
+    fn flush_database(&self) -> Result<()> {
+        self.discard();
+        self.inner.flush_database()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inmemory::InMemoryDatabase;
+    use prism_common::{
+        transaction_builder::TransactionBuilder,
+        tree::{KeyDirectoryTree, SnarkableTree},
+    };
+
+    fn setup() -> (Arc<InMemoryDatabase>, Arc<StagedDatabase>) {
+        let inner = Arc::new(InMemoryDatabase::new());
+        let staged = Arc::new(StagedDatabase::new(Arc::new(
+            Box::new(inner.clone()) as Box<dyn Database>
+        )));
+        (inner, staged)
+    }
+
+    #[test]
+    fn test_staged_writes_are_invisible_until_commit() {
+        let (inner, staged) = setup();
+
+        staged.set_epoch(&1).unwrap();
+        staged.set_commitment(&1, &Digest::hash("commitment")).unwrap();
+        staged.set_last_synced_height(&10).unwrap();
+
+        assert_eq!(staged.get_epoch().unwrap(), 1);
+        assert_eq!(inner.get_epoch().unwrap(), 0);
+        assert!(inner.get_commitment(&1).is_err());
+
+        staged.commit().unwrap();
+
+        assert_eq!(inner.get_epoch().unwrap(), 1);
+        assert_eq!(
+            inner.get_commitment(&1).unwrap(),
+            Digest::hash("commitment")
+        );
+        assert_eq!(inner.get_last_synced_height().unwrap(), 10);
+    }
+
+    #[test]
+    fn test_tree_reads_staged_nodes() {
+        let (inner, staged) = setup();
+        let mut tree = KeyDirectoryTree::new(staged.clone());
+
+        let mut tx_builder = TransactionBuilder::new();
+        tree.process_transaction(
+            tx_builder.register_service_with_random_keys("service_id").commit(),
+        )
+        .unwrap();
+        tree.process_transaction(
+            tx_builder.create_account_with_random_key("account_id", "service_id").commit(),
+        )
+        .unwrap();
+        let commitment = tree.get_commitment().unwrap();
+
+        assert!(inner.get_rightmost_leaf().unwrap().is_none());
+        staged.commit().unwrap();
+
+        let reloaded = KeyDirectoryTree::load(inner, 2);
+        assert_eq!(reloaded.get_commitment().unwrap(), commitment);
+    }
+
+    #[test]
+    fn test_buffered_transactions_are_staged() {
+        let (inner, staged) = setup();
+        let mut tx_builder = TransactionBuilder::new();
+        let first = vec![tx_builder.register_service_with_random_keys("service_1").commit()];
+        let second = vec![tx_builder.register_service_with_random_keys("service_2").commit()];
+
+        inner.set_buffered_transactions(&3, &first).unwrap();
+        staged.set_buffered_transactions(&4, &second).unwrap();
+        staged.remove_buffered_transactions(&3).unwrap();
+
+        let buffered = staged.get_buffered_transactions().unwrap();
+        assert_eq!(buffered.len(), 1);
+        assert_eq!(buffered.get(&4), Some(&second));
+        assert_eq!(
+            inner.get_buffered_transactions().unwrap().get(&3),
+            Some(&first)
+        );
+
+        staged.commit().unwrap();
+
+        let buffered = inner.get_buffered_transactions().unwrap();
+        assert_eq!(buffered.len(), 1);
+        assert_eq!(buffered.get(&4), Some(&second));
+    }
+
+    #[test]
+    fn test_discard_drops_staged_writes() {
+        let (inner, staged) = setup();
+
+        staged.set_epoch(&5).unwrap();
+        staged.discard();
+        staged.commit().unwrap();
+
+        assert_eq!(staged.get_epoch().unwrap(), 0);
+        assert_eq!(inner.get_epoch().unwrap(), 0);
+    }
+}
@@ -40,8 +40,8 @@ async fn test_light_client_prover_talking() -> Result<()> {
         ..CelestiaConfig::default()
     };
 
-    let bridge_da_layer = Arc::new(CelestiaConnection::new(&bridge_cfg, None).await.unwrap());
-    let lc_da_layer = Arc::new(CelestiaConnection::new(&lc_cfg, None).await.unwrap());
+    let bridge_da_layer = Arc::new(CelestiaConnection::new(&bridge_cfg).await.unwrap());
+    let lc_da_layer = Arc::new(CelestiaConnection::new(&lc_cfg).await.unwrap());
     let db = setup_db();
     let signing_key = create_signing_key();
     let pubkey = signing_key.verification_key();
@@ -59,7 +59,16 @@ async fn test_light_client_prover_talking() -> Result<()> {
         &prover_cfg,
     )?);
 
-    let lightclient = Arc::new(LightClient::new(lc_da_layer.clone(), lc_cfg, Some(pubkey)));
+    let lightclient = Arc::new(
+        LightClient::builder(
+            lc_da_layer.clone(),
+            setup_db(),
+            prover_cfg.proving_backend.verifier(),
+        )
+        .start_height(lc_cfg.start_height)
+        .prover_pubkey(pubkey)
+        .build()?,
+    );
 
     let prover_clone = prover.clone();
     spawn(async move {
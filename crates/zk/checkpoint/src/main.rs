@@ -0,0 +1,19 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use prism_common::checkpoint::CheckpointInput;
+use sha2::{Digest, Sha256};
+
+pub fn main() {
+    let input = sp1_zkvm::io::read::<CheckpointInput>();
+
+    // the proofs were written to stdin by the prover and are checked as deferred proofs
+    let output = input
+        .aggregate(|vkey, public_values| {
+            let public_values_digest: [u8; 32] = Sha256::digest(public_values).into();
+            sp1_zkvm::lib::verify::verify_sp1_proof(vkey, &public_values_digest);
+        })
+        .unwrap();
+
+    sp1_zkvm::io::commit(&output);
+}
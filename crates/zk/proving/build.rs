@@ -0,0 +1,19 @@
+use std::{env, fs, path::PathBuf};
+
+/// Embeds the checkpoint program into the binary. The program is built separately with
+/// `just build`, so without it the node is built without checkpoint support.
+fn main() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let elf_path = manifest_dir.join("../../../elf/checkpoint-riscv32im-succinct-zkvm-elf");
+    println!("cargo:rerun-if-changed={}", elf_path.display());
+
+    let elf = fs::read(&elf_path).unwrap_or_else(|_| {
+        println!(
+            "cargo:warning=checkpoint program not found at {}, checkpoints are disabled",
+            elf_path.display()
+        );
+        Vec::new()
+    });
+    let out_path = PathBuf::from(env::var("OUT_DIR").unwrap()).join("checkpoint-elf");
+    fs::write(out_path, elf).unwrap();
+}
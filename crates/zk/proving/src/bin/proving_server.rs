@@ -0,0 +1,33 @@
+//! Stand-in for an out-of-process prover, reachable by nodes configured with a remote
+//! proving backend.
+//!
+//! Usage: `prism-proving-server [ADDRESS] [--mock] [--groth16]`
+
+#[macro_use]
+extern crate log;
+
+use anyhow::Result;
+use prism_proving::{remote::DEFAULT_PROVING_SERVER_ADDRESS, ProvingServer, Sp1Backend};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    pretty_env_logger::init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let address = args
+        .iter()
+        .find(|arg| !arg.starts_with("--"))
+        .map_or(DEFAULT_PROVING_SERVER_ADDRESS, String::as_str);
+
+    let backend = if args.iter().any(|arg| arg == "--mock") {
+        Sp1Backend::mock()
+    } else {
+        Sp1Backend::local(args.iter().any(|arg| arg == "--groth16"))
+    };
+
+    let listener = TcpListener::bind(address).await?;
+    info!("proving server listening on {}", address);
+    ProvingServer::new(Arc::new(backend)).serve(listener).await
+}
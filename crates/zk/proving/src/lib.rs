@@ -0,0 +1,93 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use prism_common::{checkpoint::CheckpointOutput, tree::Batch};
+use serde::{Deserialize, Serialize};
+use sp1_sdk::SP1ProofWithPublicValues;
+use std::sync::Arc;
+
+pub mod remote;
+pub mod sp1;
+
+#[macro_use]
+extern crate log;
+
+pub use remote::{ProvingServer, RemoteBackend};
+pub use sp1::Sp1Backend;
+
+pub const PRISM_ELF: &[u8] = include_bytes!("../../../../elf/riscv32im-succinct-zkvm-elf");
+
+/// The checkpoint program, which is built separately from the node. Empty if the node was built
+/// without it, see `build.rs`.
+pub const CHECKPOINT_ELF: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/checkpoint-elf"));
+
+/// [`EpochVerifier`] checks the validity proof of a finalized epoch.
+#[async_trait]
+pub trait EpochVerifier: Send + Sync {
+    async fn verify(&self, proof: &SP1ProofWithPublicValues) -> Result<()>;
+
+    /// Verifies a checkpoint proof and returns its public values. The checkpoint must have
+    /// aggregated proofs of the same epoch program this verifier accepts.
+    async fn verify_checkpoint(&self, proof: &SP1ProofWithPublicValues)
+        -> Result<CheckpointOutput>;
+}
+
+/// [`ProvingBackend`] proves the state transition of an epoch. Every backend can also verify
+/// the proofs it generates.
+#[async_trait]
+pub trait ProvingBackend: EpochVerifier {
+    async fn prove(&self, batch: &Batch) -> Result<SP1ProofWithPublicValues>;
+
+    /// Aggregates consecutive epoch proofs on top of the `previous` checkpoint proof, if any.
+    async fn prove_checkpoint(
+        &self,
+        previous: Option<&SP1ProofWithPublicValues>,
+        epochs: &[SP1ProofWithPublicValues],
+    ) -> Result<SP1ProofWithPublicValues>;
+}
+
+/// Selects the [`ProvingBackend`] or [`EpochVerifier`] of a node at runtime.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProvingBackendConfig {
+    /// Generates and verifies proofs with SP1 on this machine. Epoch proofs wrapped in groth16
+    /// cannot be aggregated into checkpoints.
+    Sp1Local {
+        #[serde(default)]
+        groth16: bool,
+    },
+    /// Uses SP1's mock prover, which executes the program but generates no real proofs.
+    Sp1Mock,
+    /// Forwards proving and verification to a [`ProvingServer`] listening on `address`.
+    /// Verification then trusts the server, so light clients should verify locally.
+    Remote { address: String },
+}
+
+impl Default for ProvingBackendConfig {
+    fn default() -> Self {
+        if cfg!(feature = "mock_prover") {
+            ProvingBackendConfig::Sp1Mock
+        } else {
+            ProvingBackendConfig::Sp1Local {
+                groth16: cfg!(feature = "groth16"),
+            }
+        }
+    }
+}
+
+impl ProvingBackendConfig {
+    pub fn backend(&self) -> Arc<dyn ProvingBackend> {
+        match self {
+            ProvingBackendConfig::Sp1Local { groth16 } => Arc::new(Sp1Backend::local(*groth16)),
+            ProvingBackendConfig::Sp1Mock => Arc::new(Sp1Backend::mock()),
+            ProvingBackendConfig::Remote { address } => Arc::new(RemoteBackend::new(address)),
+        }
+    }
+
+    pub fn verifier(&self) -> Arc<dyn EpochVerifier> {
+        match self {
+            ProvingBackendConfig::Sp1Local { groth16 } => Arc::new(Sp1Backend::local(*groth16)),
+            ProvingBackendConfig::Sp1Mock => Arc::new(Sp1Backend::mock()),
+            ProvingBackendConfig::Remote { address } => Arc::new(RemoteBackend::new(address)),
+        }
+    }
+}
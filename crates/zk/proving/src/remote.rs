@@ -0,0 +1,227 @@
+use crate::{EpochVerifier, ProvingBackend};
+use anyhow::{bail, ensure, Context, Result};
+use async_trait::async_trait;
+use prism_common::{checkpoint::CheckpointOutput, tree::Batch};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sp1_sdk::SP1ProofWithPublicValues;
+use std::sync::Arc;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+pub const DEFAULT_PROVING_SERVER_ADDRESS: &str = "127.0.0.1:50051";
+
+/// Upper bound for a single message, so a faulty peer cannot make us allocate arbitrary memory.
+const MAX_MESSAGE_SIZE: u64 = 1 << 30;
+
+#[derive(Serialize, Deserialize)]
+enum Request {
+    Prove(Batch),
+    Verify(SP1ProofWithPublicValues),
+    ProveCheckpoint {
+        previous: Option<SP1ProofWithPublicValues>,
+        epochs: Vec<SP1ProofWithPublicValues>,
+    },
+    VerifyCheckpoint(SP1ProofWithPublicValues),
+}
+
+#[derive(Serialize, Deserialize)]
+enum Response {
+    Proof(SP1ProofWithPublicValues),
+    Verified,
+    VerifiedCheckpoint(CheckpointOutput),
+    Error(String),
+}
+
+/// Messages are bincode encoded and prefixed with their length as a big-endian u64.
+async fn write_message<T: Serialize>(stream: &mut TcpStream, message: &T) -> Result<()> {
+    let bytes = bincode::serialize(message)?;
+    stream.write_u64(bytes.len() as u64).await?;
+    stream.write_all(&bytes).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+async fn read_message<T: DeserializeOwned>(stream: &mut TcpStream) -> Result<T> {
+    let len = stream.read_u64().await?;
+    ensure!(
+        len <= MAX_MESSAGE_SIZE,
+        "message of {} bytes exceeds the limit of {} bytes",
+        len,
+        MAX_MESSAGE_SIZE
+    );
+
+    let mut bytes = vec![0; len as usize];
+    stream.read_exact(&mut bytes).await?;
+    Ok(bincode::deserialize(&bytes)?)
+}
+
+/// [`RemoteBackend`] forwards proving and verification to an out-of-process
+/// [`ProvingServer`], using one connection per request.
+pub struct RemoteBackend {
+    address: String,
+}
+
+impl RemoteBackend {
+    pub fn new(address: impl Into<String>) -> Self {
+        RemoteBackend {
+            address: address.into(),
+        }
+    }
+
+    async fn call(&self, request: &Request) -> Result<Response> {
+        let mut stream = TcpStream::connect(&self.address)
+            .await
+            .with_context(|| format!("Failed to connect to proving server {}", self.address))?;
+        write_message(&mut stream, request).await?;
+        read_message(&mut stream).await.context("Failed to read response from proving server")
+    }
+}
+
+#[async_trait]
+impl EpochVerifier for RemoteBackend {
+    async fn verify(&self, proof: &SP1ProofWithPublicValues) -> Result<()> {
+        match self.call(&Request::Verify(proof.clone())).await? {
+            Response::Verified => Ok(()),
+            Response::Error(e) => bail!("proving server failed to verify proof: {}", e),
+            _ => bail!("unexpected response from proving server"),
+        }
+    }
+
+    async fn verify_checkpoint(
+        &self,
+        proof: &SP1ProofWithPublicValues,
+    ) -> Result<CheckpointOutput> {
+        match self.call(&Request::VerifyCheckpoint(proof.clone())).await? {
+            Response::VerifiedCheckpoint(output) => Ok(output),
+            Response::Error(e) => bail!("proving server failed to verify checkpoint: {}", e),
+            _ => bail!("unexpected response from proving server"),
+        }
+    }
+}
+
+#[async_trait]
+impl ProvingBackend for RemoteBackend {
+    async fn prove(&self, batch: &Batch) -> Result<SP1ProofWithPublicValues> {
+        match self.call(&Request::Prove(batch.clone())).await? {
+            Response::Proof(proof) => Ok(proof),
+            Response::Error(e) => bail!("proving server failed to prove batch: {}", e),
+            _ => bail!("unexpected response from proving server"),
+        }
+    }
+
+    async fn prove_checkpoint(
+        &self,
+        previous: Option<&SP1ProofWithPublicValues>,
+        epochs: &[SP1ProofWithPublicValues],
+    ) -> Result<SP1ProofWithPublicValues> {
+        let request = Request::ProveCheckpoint {
+            previous: previous.cloned(),
+            epochs: epochs.to_vec(),
+        };
+        match self.call(&request).await? {
+            Response::Proof(proof) => Ok(proof),
+            Response::Error(e) => bail!("proving server failed to prove checkpoint: {}", e),
+            _ => bail!("unexpected response from proving server"),
+        }
+    }
+}
+
+/// [`ProvingServer`] serves requests of [`RemoteBackend`]s with a local [`ProvingBackend`].
+pub struct ProvingServer {
+    backend: Arc<dyn ProvingBackend>,
+}
+
+impl ProvingServer {
+    pub fn new(backend: Arc<dyn ProvingBackend>) -> Self {
+        ProvingServer { backend }
+    }
+
+    pub async fn serve(self, listener: TcpListener) -> Result<()> {
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let backend = self.backend.clone();
+            tokio::spawn(async move {
+                if let Err(e) = handle_connection(backend, stream).await {
+                    warn!("proving server: request from {} failed: {}", peer, e);
+                }
+            });
+        }
+    }
+}
+
+async fn handle_connection(backend: Arc<dyn ProvingBackend>, mut stream: TcpStream) -> Result<()> {
+    let response = match read_message(&mut stream).await? {
+        Request::Prove(batch) => {
+            debug!(
+                "proving server: proving batch with {} proofs",
+                batch.proofs.len()
+            );
+            match backend.prove(&batch).await {
+                Ok(proof) => Response::Proof(proof),
+                Err(e) => Response::Error(e.to_string()),
+            }
+        }
+        Request::Verify(proof) => match backend.verify(&proof).await {
+            Ok(()) => Response::Verified,
+            Err(e) => Response::Error(e.to_string()),
+        },
+        Request::ProveCheckpoint { previous, epochs } => {
+            debug!(
+                "proving server: proving checkpoint over {} epochs",
+                epochs.len()
+            );
+            match backend.prove_checkpoint(previous.as_ref(), &epochs).await {
+                Ok(proof) => Response::Proof(proof),
+                Err(e) => Response::Error(e.to_string()),
+            }
+        }
+        Request::VerifyCheckpoint(proof) => match backend.verify_checkpoint(&proof).await {
+            Ok(output) => Response::VerifiedCheckpoint(output),
+            Err(e) => Response::Error(e.to_string()),
+        },
+    };
+    write_message(&mut stream, &response).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sp1Backend;
+    use prism_common::digest::Digest;
+
+    #[tokio::test]
+    async fn test_remote_backend_roundtrip() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        tokio::spawn(ProvingServer::new(Arc::new(Sp1Backend::mock())).serve(listener));
+
+        let backend = RemoteBackend::new(address);
+        let batch = Batch {
+            prev_root: Digest::zero(),
+            new_root: Digest::zero(),
+            proofs: Vec::new(),
+        };
+
+        let mut proof = backend.prove(&batch).await.unwrap();
+        backend.verify(&proof).await.unwrap();
+
+        let prev_commitment: Digest = proof.public_values.read();
+        assert_eq!(prev_commitment, Digest::zero());
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        drop(listener);
+
+        let batch = Batch {
+            prev_root: Digest::zero(),
+            new_root: Digest::zero(),
+            proofs: Vec::new(),
+        };
+        assert!(RemoteBackend::new(address).prove(&batch).await.is_err());
+    }
+}
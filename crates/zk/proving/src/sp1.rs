@@ -0,0 +1,180 @@
+use crate::{EpochVerifier, ProvingBackend, CHECKPOINT_ELF, PRISM_ELF};
+use anyhow::{bail, ensure, Result};
+use async_trait::async_trait;
+use prism_common::{
+    checkpoint::{CheckpointInput, CheckpointOutput},
+    tree::Batch,
+};
+use sp1_sdk::{
+    HashableKey, ProverClient, SP1Proof, SP1ProofWithPublicValues, SP1ProvingKey, SP1Stdin,
+    SP1VerifyingKey,
+};
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+
+#[derive(Clone, Copy)]
+enum EpochProofKind {
+    Core,
+    Compressed,
+    Groth16,
+}
+
+/// [`Sp1Backend`] proves epochs by running the prism program in SP1.
+pub struct Sp1Backend {
+    client: Arc<ProverClient>,
+    proving_key: Arc<SP1ProvingKey>,
+    verifying_key: SP1VerifyingKey,
+    epoch_proof_kind: EpochProofKind,
+    checkpoint_keys: OnceCell<(Arc<SP1ProvingKey>, SP1VerifyingKey)>,
+}
+
+impl Sp1Backend {
+    /// Generates real proofs locally, wrapped in a groth16 proof if `groth16` is set.
+    /// Otherwise epoch proofs are compressed, so they can be aggregated into checkpoints.
+    pub fn local(groth16: bool) -> Self {
+        let epoch_proof_kind = if groth16 {
+            EpochProofKind::Groth16
+        } else {
+            EpochProofKind::Compressed
+        };
+        Self::new(ProverClient::local(), epoch_proof_kind)
+    }
+
+    /// Executes the program without generating real proofs, for development and tests.
+    pub fn mock() -> Self {
+        Self::new(ProverClient::mock(), EpochProofKind::Core)
+    }
+
+    fn new(client: ProverClient, epoch_proof_kind: EpochProofKind) -> Self {
+        let (proving_key, verifying_key) = client.setup(PRISM_ELF);
+        Sp1Backend {
+            client: Arc::new(client),
+            proving_key: Arc::new(proving_key),
+            verifying_key,
+            epoch_proof_kind,
+            checkpoint_keys: OnceCell::new(),
+        }
+    }
+
+    pub fn verifying_key(&self) -> &SP1VerifyingKey {
+        &self.verifying_key
+    }
+
+    async fn checkpoint_keys(&self) -> Result<&(Arc<SP1ProvingKey>, SP1VerifyingKey)> {
+        self.checkpoint_keys
+            .get_or_try_init(|| async {
+                ensure!(
+                    !CHECKPOINT_ELF.is_empty(),
+                    "Built without the checkpoint program, build it with `just build`"
+                );
+                // setting up the keys takes seconds, so it must not block the async runtime
+                let client = self.client.clone();
+                let (proving_key, verifying_key) =
+                    tokio::task::spawn_blocking(move || client.setup(CHECKPOINT_ELF)).await?;
+                Ok((Arc::new(proving_key), verifying_key))
+            })
+            .await
+    }
+}
+
+/// Passes `proof` to the checkpoint program, which can only verify compressed proofs.
+fn write_proof(
+    stdin: &mut SP1Stdin,
+    proof: &SP1ProofWithPublicValues,
+    verifying_key: &SP1VerifyingKey,
+) -> Result<()> {
+    match &proof.proof {
+        SP1Proof::Compressed(reduce_proof) => {
+            stdin.write_proof(*reduce_proof.clone(), verifying_key.vk.clone());
+            Ok(())
+        }
+        _ => bail!("only compressed proofs can be aggregated into a checkpoint"),
+    }
+}
+
+#[async_trait]
+impl EpochVerifier for Sp1Backend {
+    async fn verify(&self, proof: &SP1ProofWithPublicValues) -> Result<()> {
+        // verification runs on the blocking pool, so light clients can verify many epochs
+        // in parallel while catching up
+        let client = self.client.clone();
+        let verifying_key = self.verifying_key.clone();
+        let proof = proof.clone();
+        tokio::task::spawn_blocking(move || client.verify(&proof, &verifying_key)).await??;
+        Ok(())
+    }
+
+    async fn verify_checkpoint(
+        &self,
+        proof: &SP1ProofWithPublicValues,
+    ) -> Result<CheckpointOutput> {
+        let (_, checkpoint_vk) = self.checkpoint_keys().await?;
+        let client = self.client.clone();
+        let verifying_key = checkpoint_vk.clone();
+        let checkpoint = proof.clone();
+        tokio::task::spawn_blocking(move || client.verify(&checkpoint, &verifying_key)).await??;
+
+        let output: CheckpointOutput = proof.public_values.clone().read();
+        ensure!(
+            output.epoch_vkey == self.verifying_key.hash_u32(),
+            "checkpoint aggregates proofs of a different epoch program"
+        );
+        ensure!(
+            output.checkpoint_vkey == checkpoint_vk.hash_u32(),
+            "checkpoint builds on checkpoints of a different program"
+        );
+        Ok(output)
+    }
+}
+
+#[async_trait]
+impl ProvingBackend for Sp1Backend {
+    async fn prove(&self, batch: &Batch) -> Result<SP1ProofWithPublicValues> {
+        let mut stdin = SP1Stdin::new();
+        stdin.write(batch);
+
+        // proving takes minutes on real hardware, so it must not block the async runtime
+        let client = self.client.clone();
+        let proving_key = self.proving_key.clone();
+        let epoch_proof_kind = self.epoch_proof_kind;
+        tokio::task::spawn_blocking(move || {
+            let prove = client.prove(&proving_key, stdin);
+            match epoch_proof_kind {
+                EpochProofKind::Core => prove.run(),
+                EpochProofKind::Compressed => prove.compressed().run(),
+                EpochProofKind::Groth16 => prove.groth16().run(),
+            }
+        })
+        .await?
+    }
+
+    async fn prove_checkpoint(
+        &self,
+        previous: Option<&SP1ProofWithPublicValues>,
+        epochs: &[SP1ProofWithPublicValues],
+    ) -> Result<SP1ProofWithPublicValues> {
+        let (checkpoint_pk, checkpoint_vk) = self.checkpoint_keys().await?;
+
+        let input = CheckpointInput {
+            epoch_vkey: self.verifying_key.hash_u32(),
+            checkpoint_vkey: checkpoint_vk.hash_u32(),
+            previous: previous.map(|proof| proof.public_values.clone().read()),
+            epoch_public_values: epochs.iter().map(|proof| proof.public_values.to_vec()).collect(),
+        };
+
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&input);
+        if let Some(previous) = previous {
+            write_proof(&mut stdin, previous, checkpoint_vk)?;
+        }
+        for epoch in epochs {
+            write_proof(&mut stdin, epoch, &self.verifying_key)?;
+        }
+
+        // the checkpoint itself stays compressed, so the next checkpoint can verify it
+        let client = self.client.clone();
+        let proving_key = checkpoint_pk.clone();
+        tokio::task::spawn_blocking(move || client.prove(&proving_key, stdin).compressed().run())
+            .await?
+    }
+}